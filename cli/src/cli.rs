@@ -4,13 +4,23 @@
 //! It handles the mapping between user input and the internal data structures
 //! used by the rendering and indexing engines.
 
+use crate::config::Config;
+use crate::output::OutputFormat;
+use crate::render::{
+    CellAspect, build_render_options, parse_crop, resolve_charset, resolve_color_mode,
+    resolve_palette_mode,
+};
 use crate::rotate::RotateAxis;
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 use clap_complete::aot::Shell;
 
 use std::path::PathBuf;
 
-use px2ansi::{ColorMode, Density, RasterTheme, RenderStylePreset, ResizeFilter};
+use anyhow::Result;
+use px2ansi::{
+    Adjustments, Ansi16Palette, ColorMode, Density, DitherMode, FitMode, RasterTheme,
+    RenderOptions, RenderStylePreset, ResizeFilter, ResizeSpace, ToneMap,
+};
 
 #[derive(Parser)]
 #[command(
@@ -49,41 +59,46 @@ pub enum Commands {
         #[arg(short = 'O', long = "output-image")]
         output_image: Option<PathBuf>,
 
-        #[arg(long, value_enum)]
-        style: Option<RenderStylePreset>,
+        /// Output format: ansi (default), html for a `<pre>` block that keeps
+        /// the art copy-pasteable, or svg for a resolution-independent image.
+        /// Inferred from an -o path ending in .html or .svg
+        #[arg(long, value_enum, value_name = "FORMAT")]
+        format: Option<OutputFormat>,
 
-        /// Character density for --style ascii and --style fade.
-        /// Light: sparse 30-char ramp, Medium: full 92-char ramp, Heavy: block-heavy ramp.
-        #[arg(long, value_enum)]
-        density: Option<Density>,
+        /// Colour HTML spans with classes from a compact CSS palette instead
+        /// of inline styles
+        #[arg(long)]
+        html_classes: bool,
 
-        /// Enable Floyd-Steinberg dithering for smoother gradients
-        #[arg(short, long)]
-        dither: bool,
+        /// Write a complete HTML page with the --raster-theme background
+        #[arg(long)]
+        html_standalone: bool,
 
-        #[arg(
-            long = "color-mode",
-            value_enum,
-            help = "Color output mode: truecolor, ansi256, or none",
-            long_help = "Override automatic terminal color detection. --no-color takes precedence."
-        )]
-        color_mode: Option<ColorMode>,
+        /// CSS font-family list for HTML output
+        #[arg(long, value_name = "FAMILY")]
+        html_font: Option<String>,
+
+        /// Font size of HTML output in CSS pixels (default: 14)
+        #[arg(long, value_name = "PX")]
+        html_font_size: Option<f32>,
 
-        /// Force a specific width
+        /// Merge adjacent same-colour SVG rectangles into one path per colour
         #[arg(long)]
-        width: Option<u32>,
+        svg_merge: bool,
 
-        /// Resize filter
-        #[arg(
-            short,
-            long,
-            value_enum,
-            help = "The resampling filter to use",
-            long_help = "Nearest is best for pixel art. Lanczos3 is best for high-resolution images."
-        )]
-        filter: Option<ResizeFilter>,
+        /// Embed the bundled Iosevka font in SVG output (about 2 MB)
+        #[arg(long)]
+        svg_embed_font: bool,
+
+        #[command(flatten)]
+        render: RenderArgs,
+
+        /// Force a specific width, in terminal cells
+        #[arg(long)]
+        width: Option<u32>,
 
-        /// Background theme for rasterized PNG output
+        /// Background theme for rasterized PNG and SVG output and standalone
+        /// HTML pages
         #[arg(long, value_enum, default_value = "tokyo-night")]
         raster_theme: Option<RasterTheme>,
 
@@ -122,10 +137,6 @@ pub enum Commands {
         /// Pairs with --rotate to spin the image while info stays pinned to the right.
         #[arg(long, default_value = "false")]
         fetch: bool,
-
-        /// Query the terminal background color and composite transparent pixels against it (sixel only)
-        #[arg(long, default_value = "false")]
-        composite_bg: bool,
     },
     /// Create a JSON index of a directory
     Index {
@@ -141,26 +152,8 @@ pub enum Commands {
         #[arg(default_value = "random")]
         name: String,
 
-        #[arg(long, value_enum)]
-        style: Option<RenderStylePreset>,
-
-        /// Enable Floyd-Steinberg dithering for smoother gradients
-        #[arg(short, long)]
-        dither: bool,
-
-        #[arg(
-            long = "color-mode",
-            value_enum,
-            help = "Color output mode: truecolor, ansi256, or none",
-            long_help = "Override automatic terminal color detection. --no-color takes precedence."
-        )]
-        color_mode: Option<ColorMode>,
-
-        #[arg(long, value_enum)]
-        density: Option<Density>,
-
-        #[arg(short, long, value_enum)]
-        filter: Option<ResizeFilter>,
+        #[command(flatten)]
+        render: RenderArgs,
 
         #[arg(short = 'i', long)]
         interactive: bool,
@@ -200,10 +193,6 @@ pub enum Commands {
         /// Pairs with --rotate to spin the image while info stays pinned to the right.
         #[arg(long, default_value = "false")]
         fetch: bool,
-
-        /// Query the terminal background color and composite transparent pixels against it (sixel only)
-        #[arg(long, default_value = "false")]
-        composite_bg: bool,
     },
     /// List entries in the index
     List {
@@ -219,3 +208,199 @@ pub enum Commands {
         shell: Shell,
     },
 }
+
+/// Rendering flags shared by `convert` and `show`.
+#[derive(Args, Debug)]
+#[expect(
+    clippy::struct_excessive_bools,
+    reason = "Each bool is an independent command-line switch"
+)]
+pub struct RenderArgs {
+    #[arg(long, value_enum)]
+    pub style: Option<RenderStylePreset>,
+
+    /// Character density for --style ascii and --style fade.
+    /// Light: sparse 30-char ramp, Medium: full 92-char ramp, Heavy: block-heavy ramp.
+    #[arg(long, value_enum)]
+    pub density: Option<Density>,
+
+    /// How luma is spread over glyph ramps (ascii, fade, kanji, chinese,
+    /// --charset): linear (default), percentile[:N] to ignore the darkest
+    /// and brightest N% (default 1), equalize, or clahe for local contrast
+    #[arg(long, value_name = "MODE")]
+    pub tone_map: Option<ToneMap>,
+
+    /// Custom glyph ramp, darkest first (e.g. " .:-=+*#%@"), or the name of a
+    /// ramp defined under [charsets] in the config file. Overrides --style.
+    #[arg(long, value_name = "RAMP")]
+    pub charset: Option<String>,
+
+    /// Treat --charset glyphs as double-width (e.g. CJK characters)
+    #[arg(long, requires = "charset")]
+    pub wide: bool,
+
+    /// Dither against the output palette: floyd-steinberg (default), atkinson,
+    /// sierra, stucki, jarvis, bayer2, bayer4, bayer8 or blue-noise
+    #[arg(
+        short,
+        long,
+        value_name = "MODE",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "floyd-steinberg"
+    )]
+    pub dither: Option<DitherMode>,
+
+    /// In 256-colour, 16-colour and grayscale modes, diffuse colour error
+    /// across neighbouring cells (in Oklab) instead of snapping each cell
+    /// to the nearest palette colour
+    #[arg(long)]
+    pub palette_dither: bool,
+
+    /// Use at most N distinct colours in text modes (median cut in Oklab),
+    /// so fewer colour escapes are needed and the output shrinks
+    #[arg(long, value_name = "N")]
+    pub max_colors: Option<u16>,
+
+    /// Multiply brightness by FACTOR after resizing (1.0 = unchanged)
+    #[arg(long, value_name = "FACTOR", default_value_t = 1.0)]
+    pub brightness: f32,
+
+    /// Scale contrast around mid-grey by FACTOR (0.0 = flat grey)
+    #[arg(long, value_name = "FACTOR", default_value_t = 1.0)]
+    pub contrast: f32,
+
+    /// Gamma correction; values above 1.0 brighten the midtones
+    #[arg(long, value_name = "GAMMA", default_value_t = 1.0)]
+    pub gamma: f32,
+
+    /// Scale saturation by FACTOR (0.0 = grayscale)
+    #[arg(long, value_name = "FACTOR", default_value_t = 1.0)]
+    pub saturation: f32,
+
+    /// Rotate hue by DEGREES
+    #[arg(
+        long,
+        value_name = "DEGREES",
+        default_value_t = 0.0,
+        allow_negative_numbers = true
+    )]
+    pub hue: f32,
+
+    /// Invert the colours of the image
+    #[arg(long)]
+    pub invert: bool,
+
+    #[arg(
+        long = "color-mode",
+        value_enum,
+        help = "Color output mode: truecolor, ansi256, ansi16, grayscale, or none",
+        long_help = "Override automatic terminal color detection. --no-color takes precedence."
+    )]
+    pub color_mode: Option<ColorMode>,
+
+    /// Colours to match against in ansi16 mode: xterm, vga, tokyo-night,
+    /// dracula, nord, gruvbox-dark, one-dark, solarized-dark, or 16
+    /// comma-separated #rrggbb values. Implies --color-mode ansi16
+    #[arg(long, value_name = "PALETTE")]
+    pub ansi16_palette: Option<Ansi16Palette>,
+
+    /// Lock output to a fixed palette: cga, ega, game-boy, pico-8, nes, a
+    /// GIMP .gpl or hex-list file, or comma-separated #rrggbb colours.
+    /// Keeps truecolor or 256-colour emission from --color-mode
+    #[arg(long, value_name = "PALETTE")]
+    pub palette: Option<String>,
+
+    /// Force a specific height, in terminal cells
+    #[arg(long)]
+    pub height: Option<u32>,
+
+    /// How the image fills the --width/--height box: contain (default)
+    /// fits inside it, cover fills it and crops the overflow, stretch
+    /// distorts to fill it, native keeps one source pixel per pixel
+    #[arg(long, value_enum, value_name = "MODE")]
+    pub fit: Option<FitMode>,
+
+    /// Height-to-width ratio of a terminal cell (default 2.0), or auto to
+    /// ask the terminal. Fixes stretched output with narrow or wide fonts
+    #[arg(long, value_name = "RATIO")]
+    pub cell_aspect: Option<CellAspect>,
+
+    /// Render only the X,Y,W,H region of the source image, in pixels
+    #[arg(long, value_name = "X,Y,W,H", value_parser = parse_crop)]
+    pub crop: Option<(u32, u32, u32, u32)>,
+
+    /// Trim fully transparent or uniform-colour borders before sizing
+    #[arg(long)]
+    pub trim: bool,
+
+    /// Resize filter
+    #[arg(
+        short,
+        long,
+        value_enum,
+        help = "The resampling filter to use",
+        long_help = "Nearest is best for pixel art at its own size. Pixel-art detects sprites upscaled by a whole factor and scales their native pixels by whole numbers only. Scale2x (epx), scale3x and xbr enlarge tiny sprites with smooth diagonals. Lanczos3 is best for high-resolution images."
+    )]
+    pub filter: Option<ResizeFilter>,
+
+    /// Colour space to resample in: srgb (default), linear to keep
+    /// downscaled photos from darkening, or linear-premultiplied to also
+    /// keep sprite edges free of dark fringes
+    #[arg(long, value_enum, value_name = "SPACE")]
+    pub resize_space: Option<ResizeSpace>,
+
+    /// Query the terminal background color and composite transparent pixels against it (sixel only)
+    #[arg(long, default_value = "false")]
+    pub composite_bg: bool,
+}
+
+impl RenderArgs {
+    /// Builds the [`RenderOptions`] these flags describe at `width` cells,
+    /// taking palettes and named charsets from `cfg` where the flags refer to
+    /// them.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a palette cannot be parsed or loaded, or if
+    /// `--charset` is not a valid glyph ramp.
+    pub fn apply(self, width: Option<u32>, cfg: &Config) -> Result<RenderOptions> {
+        let color_mode = resolve_color_mode(self.color_mode, self.ansi16_palette, cfg)
+            .map_err(anyhow::Error::msg)?;
+        let color_mode = resolve_palette_mode(color_mode, self.palette.as_deref(), cfg)?;
+        let opts = build_render_options(
+            self.style,
+            self.density,
+            width,
+            self.filter,
+            color_mode,
+            self.dither,
+            self.composite_bg,
+        );
+        let opts = match self.charset.as_deref() {
+            Some(spec) => opts.with_charset(resolve_charset(spec, self.wide, cfg)?),
+            None => opts,
+        }
+        .with_palette_dither(self.palette_dither)
+        .with_text_max_colors(self.max_colors)
+        .with_tone_map(self.tone_map.unwrap_or_default())
+        .with_resize_space(self.resize_space.unwrap_or_default())
+        .with_fit(self.fit.unwrap_or_default())
+        .with_crop(self.crop)
+        .with_auto_trim(self.trim)
+        .with_adjustments(
+            Adjustments::default()
+                .brightness(self.brightness)
+                .contrast(self.contrast)
+                .gamma(self.gamma)
+                .saturation(self.saturation)
+                .hue_rotate(self.hue)
+                .invert(self.invert),
+        );
+        let opts = self.height.map_or(opts, |h| opts.with_height(h));
+        Ok(self
+            .cell_aspect
+            .and_then(CellAspect::resolve)
+            .map_or(opts, |ratio| opts.with_cell_aspect(ratio)))
+    }
+}
@@ -1,10 +1,19 @@
 use crate::fetch::print_fetch_with_image;
 use crate::rotate::{RotateMode, apply_static, run_spin_fetch_loop, run_spin_loop};
 use anyhow::Result;
-use px2ansi::{RasterTheme, RenderOptions};
+use px2ansi::{HtmlEncoder, RasterTheme, RenderOptions, SvgEncoder};
 use std::io::Write;
 use std::path::PathBuf;
 
+/// A format `convert` writes instead of ANSI text.
+#[derive(Debug)]
+pub enum Export {
+    /// An HTML `<pre>` block or page.
+    Html(HtmlEncoder),
+    /// An SVG image.
+    Svg(SvgEncoder),
+}
+
 /// Parameters for converting a single image file to ANSI art
 #[derive(Debug)]
 pub struct ConvertCmd {
@@ -14,6 +23,8 @@ pub struct ConvertCmd {
     pub output: Option<PathBuf>,
     /// Optional path to save a PNG rasterization of the result.
     pub output_image: Option<PathBuf>,
+    /// Writes HTML or SVG instead of ANSI text when set.
+    pub export: Option<Export>,
     /// Visual settings (width, filter, style).
     pub render: RenderOptions,
     pub raster_theme: RasterTheme,
@@ -32,6 +43,8 @@ impl ConvertCmd {
     /// the command logic encounters a processing error.
     // pub fn run<W: Write>(&self, external_writer: &mut W) -> Result<()> {
     pub fn run(&self, external_writer: &mut dyn Write) -> Result<()> {
+        self.check_export_layout()?;
+
         // 1. Load and decode
         let img = image::ImageReader::open(&self.input)?.decode()?;
 
@@ -117,7 +130,16 @@ impl ConvertCmd {
             .map(|path| std::fs::File::create(path).map(std::io::BufWriter::new))
             .transpose()?;
 
-        // 4. Render and Rasterize logic
+        // 4. HTML and SVG exports are documents, so they have no centering padding.
+        if let Some(export) = self.export.as_ref() {
+            let target: &mut dyn Write = match file_writer.as_mut() {
+                Some(fw) => fw,
+                None => external_writer,
+            };
+            return self.write_export(&img, export, target);
+        }
+
+        // 5. Render and Rasterize logic
         #[cfg(feature = "rasterize")]
         if let Some(png_path) = self.output_image.as_ref() {
             // Buffer is required for PNG rasterization
@@ -165,4 +187,38 @@ impl ConvertCmd {
 
         Ok(())
     }
+
+    /// HTML and SVG are single documents, which the animated and fetch
+    /// layouts can't produce.
+    fn check_export_layout(&self) -> Result<()> {
+        if self.export.is_none() {
+            return Ok(());
+        }
+        if matches!(self.rotate, Some(RotateMode::Spin { .. })) {
+            anyhow::bail!("--rotate spin cannot be combined with HTML or SVG output");
+        }
+        if self.fetch {
+            anyhow::bail!("--fetch cannot be combined with HTML or SVG output");
+        }
+        Ok(())
+    }
+
+    /// Writes the image as HTML or SVG, which have no PNG counterpart.
+    fn write_export(
+        &self,
+        img: &image::DynamicImage,
+        export: &Export,
+        mut target: &mut dyn Write,
+    ) -> Result<()> {
+        if self.output_image.is_some() {
+            anyhow::bail!("--output-image cannot be combined with HTML or SVG output");
+        }
+        let prepared = self.render.prepare_image(img);
+        match export {
+            Export::Html(html) => px2ansi::write_html(&prepared, &mut target, self.render, html)?,
+            Export::Svg(svg) => px2ansi::write_svg(&prepared, &mut target, self.render, svg)?,
+        }
+        target.flush()?;
+        Ok(())
+    }
 }
@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 
 use px2ansi::{RasterTheme, RenderStylePreset, ResizeFilter};
@@ -28,6 +30,34 @@ pub struct Config {
     pub output_image: Option<String>,
 
     pub raster_theme: RasterTheme,
+
+    /// The 16-colour palette `ansi16` mode matches against: a preset name
+    /// such as `"dracula"`, or 16 comma-separated `#rrggbb` colours. Set it
+    /// to your terminal theme.
+    pub ansi16_palette: Option<String>,
+
+    /// A fixed palette to lock output to unless `--color-mode` is given: a
+    /// preset name such as `"pico-8"`, or a path to a `.gpl` or hex-list file.
+    pub palette: Option<String>,
+
+    /// Named glyph ramps usable with `--charset <name>`.
+    ///
+    /// ```toml
+    /// [charsets.blocks]
+    /// glyphs = " ░▒▓█"
+    /// ```
+    pub charsets: BTreeMap<String, NamedCharset>,
+}
+
+/// A glyph ramp stored in the config file, darkest glyph first.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct NamedCharset {
+    /// The glyphs, one per character.
+    pub glyphs: String,
+
+    /// Whether each glyph occupies two terminal columns.
+    #[serde(default)]
+    pub wide: bool,
 }
 
 impl Default for Config {
@@ -40,6 +70,9 @@ impl Default for Config {
             style: RenderStylePreset::Ansi,
             output_image: None,
             raster_theme: RasterTheme::default(),
+            ansi16_palette: None,
+            palette: None,
+            charsets: BTreeMap::new(),
         }
     }
 }
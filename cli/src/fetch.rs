@@ -3,6 +3,7 @@ use ansi_width::ansi_width;
 use anyhow::Result;
 use colored::Colorize;
 use image::{DynamicImage, imageops::FilterType};
+use px2ansi::render::{CELL_PX_H, CELL_PX_W};
 use px2ansi::{CharsetMode, RenderOptions};
 use std::env;
 use std::fs;
@@ -455,40 +456,6 @@ fn truncate_ansi(s: &str, max_cols: usize) -> String {
 // Image rendering
 // ---------------------------------------------------------------------------
 
-fn term_cell_px_w() -> u32 {
-    #[cfg(unix)]
-    {
-        use std::os::unix::io::AsRawFd;
-        let fds = [std::io::stderr().as_raw_fd(), std::io::stdout().as_raw_fd()];
-        for fd in fds {
-            let mut ws: libc::winsize = unsafe { std::mem::zeroed() };
-            if unsafe { libc::ioctl(fd, libc::TIOCGWINSZ, &mut ws) } == 0
-                && ws.ws_row > 0
-                && ws.ws_xpixel > 0
-            {
-                return (u32::from(ws.ws_xpixel) / u32::from(ws.ws_col)).max(1);
-            }
-        }
-    }
-    10
-}
-fn term_cell_px_h() -> u32 {
-    #[cfg(unix)]
-    {
-        use std::os::unix::io::AsRawFd;
-        let fds = [std::io::stderr().as_raw_fd(), std::io::stdout().as_raw_fd()];
-        for fd in fds {
-            let mut ws: libc::winsize = unsafe { std::mem::zeroed() };
-            if unsafe { libc::ioctl(fd, libc::TIOCGWINSZ, &mut ws) } == 0
-                && ws.ws_row > 0
-                && ws.ws_ypixel > 0
-            {
-                return (u32::from(ws.ws_ypixel) / u32::from(ws.ws_row)).max(1);
-            }
-        }
-    }
-    20
-}
 ///  Render or IO failure.
 /// # Errors
 // pub fn print_fetch_with_image<W: Write>(
@@ -503,12 +470,20 @@ pub fn print_fetch_with_image(
 
     let max_img_cols = u32::try_from(cols.saturating_sub(38).max(20)).unwrap_or(20);
 
+    let img = &*render.crop_image(img);
     let (orig_w, orig_h) = (img.width(), img.height());
 
-    if matches!(render.charset(), CharsetMode::Sixel) {
+    if matches!(
+        render.charset(),
+        CharsetMode::Sixel | CharsetMode::Kitty | CharsetMode::Iterm2
+    ) {
         let target_char_rows: u32 = 30;
-        let cell_px_h = term_cell_px_h();
-        let cell_px_w = term_cell_px_w();
+        // Kitty and iTerm2 place the image in cells of their own fixed size,
+        // so the box reserved here has to be measured in the same units.
+        let (cell_px_w, cell_px_h) = match render.charset() {
+            CharsetMode::Kitty | CharsetMode::Iterm2 => (CELL_PX_W, CELL_PX_H),
+            _ => px2ansi::detect_cell_size().unwrap_or((10, 20)),
+        };
         let target_px_h = target_char_rows * cell_px_h;
         let scale_h = f64::from(target_px_h) / f64::from(orig_h);
         let scale_w = f64::from(max_img_cols * cell_px_w) / f64::from(orig_w);
@@ -565,7 +540,11 @@ pub fn print_fetch_with_image(
     let mut img_buf = Vec::new();
 
     let img_cols = match render.charset() {
-        CharsetMode::Ascii | CharsetMode::Chinese | CharsetMode::Kanji => {
+        CharsetMode::Ascii
+        | CharsetMode::Edges
+        | CharsetMode::Chinese
+        | CharsetMode::Kanji
+        | CharsetMode::Custom(_) => {
             let target_cols = 50_u32.min(max_img_cols);
 
             let aspect = f64::from(orig_h) / f64::from(orig_w);
@@ -601,7 +580,8 @@ pub fn print_fetch_with_image(
 
             render.render(&img_to_render, &mut img_buf)?;
 
-            tw as usize
+            // Sub-cell modes draw several pixels per column.
+            render.rendered_cols(tw) as usize
         }
     };
 
@@ -16,8 +16,8 @@ pub mod terminal;
 pub use crate::commands::Command;
 pub use crate::render::build_render_options;
 pub use crate::rotate::{RotateAxis, RotateMode};
-pub use cli::{Cli, Commands};
-pub use commands::convert::ConvertCmd;
+pub use cli::{Cli, Commands, RenderArgs};
+pub use commands::convert::{ConvertCmd, Export};
 pub use commands::index::IndexCmd;
 pub use commands::list::ListCmd;
 pub use commands::show::ShowCmd;
@@ -49,7 +49,10 @@ mod tests {
     use super::*;
     use crate::config::Config;
     use clap::Parser;
-    use px2ansi::{CharsetMode, Density, RenderStylePreset, ResizeFilter};
+    use px2ansi::{
+        Adjustments, Ansi16Palette, CharsetMode, ColorMode, ColorPalette, Density, FitMode,
+        RenderStylePreset, ResizeFilter, ResizeSpace, ToneMap,
+    };
     use std::path::PathBuf;
 
     type TestResult = Result<(), Box<dyn std::error::Error>>;
@@ -121,7 +124,7 @@ mod tests {
             Some(80),
             None,
             Some(px2ansi::ColorMode::None),
-            false,
+            None,
             false,
         );
         assert_eq!(opts.width(), Some(80));
@@ -132,7 +135,7 @@ mod tests {
             None,
             None,
             Some(px2ansi::ColorMode::None),
-            false,
+            None,
             false,
         );
         assert_eq!(opts2.width(), None);
@@ -147,7 +150,7 @@ mod tests {
             None,
             None,
             Some(px2ansi::ColorMode::None),
-            false,
+            None,
             false,
         );
         assert_eq!(opts.charset(), CharsetMode::Braille);
@@ -160,7 +163,7 @@ mod tests {
             None,
             None,
             None,
-            false,
+            None,
             false,
         );
         assert_eq!(opts.charset(), CharsetMode::Unicode);
@@ -175,7 +178,7 @@ mod tests {
             None,
             None,
             None,
-            false,
+            None,
             false,
         );
         assert!(matches!(opts.style().density(), Density::Heavy));
@@ -190,7 +193,7 @@ mod tests {
             None,
             None,
             None,
-            false,
+            None,
             false,
         );
         assert!(matches!(opts.style().density(), Density::Light));
@@ -204,7 +207,7 @@ mod tests {
             None,
             Some(ResizeFilter::Nearest),
             None,
-            false,
+            None,
             false,
         );
         assert_eq!(opts.filter(), image::imageops::FilterType::Nearest);
@@ -230,14 +233,7 @@ mod tests {
     //         "out.txt",
     //     ]);
 
-    //     let Commands::Convert {
-    //         input,
-    //         style,
-    //         width,
-    //         filter,
-    //         color_mode,
-    //         output,
-    //         ..
+    //     let Commands::Convert { //         input, //         style, //         width, //         filter, //         color_mode, //         output, //         ..
     //     } = cli.command
     //     else {
     //         unreachable!("Cli::parse_from should have produced Commands::Convert");
@@ -257,19 +253,533 @@ mod tests {
 
         let Commands::Show {
             name,
-            style,
             interactive,
+            render,
             ..
         } = cli.command
         else {
             unreachable!("Cli::parse_from should have produced Commands::Show");
         };
+        let RenderArgs { style, .. } = render;
 
         assert_eq!(name, "pikachu");
         assert_eq!(style, Some(RenderStylePreset::Ascii));
         assert!(interactive);
     }
 
+    #[test]
+    fn cli_parses_custom_charset_and_resolves_named_ramps() -> TestResult {
+        let cli = Cli::parse_from(["px2ansi-rs", "show", "--charset", "blocks", "--wide"]);
+        let Commands::Show { render, .. } = cli.command else {
+            unreachable!("Cli::parse_from should have produced Commands::Show");
+        };
+        let RenderArgs { charset, wide, .. } = render;
+        assert_eq!(charset.as_deref(), Some("blocks"));
+        assert!(wide);
+
+        let mut cfg = Config::default();
+        cfg.charsets.insert(
+            "blocks".into(),
+            crate::config::NamedCharset {
+                glyphs: " ░▒▓█".into(),
+                wide: false,
+            },
+        );
+        let named = render::resolve_charset("blocks", false, &cfg)?;
+        assert_eq!(named.glyphs(), Some(&[" ", "░", "▒", "▓", "█"][..]));
+
+        let literal = render::resolve_charset(" .#", false, &cfg)?;
+        assert_eq!(literal.glyphs(), Some(&[" ", ".", "#"][..]));
+        Ok(())
+    }
+
+    #[test]
+    fn cli_parses_dither_with_optional_mode() {
+        let dither = |args: &[&str]| {
+            let cli = Cli::parse_from(["px2ansi-rs", "show", "pikachu"].iter().chain(args));
+            let Commands::Show { render, .. } = cli.command else {
+                unreachable!("Cli::parse_from should have produced Commands::Show");
+            };
+            let RenderArgs { dither, .. } = render;
+            dither
+        };
+        assert_eq!(dither(&[]), None);
+        assert_eq!(dither(&["-d"]), Some(px2ansi::DitherMode::FloydSteinberg));
+        assert_eq!(
+            dither(&["--dither=blue-noise"]),
+            Some(px2ansi::DitherMode::BlueNoise)
+        );
+
+        let opts = build_render_options(
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some(px2ansi::DitherMode::Bayer8),
+            false,
+        );
+        assert_eq!(opts.style().dither(), px2ansi::DitherMode::Bayer8);
+    }
+
+    #[test]
+    fn cli_parses_palette_dither() {
+        let cli = Cli::parse_from(["px2ansi-rs", "show", "--palette-dither"]);
+        let Commands::Show { render, .. } = cli.command else {
+            unreachable!("Cli::parse_from should have produced Commands::Show");
+        };
+        let RenderArgs { palette_dither, .. } = render;
+        assert!(palette_dither);
+        let opts = RenderOptions::default().with_palette_dither(palette_dither);
+        assert!(opts.palette_dither());
+    }
+
+    #[test]
+    fn cli_parses_ansi16_palette_and_new_color_modes() {
+        let cli = Cli::parse_from([
+            "px2ansi-rs",
+            "convert",
+            "in.png",
+            "--ansi16-palette",
+            "dracula",
+        ]);
+        let Commands::Convert { render, .. } = cli.command else {
+            unreachable!("Cli::parse_from should have produced Commands::Convert");
+        };
+        let RenderArgs {
+            color_mode,
+            ansi16_palette,
+            ..
+        } = render;
+        let mode = render::resolve_color_mode(color_mode, ansi16_palette, &Config::default());
+        assert_eq!(mode, Ok(Some(ColorMode::Ansi16(Ansi16Palette::DRACULA))));
+
+        let cli = Cli::parse_from(["px2ansi-rs", "show", "--color-mode", "grayscale"]);
+        let Commands::Show { render, .. } = cli.command else {
+            unreachable!("Cli::parse_from should have produced Commands::Show");
+        };
+        let RenderArgs { color_mode, .. } = render;
+        assert_eq!(color_mode, Some(ColorMode::Grayscale));
+
+        let cfg = Config {
+            ansi16_palette: Some("nord".into()),
+            ..Config::default()
+        };
+        assert_eq!(
+            render::resolve_color_mode(Some(ColorMode::Ansi16(Ansi16Palette::XTERM)), None, &cfg),
+            Ok(Some(ColorMode::Ansi16(Ansi16Palette::NORD)))
+        );
+        // A configured palette alone doesn't override colour detection.
+        assert_eq!(render::resolve_color_mode(None, None, &cfg), Ok(None));
+        let bad = Config {
+            ansi16_palette: Some("#fff".into()),
+            ..Config::default()
+        };
+        let err = render::resolve_color_mode(None, None, &bad).expect_err("short hex list");
+        assert!(err.contains("16-colour palette"), "{err}");
+    }
+
+    #[test]
+    fn cli_palette_keeps_the_emission_of_the_color_mode() {
+        let cli = Cli::parse_from([
+            "px2ansi-rs",
+            "show",
+            "--palette",
+            "gameboy",
+            "--color-mode",
+            "256",
+        ]);
+        let Commands::Show { render, .. } = cli.command else {
+            unreachable!("Cli::parse_from should have produced Commands::Show");
+        };
+        let RenderArgs {
+            color_mode,
+            palette,
+            ..
+        } = render;
+        let cfg = Config::default();
+        let mode = render::resolve_palette_mode(color_mode, palette.as_deref(), &cfg)
+            .expect("preset palette");
+        assert_eq!(
+            mode,
+            Some(ColorMode::Palette {
+                palette: ColorPalette::GAME_BOY,
+                ansi256: true,
+            })
+        );
+
+        let cfg = Config {
+            palette: Some("#102030, #405060".into()),
+            ..Config::default()
+        };
+        // An explicit mode beats a palette that only comes from the config.
+        let mode = render::resolve_palette_mode(Some(ColorMode::TrueColor), None, &cfg)
+            .expect("no palette applied");
+        assert_eq!(mode, Some(ColorMode::TrueColor));
+        let mode = render::resolve_palette_mode(None, Some("nes"), &cfg).expect("nes preset");
+        assert!(
+            matches!(mode, Some(ColorMode::Palette { palette, .. }) if palette == ColorPalette::NES)
+        );
+        render::resolve_palette_mode(None, Some("no-such-palette"), &cfg)
+            .expect_err("neither preset nor file");
+    }
+
+    #[test]
+    fn cli_parses_max_colors() {
+        let cli = Cli::parse_from(["px2ansi-rs", "convert", "in.png", "--max-colors", "16"]);
+        let Commands::Convert { render, .. } = cli.command else {
+            unreachable!("Cli::parse_from should have produced Commands::Convert");
+        };
+        let RenderArgs { max_colors, .. } = render;
+        assert_eq!(max_colors, Some(16));
+        let opts = RenderOptions::default().with_text_max_colors(max_colors);
+        assert_eq!(opts.text_max_colors(), Some(16));
+        assert_eq!(opts.sixel_max_colors(), 64);
+        let opts = opts.with_text_max_colors(None);
+        assert_eq!(opts.text_max_colors(), None);
+        assert_eq!(opts.sixel_max_colors(), 64);
+    }
+
+    #[test]
+    fn render_args_apply_every_flag() -> TestResult {
+        let cli = Cli::parse_from([
+            "px2ansi-rs",
+            "show",
+            "--style",
+            "braille",
+            "--max-colors",
+            "16",
+            "--height",
+            "8",
+            "--crop",
+            "1,2,3,4",
+            "--trim",
+            "--cell-aspect",
+            "2.5",
+            "--invert",
+        ]);
+        let Commands::Show { render, .. } = cli.command else {
+            unreachable!("Cli::parse_from should have produced Commands::Show");
+        };
+        let opts = render.apply(Some(30), &Config::default())?;
+        assert_eq!(opts.charset(), CharsetMode::Braille);
+        assert_eq!(opts.width(), Some(30));
+        assert_eq!(opts.height(), Some(8));
+        assert_eq!(opts.text_max_colors(), Some(16));
+        assert_eq!(opts.crop(), Some((1, 2, 3, 4)));
+        assert!(opts.auto_trim());
+        assert!((opts.cell_aspect() - 2.5).abs() < f32::EPSILON);
+        assert_eq!(opts.adjustments(), Adjustments::default().invert(true));
+        Ok(())
+    }
+
+    #[test]
+    fn cli_parses_pixel_art_filter() {
+        let cli = Cli::parse_from(["px2ansi-rs", "convert", "in.png", "-f", "pixel-art"]);
+        let Commands::Convert { render, .. } = cli.command else {
+            unreachable!("Cli::parse_from should have produced Commands::Convert");
+        };
+        let RenderArgs { filter, .. } = render;
+        assert_eq!(filter, Some(ResizeFilter::PixelArt));
+    }
+
+    #[test]
+    fn cli_parses_upscale_filters() {
+        for (name, expected) in [
+            ("scale2x", ResizeFilter::Scale2x),
+            ("epx", ResizeFilter::Scale2x),
+            ("scale3x", ResizeFilter::Scale3x),
+            ("xbr", ResizeFilter::Xbr),
+        ] {
+            let cli = Cli::parse_from(["px2ansi-rs", "convert", "in.png", "-f", name]);
+            let Commands::Convert { render, .. } = cli.command else {
+                unreachable!("Cli::parse_from should have produced Commands::Convert");
+            };
+            let RenderArgs { filter, .. } = render;
+            assert_eq!(filter, Some(expected));
+        }
+    }
+
+    #[test]
+    fn cli_parses_html_output() {
+        let cli = Cli::parse_from([
+            "px2ansi-rs",
+            "convert",
+            "in.png",
+            "--format",
+            "html",
+            "--html-classes",
+            "--html-standalone",
+            "--html-font-size",
+            "18",
+        ]);
+        let Commands::Convert {
+            format,
+            html_classes,
+            html_standalone,
+            html_font_size,
+            ..
+        } = cli.command
+        else {
+            unreachable!("Cli::parse_from should have produced Commands::Convert");
+        };
+        assert_eq!(format, Some(output::OutputFormat::Html));
+        assert!(html_classes && html_standalone);
+        assert_eq!(html_font_size, Some(18.0));
+
+        let from_path = |path: &str| output::OutputFormat::from_path(Some(path.as_ref()));
+        assert_eq!(from_path("art.HTML"), output::OutputFormat::Html);
+        assert_eq!(from_path("art.htm"), output::OutputFormat::Html);
+        assert_eq!(from_path("art.ans"), output::OutputFormat::Ansi);
+        assert_eq!(
+            output::OutputFormat::from_path(None),
+            output::OutputFormat::Ansi
+        );
+    }
+
+    #[test]
+    fn cli_parses_svg_output() {
+        let cli = Cli::parse_from([
+            "px2ansi-rs",
+            "convert",
+            "in.png",
+            "--format",
+            "svg",
+            "--svg-merge",
+            "--svg-embed-font",
+        ]);
+        let Commands::Convert {
+            format,
+            svg_merge,
+            svg_embed_font,
+            ..
+        } = cli.command
+        else {
+            unreachable!("Cli::parse_from should have produced Commands::Convert");
+        };
+        assert_eq!(format, Some(output::OutputFormat::Svg));
+        assert!(svg_merge && svg_embed_font);
+        assert_eq!(
+            output::OutputFormat::from_path(Some("art.SVG".as_ref())),
+            output::OutputFormat::Svg
+        );
+    }
+
+    #[test]
+    fn cli_parses_resize_space() {
+        let cli = Cli::parse_from([
+            "px2ansi-rs",
+            "convert",
+            "in.png",
+            "--resize-space",
+            "linear-premultiplied",
+        ]);
+        let Commands::Convert { render, .. } = cli.command else {
+            unreachable!("Cli::parse_from should have produced Commands::Convert");
+        };
+        let RenderArgs { resize_space, .. } = render;
+        assert_eq!(resize_space, Some(ResizeSpace::LinearPremultiplied));
+
+        let cli = Cli::parse_from(["px2ansi-rs", "show", "--resize-space", "linear"]);
+        let Commands::Show { render, .. } = cli.command else {
+            unreachable!("Cli::parse_from should have produced Commands::Show");
+        };
+        let RenderArgs { resize_space, .. } = render;
+        assert_eq!(resize_space, Some(ResizeSpace::Linear));
+        let opts = RenderOptions::default().with_resize_space(ResizeSpace::Linear);
+        assert_eq!(opts.resize_space(), ResizeSpace::Linear);
+        assert_eq!(RenderOptions::default().resize_space(), ResizeSpace::Srgb);
+    }
+
+    #[test]
+    fn cli_parses_height_and_fit() {
+        let cli = Cli::parse_from([
+            "px2ansi-rs",
+            "convert",
+            "in.png",
+            "--width",
+            "40",
+            "--height",
+            "12",
+            "--fit",
+            "cover",
+        ]);
+        let Commands::Convert { width, render, .. } = cli.command else {
+            unreachable!("Cli::parse_from should have produced Commands::Convert");
+        };
+        let RenderArgs { height, fit, .. } = render;
+        assert_eq!(
+            (width, height, fit),
+            (Some(40), Some(12), Some(FitMode::Cover))
+        );
+
+        let cli = Cli::parse_from(["px2ansi-rs", "show", "--height", "8", "--fit", "native"]);
+        let Commands::Show { render, .. } = cli.command else {
+            unreachable!("Cli::parse_from should have produced Commands::Show");
+        };
+        let RenderArgs { height, fit, .. } = render;
+        assert_eq!((height, fit), (Some(8), Some(FitMode::Native)));
+        let opts = RenderOptions::default()
+            .with_height(8)
+            .with_fit(FitMode::Stretch);
+        assert_eq!((opts.height(), opts.fit()), (Some(8), FitMode::Stretch));
+        assert_eq!(RenderOptions::default().fit(), FitMode::Contain);
+    }
+
+    #[test]
+    fn cli_parses_cell_aspect() {
+        use crate::render::CellAspect;
+
+        let cli = Cli::parse_from(["px2ansi-rs", "convert", "in.png", "--cell-aspect", "2.4"]);
+        let Commands::Convert { render, .. } = cli.command else {
+            unreachable!("Cli::parse_from should have produced Commands::Convert");
+        };
+        let RenderArgs { cell_aspect, .. } = render;
+        assert_eq!(cell_aspect, Some(CellAspect::Ratio(2.4)));
+
+        let cli = Cli::parse_from(["px2ansi-rs", "show", "--cell-aspect", "auto"]);
+        let Commands::Show { render, .. } = cli.command else {
+            unreachable!("Cli::parse_from should have produced Commands::Show");
+        };
+        let RenderArgs { cell_aspect, .. } = render;
+        assert_eq!(cell_aspect, Some(CellAspect::Auto));
+        assert!(
+            Cli::try_parse_from(["px2ansi-rs", "convert", "in.png", "--cell-aspect", "-1"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn cli_parses_crop_and_trim() {
+        let cli = Cli::parse_from([
+            "px2ansi-rs",
+            "convert",
+            "in.png",
+            "--crop",
+            "4,8,32,16",
+            "--trim",
+        ]);
+        let Commands::Convert { render, .. } = cli.command else {
+            unreachable!("Cli::parse_from should have produced Commands::Convert");
+        };
+        let RenderArgs { crop, trim, .. } = render;
+        assert_eq!((crop, trim), (Some((4, 8, 32, 16)), true));
+
+        let cli = Cli::parse_from(["px2ansi-rs", "show", "--trim"]);
+        let Commands::Show { render, .. } = cli.command else {
+            unreachable!("Cli::parse_from should have produced Commands::Show");
+        };
+        let RenderArgs { crop, trim, .. } = render;
+        assert_eq!((crop, trim), (None, true));
+        assert!(
+            Cli::try_parse_from(["px2ansi-rs", "convert", "in.png", "--crop", "1,2,0,4"]).is_err()
+        );
+    }
+
+    #[test]
+    fn cli_parses_tone_map() {
+        let tone_map = |args: &[&str]| {
+            let cli = Cli::parse_from([&["px2ansi-rs", "convert", "in.png"], args].concat());
+            let Commands::Convert { render, .. } = cli.command else {
+                unreachable!("Cli::parse_from should have produced Commands::Convert");
+            };
+            let RenderArgs { tone_map, .. } = render;
+            tone_map
+        };
+        assert_eq!(tone_map(&[]), None);
+        assert_eq!(tone_map(&["--tone-map", "clahe"]), Some(ToneMap::Clahe));
+        assert_eq!(
+            tone_map(&["--tone-map", "equalise"]),
+            Some(ToneMap::Equalize)
+        );
+        assert_eq!(
+            tone_map(&["--tone-map", "percentile"]),
+            Some(ToneMap::Percentile(1))
+        );
+        assert_eq!(
+            tone_map(&["--tone-map", "percentile:5"]),
+            Some(ToneMap::Percentile(5))
+        );
+        assert!(
+            Cli::try_parse_from([
+                "px2ansi-rs",
+                "convert",
+                "in.png",
+                "--tone-map",
+                "percentile:50"
+            ])
+            .is_err()
+        );
+        assert!(Cli::try_parse_from(["px2ansi-rs", "show", "--tone-map", "gamma"]).is_err());
+        let opts = RenderOptions::default().with_tone_map(ToneMap::Equalize);
+        assert_eq!(opts.tone_map(), ToneMap::Equalize);
+    }
+
+    #[test]
+    fn cli_parses_image_adjustments() {
+        let cli = Cli::parse_from([
+            "px2ansi-rs",
+            "show",
+            "mario",
+            "--brightness",
+            "1.2",
+            "--saturation",
+            "0",
+            "--hue",
+            "-90",
+            "--invert",
+        ]);
+        let Commands::Show { render, .. } = cli.command else {
+            unreachable!("Cli::parse_from should have produced Commands::Show");
+        };
+        let RenderArgs {
+            brightness,
+            contrast,
+            gamma,
+            saturation,
+            hue,
+            invert,
+            ..
+        } = render;
+        let adjust = Adjustments::default()
+            .brightness(brightness)
+            .contrast(contrast)
+            .gamma(gamma)
+            .saturation(saturation)
+            .hue_rotate(hue)
+            .invert(invert);
+        assert_eq!(
+            adjust,
+            Adjustments::default()
+                .brightness(1.2)
+                .saturation(0.0)
+                .hue_rotate(-90.0)
+                .invert(true)
+        );
+
+        let cli = Cli::parse_from(["px2ansi-rs", "convert", "in.png"]);
+        let Commands::Convert { render, .. } = cli.command else {
+            unreachable!("Cli::parse_from should have produced Commands::Convert");
+        };
+        let RenderArgs {
+            brightness,
+            contrast,
+            gamma,
+            saturation,
+            hue,
+            invert,
+            ..
+        } = render;
+        let adjust = Adjustments::default()
+            .brightness(brightness)
+            .contrast(contrast)
+            .gamma(gamma)
+            .saturation(saturation)
+            .hue_rotate(hue)
+            .invert(invert);
+        assert!(adjust.is_identity());
+    }
+
     #[test]
     fn cli_show_defaults_to_random() {
         let cli = Cli::parse_from(["px2ansi-rs", "show"]);
@@ -327,6 +837,141 @@ mod tests {
         assert_eq!(cli.index.as_deref(), Some("custom.json"));
     }
 
+    // --- Fetch layout ---
+
+    /// Returns `n` from the first `ESC [ n <end>` in `out` with `n > 1`.
+    fn csi_param(out: &str, end: char) -> Option<usize> {
+        out.split("\x1b[").skip(1).find_map(|seq| {
+            let digits = seq.len() - seq.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            let n: usize = seq[..digits].parse().ok()?;
+            (seq[digits..].starts_with(end) && n > 1).then_some(n)
+        })
+    }
+
+    /// Returns `key` from the first `prefix` sequence in `out`, whose keys are
+    /// separated by `sep` and end at `end`.
+    fn placement_key(out: &str, prefix: &str, end: char, sep: char, key: &str) -> Option<usize> {
+        let control = out.split(prefix).nth(1)?.split(end).next()?;
+        control
+            .split(sep)
+            .find_map(|kv| kv.strip_prefix(key)?.strip_prefix('=')?.parse().ok())
+    }
+
+    #[test]
+    fn fetch_reserves_the_cells_pixel_protocols_place_the_image_in() -> TestResult {
+        for (charset, prefix, end, sep, cols_key, rows_key) in [
+            (CharsetMode::Kitty, "\x1b_G", ';', ',', "c", "r"),
+            (
+                CharsetMode::Iterm2,
+                "\x1b]1337;File=",
+                ':',
+                ';',
+                "width",
+                "height",
+            ),
+        ] {
+            let render = RenderOptions::builder().charset(charset).build();
+            for (w, h) in [(400, 40), (40, 400)] {
+                let img = image::DynamicImage::ImageRgba8(image::RgbaImage::new(w, h));
+                let mut buf = Vec::new();
+                fetch::print_fetch_with_image(&img, &render, &mut buf)?;
+                let out = String::from_utf8(buf)?;
+
+                let rows = csi_param(&out, 'A').ok_or("no reserved rows")?;
+                let text_col = csi_param(&out, 'G').ok_or("no info column")?;
+                let c = placement_key(&out, prefix, end, sep, cols_key).ok_or("no column key")?;
+                let r = placement_key(&out, prefix, end, sep, rows_key).ok_or("no row key")?;
+                // The info column starts one gap column after the image.
+                assert_eq!(
+                    c + 2,
+                    text_col,
+                    "{charset:?} {w}x{h}: image overlaps the info"
+                );
+                assert!(
+                    r <= rows,
+                    "{charset:?} {w}x{h}: {r} rows placed in {rows} reserved"
+                );
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn fetch_indents_the_info_past_sub_cell_images() -> TestResult {
+        // Columns left of the info lines below a one-row image.
+        let indent = |charset| -> Result<usize, Box<dyn std::error::Error>> {
+            let render = RenderOptions::builder().charset(charset).build();
+            let img = image::DynamicImage::ImageRgba8(image::RgbaImage::new(200, 2));
+            let mut buf = Vec::new();
+            fetch::print_fetch_with_image(&img, &render, &mut buf)?;
+            let out = String::from_utf8(buf)?;
+            let line = out
+                .lines()
+                .find(|l| l.starts_with(' ') && !l.trim().is_empty())
+                .ok_or("no info line below the image")?;
+            Ok(line.len() - line.trim_start().len())
+        };
+        // Half blocks draw one pixel per column.
+        let image_px = indent(CharsetMode::Ansi)? - 1;
+        for (charset, px_per_col) in [
+            (CharsetMode::Quadrant, 2),
+            (CharsetMode::Sextant, 2),
+            (CharsetMode::Octant, 2),
+            (CharsetMode::Braille, 2),
+            (CharsetMode::Hybrid, 2),
+            (CharsetMode::Shape, 4),
+        ] {
+            assert_eq!(
+                indent(charset)? - 1,
+                image_px.div_ceil(px_per_col),
+                "{charset:?}"
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn spin_fetch_rejects_kitty_and_iterm2_output() {
+        let img = image::DynamicImage::ImageRgba8(image::RgbaImage::new(4, 4));
+        for charset in [CharsetMode::Kitty, CharsetMode::Iterm2] {
+            let render = RenderOptions::builder().charset(charset).build();
+            let err = rotate::run_spin_fetch_loop(
+                &img,
+                &render,
+                10,
+                RotateAxis::Y,
+                false,
+                &mut Vec::new(),
+            )
+            .expect_err("pixel protocol spin fetch is rejected");
+            assert!(err.to_string().contains("--fetch"), "{err}");
+        }
+    }
+
+    // --- Spin frames ---
+
+    #[test]
+    fn spin_frames_crop_and_trim_the_source_once() {
+        // A 10x6 opaque sprite inside a transparent 20x10 canvas.
+        let img = image::DynamicImage::ImageRgba8(image::RgbaImage::from_fn(20, 10, |x, y| {
+            let inside = (5..15).contains(&x) && (2..8).contains(&y);
+            image::Rgba([255, 0, 0, if inside { 255 } else { 0 }])
+        }));
+        let render = RenderOptions::builder().auto_trim(true).build();
+
+        let (frames, frame_opts) = rotate::spin_frames(&img, &render, RotateAxis::Y, false);
+        assert!(frames.iter().all(|f| (f.width(), f.height()) == (10, 6)));
+        assert!(
+            !frame_opts.auto_trim(),
+            "squished frames would lose their padding"
+        );
+
+        let render = RenderOptions::builder().crop(0, 0, 8, 4).build();
+        let (frames, frame_opts) = rotate::spin_frames(&img, &render, RotateAxis::Z, false);
+        assert_eq!((frames[0].width(), frames[0].height()), (8, 4));
+        assert_eq!(frame_opts.crop(), None);
+    }
+
     // --- RenderStylePreset parsing ---
 
     #[test]
@@ -335,8 +980,14 @@ mod tests {
         let cases = [
             ("ansi", RenderStylePreset::Ansi),
             ("braille", RenderStylePreset::Braille),
+            ("quadrant", RenderStylePreset::Quadrant),
+            ("sextant", RenderStylePreset::Sextant),
+            ("octant", RenderStylePreset::Octant),
+            ("hybrid", RenderStylePreset::Hybrid),
             ("fade", RenderStylePreset::Fade),
             ("ascii", RenderStylePreset::Ascii),
+            ("shape", RenderStylePreset::Shape),
+            ("edges", RenderStylePreset::Edges),
             ("kanji", RenderStylePreset::Kanji),
             ("chinese", RenderStylePreset::Chinese),
             ("full-block", RenderStylePreset::FullBlock),
@@ -28,11 +28,12 @@
 // static GLOBAL: MiMalloc = MiMalloc;
 
 use px2ansi_rs::{
-    Cli, Command, Commands, Config, ConvertCmd, IndexCmd, ListCmd, ResolvedOptions, ShowCmd,
-    commands, output, render,
+    Cli, Command, Commands, Config, ConvertCmd, Export, IndexCmd, ListCmd, ResolvedOptions,
+    ShowCmd, commands, output,
 };
 
 use clap::{CommandFactory, Parser};
+use px2ansi::{HtmlEncoder, SvgEncoder};
 
 use anyhow::Result;
 
@@ -89,40 +90,67 @@ fn build_command(cli: Cli, cfg: &Config, opts: &ResolvedOptions) -> Result<Comma
             input,
             output,
             output_image,
+            format,
+            html_classes,
+            html_standalone,
+            html_font,
+            html_font_size,
+            svg_merge,
+            svg_embed_font,
+            render,
             width,
-            filter,
-            style,
-            density,
-            dither,
             raster_theme,
-            color_mode,
             rotate,
             axis,
             unidirectional,
             fps,
             fetch,
-            composite_bg,
         } => {
-            let render_opts = render::build_render_options(
-                style,
-                density,
-                width,
-                filter,
-                color_mode,
-                dither,
-                composite_bg,
-            );
+            let render_opts = render.apply(width, cfg)?;
 
             let output_image = output_image.or_else(|| cfg.output_image.as_ref().map(Into::into));
 
             let rotate = px2ansi_rs::rotate::parse_rotate(rotate, fps, axis, unidirectional)?;
 
+            let raster_theme = raster_theme.unwrap_or(cfg.raster_theme);
+            let format =
+                format.unwrap_or_else(|| output::OutputFormat::from_path(output.as_deref()));
+            let export_mode = px2ansi::ansi_encoder(&render_opts).color_mode();
+            let export = match format {
+                output::OutputFormat::Ansi => None,
+                output::OutputFormat::Html => {
+                    let mut html = HtmlEncoder::new(export_mode)
+                        .with_classes(html_classes)
+                        .with_standalone(html_standalone);
+                    if html_standalone {
+                        html = html.with_theme(raster_theme);
+                    }
+                    if let Some(stem) = input.file_stem() {
+                        html = html.with_title(stem.to_string_lossy().into_owned());
+                    }
+                    if let Some(family) = html_font {
+                        html = html.with_font_family(family);
+                    }
+                    if let Some(px) = html_font_size {
+                        html = html.with_font_size(px);
+                    }
+                    Some(Export::Html(html))
+                }
+                output::OutputFormat::Svg => Some(Export::Svg(
+                    SvgEncoder::new(export_mode)
+                        .with_theme(raster_theme)
+                        .with_merge(svg_merge)
+                        .with_embedded_font(svg_embed_font),
+                )),
+            };
+
             Ok(Command::Convert(ConvertCmd {
                 input,
                 output,
                 output_image,
+                export,
                 render: render_opts,
-                raster_theme: raster_theme.unwrap_or(cfg.raster_theme),
+                raster_theme,
                 rotate,
                 fetch,
             }))
@@ -137,28 +165,15 @@ fn build_command(cli: Cli, cfg: &Config, opts: &ResolvedOptions) -> Result<Comma
         })),
         Commands::Show {
             name,
-            filter,
+            render,
             interactive,
-            style,
-            density,
-            dither,
-            color_mode,
             rotate,
             axis,
             unidirectional,
             fps,
             fetch,
-            composite_bg,
         } => {
-            let render_opts = render::build_render_options(
-                style,
-                density,
-                None,
-                filter,
-                color_mode,
-                dither,
-                composite_bg,
-            );
+            let render_opts = render.apply(None, cfg)?;
 
             let rotate = px2ansi_rs::rotate::parse_rotate(rotate, fps, axis, unidirectional)?;
 
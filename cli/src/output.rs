@@ -8,3 +8,33 @@ pub fn print_summary(duration: std::time::Duration) {
         duration.as_millis()
     );
 }
+
+/// Text format `convert` writes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// ANSI escape sequences for a terminal. (default)
+    #[default]
+    Ansi,
+    /// An HTML `<pre>` block that keeps the art as selectable text.
+    Html,
+    /// A resolution-independent SVG image.
+    Svg,
+}
+
+impl OutputFormat {
+    /// Picks the format from the output file's extension: `.html` and
+    /// `.htm` give [`OutputFormat::Html`], `.svg` [`OutputFormat::Svg`],
+    /// anything else ANSI.
+    #[must_use]
+    pub fn from_path(path: Option<&std::path::Path>) -> Self {
+        let ext = path.and_then(std::path::Path::extension);
+        let is = |name: &str| ext.is_some_and(|e| e.eq_ignore_ascii_case(name));
+        if is("html") || is("htm") {
+            Self::Html
+        } else if is("svg") {
+            Self::Svg
+        } else {
+            Self::Ansi
+        }
+    }
+}
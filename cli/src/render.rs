@@ -1,5 +1,10 @@
 use crate::RenderStylePreset;
-use px2ansi::{ColorMode, Density, RenderOptions, ResizeFilter};
+use crate::config::Config;
+use px2ansi::{
+    Ansi16Palette, CharsetMode, ColorMode, ColorPalette, CustomCharset, Density, DitherMode,
+    RenderError, RenderOptions, ResizeFilter,
+};
+use std::str::FromStr;
 
 /// Constructs a [`RenderOptions`] instance from a set of optional configuration parameters.
 ///
@@ -14,6 +19,7 @@ use px2ansi::{ColorMode, Density, RenderOptions, ResizeFilter};
 /// * `width` - The target width in characters for the rendered output.
 /// * `filter` - The resampling algorithm used if the image needs to be resized.
 /// * `no_color` - If `true`, explicitly disables ANSI color output in the resulting options.
+/// * `dither` - The dithering algorithm applied after resizing, if any.
 ///
 /// # Examples
 ///
@@ -22,16 +28,16 @@ use px2ansi::{ColorMode, Density, RenderOptions, ResizeFilter};
 /// use px2ansi::{RenderStylePreset, ColorMode};
 ///
 /// // Width is passed through
-/// let opts = build_render_options(None, None, Some(80), None, None, false, false);
+/// let opts = build_render_options(None, None, Some(80), None, None, None, false);
 /// assert_eq!(opts.width(), Some(80));
 ///
 /// // No-op when all None/false
-/// let opts = build_render_options(None, None, None, None, None, false, false);
+/// let opts = build_render_options(None, None, None, None, None, None, false);
 /// assert_eq!(opts.width(), None);
 /// assert_ne!(opts.color_mode(), ColorMode::None); // color is on by default
 ///
 /// // Explicitly disable color by passing Some(ColorMode::None)
-/// let opts = build_render_options(None, None, None, None, Some(ColorMode::None), false, false);
+/// let opts = build_render_options(None, None, None, None, Some(ColorMode::None), None, false);
 /// assert_eq!(opts.color_mode(), ColorMode::None);
 /// ```
 #[must_use]
@@ -41,7 +47,7 @@ pub fn build_render_options(
     width: Option<u32>,
     filter: Option<ResizeFilter>,
     color_mode: Option<ColorMode>,
-    dither: bool,
+    dither: Option<DitherMode>,
     composite_bg: bool,
 ) -> RenderOptions {
     let mut builder = RenderOptions::builder();
@@ -63,10 +69,217 @@ pub fn build_render_options(
         builder = builder.color_mode(mode);
     }
 
+    if let Some(mode) = dither {
+        builder = builder.dither(mode);
+    }
+
     if composite_bg && let Some(bg) = crate::terminal::query_terminal_bg() {
         builder = builder.bg_color(bg);
     }
 
-    // Always apply the dither flag and then build
-    builder.dither(dither).build()
+    builder.build()
+}
+
+/// Resolves a `--charset` argument into a [`CharsetMode::Custom`].
+///
+/// A name matching an entry in [`Config::charsets`] selects that ramp;
+/// anything else is taken literally as the glyphs, darkest first. `wide`
+/// forces double-width glyphs even when the named ramp does not set it.
+///
+/// # Errors
+///
+/// Returns [`RenderError::InvalidCharset`] if the resulting ramp is empty.
+///
+/// # Examples
+///
+/// ```rust
+/// use px2ansi_rs::{Config, render::resolve_charset};
+///
+/// let mode = resolve_charset(" .:#", false, &Config::default()).unwrap();
+/// assert_eq!(mode.glyphs(), Some(&[" ", ".", ":", "#"][..]));
+/// ```
+pub fn resolve_charset(spec: &str, wide: bool, cfg: &Config) -> Result<CharsetMode, RenderError> {
+    let custom = match cfg.charsets.get(spec) {
+        Some(named) => CustomCharset::from_ramp(&named.glyphs, wide || named.wide)?,
+        None => CustomCharset::from_ramp(spec, wide)?,
+    };
+    Ok(CharsetMode::Custom(custom))
+}
+
+/// Resolves the color mode, fitting [`ColorMode::Ansi16`] with its palette.
+///
+/// An explicit `palette` wins over the config's `ansi16_palette`, and implies
+/// [`ColorMode::Ansi16`] when no mode is given.
+///
+/// # Errors
+///
+/// Returns an error if the config's palette cannot be parsed.
+///
+/// # Examples
+///
+/// ```
+/// use px2ansi::{Ansi16Palette, ColorMode};
+/// use px2ansi_rs::{Config, render::resolve_color_mode};
+///
+/// let mode = resolve_color_mode(None, Some(Ansi16Palette::NORD), &Config::default()).unwrap();
+/// assert_eq!(mode, Some(ColorMode::Ansi16(Ansi16Palette::NORD)));
+/// ```
+pub fn resolve_color_mode(
+    mode: Option<ColorMode>,
+    palette: Option<Ansi16Palette>,
+    cfg: &Config,
+) -> Result<Option<ColorMode>, String> {
+    let implied = palette.map(ColorMode::Ansi16);
+    let palette = match palette {
+        Some(palette) => Some(palette),
+        None => cfg.ansi16_palette.as_deref().map(str::parse).transpose()?,
+    };
+    Ok(match mode {
+        Some(ColorMode::Ansi16(default)) => Some(ColorMode::Ansi16(palette.unwrap_or(default))),
+        None => implied,
+        mode => mode,
+    })
+}
+
+/// Resolves a `--palette` argument into a [`ColorPalette`].
+///
+/// `spec` is a preset name (`cga`, `ega`, `game-boy`, `pico-8`, `nes`), a
+/// path to a GIMP `.gpl` or hex-list file, or an inline list of `#rrggbb`
+/// colours separated by commas.
+///
+/// # Errors
+///
+/// Returns [`RenderError::Io`] if the palette file cannot be read, or
+/// [`RenderError::InvalidPalette`] if `spec` matches none of the above.
+///
+/// # Examples
+///
+/// ```rust
+/// use px2ansi::ColorPalette;
+/// use px2ansi_rs::render::resolve_palette;
+///
+/// assert_eq!(resolve_palette("pico-8").unwrap(), ColorPalette::PICO8);
+/// assert_eq!(resolve_palette("#000000,#ffffff").unwrap().colors().len(), 2);
+/// ```
+pub fn resolve_palette(spec: &str) -> Result<ColorPalette, RenderError> {
+    if let Ok(preset) = spec.parse() {
+        return Ok(preset);
+    }
+    if std::path::Path::new(spec).is_file() {
+        return ColorPalette::load(spec);
+    }
+    if spec.starts_with('#') || spec.contains(',') {
+        return ColorPalette::from_hex_list(spec);
+    }
+    Err(RenderError::InvalidPalette(format!(
+        "'{spec}' is not a preset (cga, ega, game-boy, pico-8, nes), a palette file, or a list of hex colours"
+    )))
+}
+
+/// Locks the color mode to a fixed palette from `--palette` or the config's
+/// `palette`.
+///
+/// The palette keeps the emission of the given mode: 256-color escapes for
+/// [`ColorMode::Ansi256`], 24-bit escapes for [`ColorMode::TrueColor`], and
+/// whatever the terminal supports when no mode is given. Other modes are left
+/// alone, as is an explicit mode when the palette only comes from the config.
+///
+/// # Errors
+///
+/// Returns an error if the palette cannot be resolved; see
+/// [`resolve_palette`].
+pub fn resolve_palette_mode(
+    mode: Option<ColorMode>,
+    palette: Option<&str>,
+    cfg: &Config,
+) -> Result<Option<ColorMode>, RenderError> {
+    let spec = match (palette, &cfg.palette) {
+        (Some(spec), _) => spec,
+        (None, Some(spec)) if mode.is_none() => spec,
+        _ => return Ok(mode),
+    };
+    let ansi256 = match mode.unwrap_or_else(ColorMode::detect) {
+        ColorMode::Ansi256 => true,
+        ColorMode::TrueColor => false,
+        other => return Ok(Some(other)),
+    };
+    Ok(Some(ColorMode::Palette {
+        palette: resolve_palette(spec)?,
+        ansi256,
+    }))
+}
+
+/// A `--cell-aspect` argument: the height-to-width ratio of a terminal cell,
+/// or `auto` to ask the terminal.
+///
+/// # Examples
+///
+/// ```rust
+/// use px2ansi_rs::render::CellAspect;
+///
+/// assert_eq!("2.2".parse(), Ok(CellAspect::Ratio(2.2)));
+/// assert_eq!("auto".parse(), Ok(CellAspect::Auto));
+/// assert!("0".parse::<CellAspect>().is_err());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CellAspect {
+    /// Detect the ratio with [`px2ansi::detect_cell_aspect`].
+    Auto,
+    /// A fixed ratio, such as `2.0` for cells twice as tall as wide.
+    Ratio(f32),
+}
+
+impl CellAspect {
+    /// Returns the ratio, detecting it for [`CellAspect::Auto`]. `None` means
+    /// the terminal did not report its cell size.
+    #[must_use]
+    pub fn resolve(self) -> Option<f32> {
+        match self {
+            Self::Auto => px2ansi::detect_cell_aspect(),
+            Self::Ratio(ratio) => Some(ratio),
+        }
+    }
+}
+
+impl FromStr for CellAspect {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("auto") {
+            return Ok(Self::Auto);
+        }
+        match s.parse::<f32>() {
+            Ok(ratio) if ratio.is_finite() && ratio > 0.0 => Ok(Self::Ratio(ratio)),
+            _ => Err(format!(
+                "invalid cell aspect: '{s}'. (valid: auto, or a positive height/width ratio)"
+            )),
+        }
+    }
+}
+
+/// Parses a `--crop` argument of the form `X,Y,W,H` in source pixels.
+///
+/// # Errors
+///
+/// Returns an error unless `spec` is four comma-separated whole numbers with
+/// a non-zero width and height.
+///
+/// # Examples
+///
+/// ```rust
+/// use px2ansi_rs::render::parse_crop;
+///
+/// assert_eq!(parse_crop("8, 0, 32, 32"), Ok((8, 0, 32, 32)));
+/// assert!(parse_crop("8,0,32").is_err());
+/// ```
+pub fn parse_crop(spec: &str) -> Result<(u32, u32, u32, u32), String> {
+    let invalid = || format!("invalid crop: '{spec}'. (expected X,Y,W,H in pixels)");
+    let parts = spec
+        .split(',')
+        .map(|n| n.trim().parse::<u32>().map_err(|_| invalid()))
+        .collect::<Result<Vec<_>, _>>()?;
+    match parts[..] {
+        [x, y, w, h] if w > 0 && h > 0 => Ok((x, y, w, h)),
+        _ => Err(invalid()),
+    }
 }
@@ -244,6 +244,27 @@ fn pad_to_height(img: DynamicImage, target_h: u32) -> DynamicImage {
 
 // ── Spin loop ─────────────────────────────────────────────────────────────────
 
+/// Generates the spin frames for `img` and the options to render them with.
+///
+/// The crop and trim in `render` are applied to `img` once, before the frames
+/// are squished and padded, and turned off in the returned options so the
+/// padding of each frame is kept.
+#[must_use]
+pub fn spin_frames(
+    img: &DynamicImage,
+    render: &RenderOptions,
+    axis: RotateAxis,
+    unidirectional: bool,
+) -> (Vec<DynamicImage>, RenderOptions) {
+    let img = &*render.crop_image(img);
+    let frames = match axis {
+        RotateAxis::Z => generate_zaxis_frames(img),
+        axis if unidirectional => generate_unidirectional_frames(img, axis),
+        axis => generate_pingpong_frames(img, axis),
+    };
+    (frames, render.with_crop(None).with_auto_trim(false))
+}
+
 /// Renders a continuous spin loop to `writer`.
 ///
 /// Pre-renders all frames once to ANSI byte buffers, then replays them in a
@@ -269,11 +290,8 @@ pub fn run_spin_loop(
     // const CLEAR: &[u8] = b"\x1b[2J\x1b[H";
     let delay = Duration::from_millis(1000 / u64::from(fps.max(1)));
 
-    let frames = match axis {
-        RotateAxis::Z => generate_zaxis_frames(img),
-        axis if unidirectional => generate_unidirectional_frames(img, axis),
-        axis => generate_pingpong_frames(img, axis),
-    };
+    let (frames, render) = spin_frames(img, render, axis, unidirectional);
+    let render = &render;
 
     // Pre-render all frames to ANSI byte buffers.
     // With the `parallel` feature, all frames are rendered concurrently
@@ -328,6 +346,8 @@ pub fn run_spin_loop(
 ///
 /// This function will return an error if any of the following occur:
 ///
+/// * **Unsupported Output**: `render` uses kitty or iTerm2 output, whose images
+///   can't be laid out next to the info lines frame by frame.
 /// * **Pre-rendering Failure**: Any individual frame fails to render or compose.
 ///   If using the `parallel` feature, this error may be propagated from a
 ///   background thread via [`rayon`].
@@ -353,13 +373,16 @@ pub fn run_spin_fetch_loop(
     const GOTO_HOME: &[u8] = b"\x1b[H";
     const CLEAR_SCREEN: &[u8] = b"\x1b[2J";
 
+    // Each frame would transmit a new kitty or iTerm2 image, and its escape
+    // payload can't be measured as text to place the info column beside it.
+    if matches!(render.charset(), CharsetMode::Kitty | CharsetMode::Iterm2) {
+        anyhow::bail!("--rotate spin cannot be combined with --fetch for kitty or iTerm2 output");
+    }
+
     let delay = Duration::from_millis(1000 / u64::from(fps.max(1)));
 
-    let frames = match axis {
-        RotateAxis::Z => generate_zaxis_frames(img),
-        axis if unidirectional => generate_unidirectional_frames(img, axis),
-        axis => generate_pingpong_frames(img, axis),
-    };
+    let (frames, render) = spin_frames(img, render, axis, unidirectional);
+    let render = &render;
 
     // Snapshot info once — no per-frame sysinfo churn
     let info_lines = crate::fetch::fetch_lines();
@@ -409,7 +432,14 @@ fn compose_fetch_frame(
     let mut img_buf = Vec::with_capacity(frame.width() as usize * frame.height() as usize * 2);
 
     let cs = render.charset();
-    if cs == CharsetMode::Ascii || cs == CharsetMode::Chinese || cs == CharsetMode::Kanji {
+    if matches!(
+        cs,
+        CharsetMode::Ascii
+            | CharsetMode::Edges
+            | CharsetMode::Chinese
+            | CharsetMode::Kanji
+            | CharsetMode::Custom(_)
+    ) {
         let target_cols: u32 = 50;
         let aspect = f64::from(orig_h) / f64::from(orig_w);
         #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
@@ -418,6 +448,8 @@ fn compose_fetch_frame(
             frame.resize_exact(target_cols, target_h, image::imageops::FilterType::Nearest);
         let capped = render.with_width(target_cols);
         capped.render(&ascii_img, &mut img_buf)?;
+    } else if cs == CharsetMode::Sixel {
+        render.render(&render.prepare_image(frame), &mut img_buf)?;
     } else if orig_h > target_px_height {
         let scale = f64::from(target_px_height) / f64::from(orig_h);
         #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
@@ -39,3 +39,13 @@ fn version_flag_exits_successfully() -> TestResult {
     cmd()?.arg("--version").assert().success();
     Ok(())
 }
+
+#[test]
+fn svg_output_rejects_fetch_layout() -> TestResult {
+    cmd()?
+        .args(["convert", "input.png", "--format", "svg", "--fetch"])
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("--fetch cannot be combined"));
+    Ok(())
+}
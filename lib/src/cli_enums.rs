@@ -14,10 +14,22 @@ pub enum RenderStylePreset {
     Unicode,
     /// Uses 2x4 dot Braille patterns to significantly increase effective resolution.
     Braille,
+    /// Uses 2x2 quadrant blocks with a fitted foreground/background pair per cell.
+    Quadrant,
+    /// Uses 2x3 sextant blocks (Unicode 13) with a fitted colour pair per cell.
+    Sextant,
+    /// Uses 2x4 octant blocks (Unicode 16) with a fitted colour pair per cell.
+    Octant,
+    /// Picks the lowest-error glyph and colour pair per cell from a mixed symbol set.
+    Hybrid,
     /// Uses varying character densities to simulate color gradients or shadows.
     Fade,
     /// Uses standard 7-bit ASCII characters (e.g., #, @, ., :).
     Ascii,
+    /// Picks ASCII characters by matching each cell's shape against font glyphs.
+    Shape,
+    /// Traces contours with line characters, leaving filled regions blank.
+    Edges,
     /// Uses the full block character (U+2588) for solid color rendering.
     FullBlock,
     /// Uses high-density Unicode characters for a detailed grayscale effect.
@@ -28,6 +40,10 @@ pub enum RenderStylePreset {
     Chinese,
     /// Uses the SIXEL bitmap protocol for true high-resolution graphics in supported terminals.
     Sixel,
+    /// Uses the kitty graphics protocol to send an RGBA bitmap (kitty, `WezTerm`, Ghostty).
+    Kitty,
+    /// Uses the iTerm2 inline image protocol to send a PNG (iTerm2, `WezTerm`, Konsole).
+    Iterm2,
 }
 
 impl FromStr for RenderStylePreset {
@@ -38,15 +54,23 @@ impl FromStr for RenderStylePreset {
             "ansi" => Ok(Self::Ansi),
             "unicode" => Ok(Self::Unicode),
             "braille" => Ok(Self::Braille),
+            "quadrant" => Ok(Self::Quadrant),
+            "sextant" => Ok(Self::Sextant),
+            "octant" => Ok(Self::Octant),
+            "hybrid" => Ok(Self::Hybrid),
             "fade" => Ok(Self::Fade),
             "ascii" => Ok(Self::Ascii),
+            "shape" => Ok(Self::Shape),
+            "edges" | "outline" => Ok(Self::Edges),
             "kanji" => Ok(Self::Kanji),
             "chinese" => Ok(Self::Chinese),
             "fullblock" => Ok(Self::FullBlock),
             "dense" => Ok(Self::Dense),
             "sixel" => Ok(Self::Sixel),
+            "kitty" => Ok(Self::Kitty),
+            "iterm2" | "iterm" => Ok(Self::Iterm2),
             _ => Err(format!(
-                "invalid style: '{s}'. (valid: ansi, unicode, braille, fade, ascii, kanji, chinese, full-block, dense)"
+                "invalid style: '{s}'. (valid: ansi, unicode, braille, quadrant, sextant, octant, hybrid, fade, ascii, shape, edges, kanji, chinese, full-block, dense, sixel, kitty, iterm2)"
             )),
         }
     }
@@ -79,6 +103,32 @@ pub enum ResizeFilter {
     ///
     /// Highest quality resampling; reduces aliasing significantly but is computationally expensive.
     Lanczos3,
+    /// Integer scaling of the native pixel grid.
+    ///
+    /// Detects images upscaled by a whole factor with nearest neighbour,
+    /// recovers their native pixels and scales those by whole numbers only,
+    /// so no pixel is smeared or dropped. The output may come out smaller
+    /// than the requested size.
+    PixelArt,
+    /// The most common colour of each source block.
+    ///
+    /// Downscales without blending colours or sampling a single pixel, and
+    /// keeps thin dark outlines that cross a block. Upscales like
+    /// [`Nearest`](Self::Nearest).
+    Majority,
+    /// Scale2x (also known as EPX or `AdvMAME2x`) upscaling.
+    ///
+    /// Doubles the image as often as it fits the target, turning staircase
+    /// diagonals into smooth steps without adding colours, then finishes
+    /// like [`Nearest`](Self::Nearest).
+    Scale2x,
+    /// Scale3x (`AdvMAME3x`) upscaling, tripling instead of doubling.
+    Scale3x,
+    /// 2xBR upscaling.
+    ///
+    /// Like [`Scale2x`](Self::Scale2x), but finds edges with weighted colour
+    /// distances and blends along them, giving softer diagonals.
+    Xbr,
 }
 
 impl FromStr for ResizeFilter {
@@ -91,8 +141,13 @@ impl FromStr for ResizeFilter {
             "catmullrom" => Ok(Self::CatmullRom),
             "gaussian" => Ok(Self::Gaussian),
             "lanczos3" => Ok(Self::Lanczos3),
+            "pixelart" | "pixel" => Ok(Self::PixelArt),
+            "majority" | "mode" => Ok(Self::Majority),
+            "scale2x" | "epx" | "advmame2x" => Ok(Self::Scale2x),
+            "scale3x" | "advmame3x" => Ok(Self::Scale3x),
+            "xbr" | "2xbr" => Ok(Self::Xbr),
             _ => Err(format!(
-                "invalid filter: '{s}'. (valid: nearest, triangle, catmull-rom, gaussian, lanczos3)"
+                "invalid filter: '{s}'. (valid: nearest, triangle, catmull-rom, gaussian, lanczos3, pixel-art, majority, scale2x, scale3x, xbr)"
             )),
         }
     }
@@ -101,7 +156,12 @@ impl FromStr for ResizeFilter {
 impl From<ResizeFilter> for image::imageops::FilterType {
     fn from(f: ResizeFilter) -> Self {
         match f {
-            ResizeFilter::Nearest => Self::Nearest,
+            ResizeFilter::Nearest
+            | ResizeFilter::PixelArt
+            | ResizeFilter::Majority
+            | ResizeFilter::Scale2x
+            | ResizeFilter::Scale3x
+            | ResizeFilter::Xbr => Self::Nearest,
             ResizeFilter::Triangle => Self::Triangle,
             ResizeFilter::CatmullRom => Self::CatmullRom,
             ResizeFilter::Gaussian => Self::Gaussian,
@@ -109,3 +169,76 @@ impl From<ResizeFilter> for image::imageops::FilterType {
         }
     }
 }
+
+/// The colour space images are resampled in.
+///
+/// Averaging sRGB values darkens mixes of light and dark pixels, so
+/// downscaled photos come out too dark and fine detail turns muddy.
+/// Resampling in linear light avoids that at the cost of a conversion each
+/// way.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ResizeSpace {
+    /// Resample the gamma-encoded sRGB values directly.
+    #[default]
+    Srgb,
+    /// Convert to linear light, resample, and convert back.
+    Linear,
+    /// Like [`Linear`](Self::Linear), with colour premultiplied by alpha
+    /// while resampling so transparent pixels do not bleed dark fringes
+    /// into sprite edges.
+    LinearPremultiplied,
+}
+
+impl FromStr for ResizeSpace {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().replace('-', "").as_str() {
+            "srgb" | "gamma" => Ok(Self::Srgb),
+            "linear" => Ok(Self::Linear),
+            "linearpremultiplied" | "premultiplied" => Ok(Self::LinearPremultiplied),
+            _ => Err(format!(
+                "invalid resize space: '{s}'. (valid: srgb, linear, linear-premultiplied)"
+            )),
+        }
+    }
+}
+
+/// How an image is sized into its box of terminal cells.
+///
+/// The box is [`RenderOptions::width`](crate::RenderOptions::width) by
+/// [`RenderOptions::height`](crate::RenderOptions::height), or the terminal
+/// when neither is set.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FitMode {
+    /// Scale to the largest size that fits inside the box, keeping the
+    /// aspect ratio.
+    #[default]
+    Contain,
+    /// Scale to fill the whole box, keeping the aspect ratio, and crop what
+    /// overflows equally from both sides.
+    Cover,
+    /// Fill the box exactly, distorting the aspect ratio if needed.
+    Stretch,
+    /// Keep the source size: one source pixel per rendered pixel. Only
+    /// `max_width` and `max_height` still scale the image down.
+    Native,
+}
+
+impl FromStr for FitMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "contain" | "fit" => Ok(Self::Contain),
+            "cover" | "fill" | "crop" => Ok(Self::Cover),
+            "stretch" => Ok(Self::Stretch),
+            "native" | "none" => Ok(Self::Native),
+            _ => Err(format!(
+                "invalid fit mode: '{s}'. (valid: contain, cover, stretch, native)"
+            )),
+        }
+    }
+}
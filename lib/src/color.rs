@@ -19,31 +19,53 @@ fn get_oklab_palette() -> &'static [[f32; 3]; 256] {
 
 static SRGB_LUT: OnceLock<[f32; 256]> = OnceLock::new();
 
-fn srgb_to_linear_fast(c: u8) -> f32 {
+/// Convert an 8-bit sRGB channel to linear light through a lookup table.
+pub fn srgb_to_linear_fast(c: u8) -> f32 {
     SRGB_LUT.get_or_init(|| {
         let mut table = [0.0f32; 256];
 
         for (i, slot) in (0u8..=255).zip(table.iter_mut()) {
-            let val = f32::from(i) / 255.0;
-
-            *slot = if val <= 0.04045 {
-                val / 12.92
-            } else {
-                ((val + 0.055) / 1.055).powf(2.4)
-            };
+            *slot = srgb_to_linear(f32::from(i) / 255.0);
         }
 
         table
     })[usize::from(c)]
 }
 
-/// Convert linear RGB to Oklab [L, a, b].
+/// Convert an sRGB channel in `0.0..=1.0` to linear light.
+#[inline]
+pub fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Convert a linear-light channel back to sRGB, clamping to `0.0..=1.0`.
+#[inline]
+pub fn linear_to_srgb(c: f32) -> f32 {
+    let c = c.clamp(0.0, 1.0);
+    if c <= 0.003_130_8 {
+        c * 12.92
+    } else {
+        1.055_f32.mul_add(c.powf(1.0 / 2.4), -0.055)
+    }
+}
+
+/// Convert sRGB to Oklab [L, a, b].
 #[inline]
 pub fn rgb_to_oklab(red: u8, green: u8, blue: u8) -> [f32; 3] {
-    let red = srgb_to_linear_fast(red);
-    let green = srgb_to_linear_fast(green);
-    let blue = srgb_to_linear_fast(blue);
+    linear_to_oklab([
+        srgb_to_linear_fast(red),
+        srgb_to_linear_fast(green),
+        srgb_to_linear_fast(blue),
+    ])
+}
 
+/// Convert linear RGB to Oklab [L, a, b].
+#[inline]
+pub fn linear_to_oklab([red, green, blue]: [f32; 3]) -> [f32; 3] {
     let l = 0.412_221_46_f32.mul_add(red, 0.536_332_55_f32.mul_add(green, 0.051_445_995 * blue));
     let m = 0.211_903_5_f32.mul_add(red, 0.680_699_5_f32.mul_add(green, 0.107_396_96 * blue));
     let s = 0.088_302_46_f32.mul_add(red, 0.281_718_85_f32.mul_add(green, 0.629_978_7 * blue));
@@ -54,11 +76,52 @@ pub fn rgb_to_oklab(red: u8, green: u8, blue: u8) -> [f32; 3] {
 
     [
         0.210_454_26_f32.mul_add(l, 0.793_617_8_f32.mul_add(m, -0.004_072_047 * s)),
-        1.977_998_5_f32.mul_add(l, -2.428_592_2_f32.mul_add(m, 0.450_593_7 * s)),
+        1.977_998_5_f32.mul_add(l, (-2.428_592_2_f32).mul_add(m, 0.450_593_7 * s)),
         0.025_904_037_f32.mul_add(l, 0.782_771_77_f32.mul_add(m, -0.808_675_77 * s)),
     ]
 }
 
+/// Convert Oklab [L, a, b] to linear RGB. Out-of-gamut colours come back
+/// outside `0.0..=1.0`.
+pub fn oklab_to_linear([lightness, green_red, blue_yellow]: [f32; 3]) -> [f32; 3] {
+    let long =
+        0.396_337_78_f32.mul_add(green_red, 0.215_803_76_f32.mul_add(blue_yellow, lightness));
+    let medium = (-0.105_561_346_f32).mul_add(
+        green_red,
+        (-0.063_854_17_f32).mul_add(blue_yellow, lightness),
+    );
+    let short = (-0.089_484_18_f32).mul_add(
+        green_red,
+        (-1.291_485_5_f32).mul_add(blue_yellow, lightness),
+    );
+
+    let long = long * long * long;
+    let medium = medium * medium * medium;
+    let short = short * short * short;
+
+    [
+        4.076_741_7_f32.mul_add(
+            long,
+            (-3.307_711_6_f32).mul_add(medium, 0.230_969_94 * short),
+        ),
+        (-1.268_438_f32).mul_add(long, 2.609_757_4_f32.mul_add(medium, -0.341_319_38 * short)),
+        (-0.004_196_086_3_f32).mul_add(
+            long,
+            (-0.703_418_6_f32).mul_add(medium, 1.707_614_7 * short),
+        ),
+    ]
+}
+
+/// Convert Oklab [L, a, b] back to sRGB, clamping out-of-gamut values.
+#[expect(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    reason = "Channels are clamped to 0.0..=255.0 before the cast"
+)]
+pub fn oklab_to_rgb(lab: [f32; 3]) -> [u8; 3] {
+    oklab_to_linear(lab).map(|c| (linear_to_srgb(c) * 255.0).round().clamp(0.0, 255.0) as u8)
+}
+
 /// Perceptual distance between two colors in Oklab space.
 #[inline]
 pub fn oklab_distance(a: [f32; 3], b: [f32; 3]) -> f32 {
@@ -138,7 +201,12 @@ const fn generate_xterm_256() -> [[u8; 3]; 256] {
 /// This is O(256) but fast in practice since it's just float arithmetic.
 #[must_use]
 pub fn rgb_to_xterm256(r: u8, g: u8, b: u8) -> u8 {
-    let target = rgb_to_oklab(r, g, b);
+    oklab_to_xterm256(rgb_to_oklab(r, g, b))
+}
+
+/// Find the closest xterm-256 color index for an Oklab color.
+#[must_use]
+pub fn oklab_to_xterm256(target: [f32; 3]) -> u8 {
     let palette = get_oklab_palette();
     let mut best_idx = 0u8;
     let mut best_dist = f32::MAX;
@@ -154,6 +222,151 @@ pub fn rgb_to_xterm256(r: u8, g: u8, b: u8) -> u8 {
     best_idx
 }
 
+/// Find the closest entry of an arbitrary palette using Oklab distance.
+///
+/// Returns 0 for an empty palette.
+#[must_use]
+pub fn nearest_palette_index(r: u8, g: u8, b: u8, palette: &[[u8; 3]]) -> usize {
+    let target = rgb_to_oklab(r, g, b);
+    let mut best_idx = 0;
+    let mut best_dist = f32::MAX;
+
+    for (i, &[pr, pg, pb]) in palette.iter().enumerate() {
+        let dist = oklab_distance(target, rgb_to_oklab(pr, pg, pb));
+        if dist < best_dist {
+            best_dist = dist;
+            best_idx = i;
+        }
+    }
+
+    best_idx
+}
+
+/// Find the closest entry of a 16-color palette using Oklab distance.
+#[must_use]
+#[expect(
+    clippy::cast_possible_truncation,
+    reason = "The palette has 16 entries"
+)]
+pub fn rgb_to_ansi16(r: u8, g: u8, b: u8, palette: &[[u8; 3]; 16]) -> u8 {
+    nearest_palette_index(r, g, b, palette) as u8
+}
+
+/// Find the closest entry of the xterm grayscale ramp (232–255) by Oklab
+/// lightness.
+#[must_use]
+pub fn rgb_to_grayscale(r: u8, g: u8, b: u8) -> u8 {
+    let [lightness, ..] = rgb_to_oklab(r, g, b);
+    let ramp = &get_oklab_palette()[232..];
+    let mut best_idx = 232u8;
+    let mut best_dist = f32::MAX;
+
+    for (i, [candidate, ..]) in (232..=255u8).zip(ramp.iter()) {
+        let dist = (lightness - candidate).abs();
+        if dist < best_dist {
+            best_dist = dist;
+            best_idx = i;
+        }
+    }
+
+    best_idx
+}
+
+/// Maps a grid of cells to a palette with Floyd–Steinberg error diffusion in
+/// Oklab.
+///
+/// Snapping each cell on its own bands smooth gradients into flat steps.
+/// The diffuser instead carries every cell's perceptual error to the
+/// neighbours that haven't been quantized yet, so runs of cells alternate
+/// between the nearest palette entries and average out to the source colour.
+///
+/// Cells are fed in row-major order; a new row starts after `width` calls to
+/// [`quantize`](Self::quantize) or [`skip`](Self::skip).
+#[derive(Debug, Clone)]
+pub struct PaletteDiffuser {
+    /// The palette in Oklab, in index order.
+    palette: Vec<[f32; 3]>,
+    width: usize,
+    x: usize,
+    /// Error carried into the current row.
+    current: Vec<[f32; 3]>,
+    /// Error carried into the next row.
+    next: Vec<[f32; 3]>,
+}
+
+impl PaletteDiffuser {
+    /// Creates a diffuser for rows of `width` cells, mapping to `palette`.
+    #[must_use]
+    pub fn new(width: usize, palette: &[[u8; 3]]) -> Self {
+        Self {
+            palette: palette
+                .iter()
+                .map(|&[r, g, b]| rgb_to_oklab(r, g, b))
+                .collect(),
+            width,
+            x: 0,
+            current: vec![[0.0; 3]; width + 1],
+            next: vec![[0.0; 3]; width + 1],
+        }
+    }
+
+    /// Quantizes the next cell and returns its palette index.
+    pub fn quantize(&mut self, r: u8, g: u8, b: u8) -> usize {
+        let [lightness, green_red, blue_yellow] = rgb_to_oklab(r, g, b);
+        let [err_l, err_a, err_b] = self.current[self.x];
+        // Keep the carried error inside the gamut so saturated regions the
+        // palette can't reach don't smear it across the rest of the row.
+        let target = [
+            (lightness + err_l).clamp(0.0, 1.0),
+            (green_red + err_a).clamp(-0.5, 0.5),
+            (blue_yellow + err_b).clamp(-0.5, 0.5),
+        ];
+        let (idx, chosen) = self
+            .palette
+            .iter()
+            .copied()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| {
+                oklab_distance(target, *a).total_cmp(&oklab_distance(target, *b))
+            })
+            .unwrap_or((0, target));
+        let error = [0, 1, 2].map(|c| target[c] - chosen[c]);
+
+        let col = self.x;
+        let spread = |cell: &mut [f32; 3], weight: f32| {
+            for (carried, e) in cell.iter_mut().zip(error) {
+                *carried = e.mul_add(weight / 16.0, *carried);
+            }
+        };
+        if col + 1 < self.width {
+            spread(&mut self.current[col + 1], 7.0);
+            spread(&mut self.next[col + 1], 1.0);
+        }
+        if col > 0 {
+            spread(&mut self.next[col - 1], 3.0);
+        }
+        spread(&mut self.next[col], 5.0);
+
+        self.advance();
+        idx
+    }
+
+    /// Skips the next cell, such as a transparent one, dropping any error
+    /// carried into it.
+    pub fn skip(&mut self) {
+        self.advance();
+    }
+
+    fn advance(&mut self) {
+        self.x += 1;
+        if self.x >= self.width {
+            self.x = 0;
+            std::mem::swap(&mut self.current, &mut self.next);
+            self.next.fill([0.0; 3]);
+        }
+    }
+}
+
 /// Detects whether the terminal supports 24-bit truecolor.
 ///
 /// Checks `COLORTERM` env var first (most reliable), then falls back
@@ -209,6 +422,50 @@ mod tests {
         b >= r && b >= g
     }
 
+    #[test]
+    fn oklab_of_neutrals_has_no_chroma() {
+        for v in [0, 128, 255] {
+            let [_, a, b] = rgb_to_oklab(v, v, v);
+            assert!(a.abs() < 1e-3 && b.abs() < 1e-3, "{v}: a={a} b={b}");
+        }
+    }
+
+    #[test]
+    fn oklab_matches_reference_values() {
+        // Reference values from Björn Ottosson's Oklab post.
+        let cases = [
+            ([255, 255, 255], [1.0, 0.0, 0.0]),
+            ([255, 0, 0], [0.627_955, 0.224_863, 0.125_846]),
+            ([0, 255, 0], [0.866_440, -0.233_888, 0.179_498]),
+            ([0, 0, 255], [0.452_014, -0.032_457, -0.311_528]),
+        ];
+        for ([r, g, b], expected) in cases {
+            let lab = rgb_to_oklab(r, g, b);
+            for (got, want) in lab.into_iter().zip(expected) {
+                assert!(
+                    (got - want).abs() < 1e-3,
+                    "{r},{g},{b}: {lab:?} != {expected:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn oklab_roundtrips_to_rgb() {
+        for rgb in [
+            [0, 0, 0],
+            [255, 255, 255],
+            [255, 0, 0],
+            [12, 200, 99],
+            [128, 64, 250],
+        ] {
+            let back = oklab_to_rgb(rgb_to_oklab(rgb[0], rgb[1], rgb[2]));
+            for (a, b) in rgb.iter().zip(back) {
+                assert!(a.abs_diff(b) <= 1, "{rgb:?} -> {back:?}");
+            }
+        }
+    }
+
     #[test]
     fn maps_black_to_a_dark_entry() {
         let idx = rgb_to_xterm256(0, 0, 0);
@@ -250,4 +507,56 @@ mod tests {
         let rgb = XTERM_256[idx as usize];
         assert!(has_dominant_blue(rgb));
     }
+
+    #[test]
+    fn diffuser_dithers_between_palette_neighbours() {
+        // Halfway between two grey ramp entries: plain snapping picks one,
+        // diffusion alternates so the average stays close to the source.
+        let grey = 113;
+        let snapped = rgb_to_xterm256(grey, grey, grey);
+        let mut diffuser = PaletteDiffuser::new(8, &XTERM_256);
+        let row: Vec<usize> = (0..8)
+            .map(|_| diffuser.quantize(grey, grey, grey))
+            .collect();
+        assert!(row.iter().any(|&i| i != usize::from(snapped)), "{row:?}");
+        let mean = row.iter().map(|&i| f32::from(XTERM_256[i][0])).sum::<f32>() / 8.0;
+        assert!((mean - f32::from(grey)).abs() < 3.0, "{mean}");
+    }
+
+    #[test]
+    fn ansi16_and_grayscale_pick_nearest_entries() {
+        let vga = [
+            [0, 0, 0],
+            [170, 0, 0],
+            [0, 170, 0],
+            [170, 85, 0],
+            [0, 0, 170],
+            [170, 0, 170],
+            [0, 170, 170],
+            [170, 170, 170],
+            [85, 85, 85],
+            [255, 85, 85],
+            [85, 255, 85],
+            [255, 255, 85],
+            [85, 85, 255],
+            [255, 85, 255],
+            [85, 255, 255],
+            [255, 255, 255],
+        ];
+        assert_eq!(rgb_to_ansi16(160, 10, 10, &vga), 1);
+        assert_eq!(rgb_to_ansi16(240, 240, 90, &vga), 11);
+        assert_eq!(rgb_to_grayscale(0, 0, 0), 232);
+        assert_eq!(rgb_to_grayscale(128, 128, 128), 244);
+        assert_eq!(rgb_to_grayscale(255, 255, 255), 255);
+    }
+
+    #[test]
+    fn diffuser_keeps_exact_palette_colours() {
+        let [r, g, b] = XTERM_256[67];
+        let mut diffuser = PaletteDiffuser::new(3, &XTERM_256);
+        for _ in 0..6 {
+            let idx = diffuser.quantize(r, g, b);
+            assert_eq!(XTERM_256[idx], [r, g, b]);
+        }
+    }
 }
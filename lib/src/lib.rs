@@ -59,11 +59,14 @@ pub(crate) mod themes;
 
 // ── Core re-exports (always available) ──────────────────────────────────────
 pub use crate::{
-    cli_enums::{RenderStylePreset, ResizeFilter},
+    cli_enums::{FitMode, RenderStylePreset, ResizeFilter, ResizeSpace},
     indexer::{ImageEntry, build_index},
     render::{
-        CharsetMode, ColorMode, Density, RenderError, RenderOptions, RenderOptionsBuilder,
-        RenderStyle, get_terminal_size, write_ansi_art,
+        Adjustments, Ansi16Palette, AnsiEncoder, Attributes, Cell, CellGrid, CharsetMode,
+        ColorMode, ColorPalette, CustomCharset, Density, DitherMode, Encoder, HtmlEncoder,
+        RenderError, RenderOptions, RenderOptionsBuilder, RenderStyle, SymbolSet, ToneMap,
+        ansi_encoder, detect_cell_aspect, detect_cell_size, get_terminal_size, render_grid,
+        write_ansi_art, write_html,
     },
 };
 
@@ -72,5 +75,6 @@ pub use crate::{
 #[cfg_attr(docsrs, doc(cfg(feature = "rasterize")))]
 pub use crate::{
     rasterize::{rasterize_ansi, rasterize_ansi_with_theme},
+    render::{SvgEncoder, write_svg},
     themes::RasterTheme,
 };
@@ -6,11 +6,11 @@ use ab_glyph::{Font, FontRef, PxScale};
 use image::{Rgba, RgbaImage};
 
 const FONT_SIZE: f32 = 14.0;
-const CELL_W: u32 = 8;
+pub const CELL_W: u32 = 8;
 /// Must match `FONT_SIZE` to avoid per-row gaps.
-const CELL_H: u32 = 14;
+pub const CELL_H: u32 = 14;
 
-const DEFAULT_FONT: &[u8] = include_bytes!("../assets/IosevkaCharonMono-Regular.ttf");
+pub const DEFAULT_FONT: &[u8] = include_bytes!("../assets/IosevkaCharonMono-Regular.ttf");
 
 // ---------------------------------------------------------------------------
 // Public API
@@ -198,6 +198,7 @@ enum Cell {
 /// * SGR truecolor foreground (`ESC[38;2;R;G;Bm`)
 /// * SGR truecolor background (`ESC[48;2;R;G;Bm`)
 /// * SGR reset (`ESC[0m` or `ESC[m`) — resets fg to white, bg to theme color
+/// * SGR default colours (`ESC[39m` / `ESC[49m`) — reset fg or bg alone
 /// * Half-block characters `▀` / `▄` (classified as [`Cell::HalfBlock`] /
 ///   [`Cell::HalfBlockBot`] using the current fg/bg colors)
 /// * All other printable chars as [`Cell::Glyph`]
@@ -269,6 +270,7 @@ fn parse_ansi(input: &[u8], theme_bg: Rgba<u8>) -> Vec<Vec<Cell>> {
 /// * `38;2;R;G;B` → set foreground truecolor
 /// * `48;2;R;G;B` → set background truecolor
 /// * `0` or empty  → reset fg to white, bg to theme background
+/// * `39` / `49`    → reset only the foreground / background
 fn parse_color_params(params: &str, fg: &mut [u8; 3], bg: &mut Rgba<u8>, theme_bg: Rgba<u8>) {
     if params == "0" || params.is_empty() {
         *fg = [255, 255, 255];
@@ -291,6 +293,14 @@ fn parse_color_params(params: &str, fg: &mut [u8; 3], bg: &mut Rgba<u8>, theme_b
                 *fg = [parts[idx + 2], parts[idx + 3], parts[idx + 4]];
                 idx += 5;
             }
+            39 => {
+                *fg = [255, 255, 255];
+                idx += 1;
+            }
+            49 => {
+                *bg = theme_bg;
+                idx += 1;
+            }
             48 if parts.get(idx + 1) == Some(&2) && parts.len() > idx + 4 => {
                 let [r, g, b] = [parts[idx + 2], parts[idx + 3], parts[idx + 4]];
                 *bg = Rgba([r, g, b, 255]);
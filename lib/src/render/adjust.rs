@@ -0,0 +1,216 @@
+//! Tonal and colour adjustments applied by [`RenderOptions::prepare_image`].
+//!
+//! [`RenderOptions::prepare_image`]: super::RenderOptions::prepare_image
+
+use image::{Rgba32FImage, RgbaImage};
+
+use crate::color::{linear_to_oklab, linear_to_srgb, oklab_to_linear, srgb_to_linear};
+
+/// Brightness, contrast, gamma, saturation, hue rotation and inversion.
+///
+/// The default leaves the image untouched. Adjustments run in a fixed
+/// order: brightness, contrast, gamma, saturation, hue, then invert.
+/// Brightness, contrast and gamma act on each sRGB channel; saturation and
+/// hue act on chroma in Oklab, so lightness is preserved. Alpha is never
+/// changed.
+///
+/// ```
+/// use px2ansi::Adjustments;
+///
+/// let adjust = Adjustments::default().contrast(1.2).saturation(0.0);
+/// assert!(!adjust.is_identity());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Adjustments {
+    brightness: f32,
+    contrast: f32,
+    gamma: f32,
+    saturation: f32,
+    hue: f32,
+    invert: bool,
+}
+
+impl Default for Adjustments {
+    fn default() -> Self {
+        Self {
+            brightness: 1.0,
+            contrast: 1.0,
+            gamma: 1.0,
+            saturation: 1.0,
+            hue: 0.0,
+            invert: false,
+        }
+    }
+}
+
+impl Adjustments {
+    /// Multiplies every channel by `factor`; `1.0` is unchanged.
+    #[must_use]
+    pub const fn brightness(mut self, factor: f32) -> Self {
+        self.brightness = factor;
+        self
+    }
+
+    /// Scales each channel's distance from mid-grey by `factor`; `1.0` is
+    /// unchanged and `0.0` gives flat grey.
+    #[must_use]
+    pub const fn contrast(mut self, factor: f32) -> Self {
+        self.contrast = factor;
+        self
+    }
+
+    /// Raises each channel to `1 / gamma`, so values above `1.0` lift the
+    /// midtones. Values that are not positive are ignored.
+    #[must_use]
+    pub const fn gamma(mut self, gamma: f32) -> Self {
+        self.gamma = gamma;
+        self
+    }
+
+    /// Scales chroma by `factor`; `0.0` is grayscale and `1.0` is unchanged.
+    #[must_use]
+    pub const fn saturation(mut self, factor: f32) -> Self {
+        self.saturation = factor;
+        self
+    }
+
+    /// Rotates hue by `degrees` around the Oklab lightness axis.
+    #[must_use]
+    pub const fn hue_rotate(mut self, degrees: f32) -> Self {
+        self.hue = degrees;
+        self
+    }
+
+    /// Inverts every channel after the other adjustments.
+    #[must_use]
+    pub const fn invert(mut self, invert: bool) -> Self {
+        self.invert = invert;
+        self
+    }
+
+    /// Returns `true` if these adjustments leave every pixel unchanged.
+    #[must_use]
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Adjusts one sRGB colour with channels in `0.0..=1.0`.
+    fn apply_rgb(&self, rgb: [f32; 3]) -> [f32; 3] {
+        let gamma = if self.gamma > 0.0 { self.gamma } else { 1.0 };
+        let mut rgb = rgb.map(|c| {
+            let c = (c * self.brightness).clamp(0.0, 1.0);
+            let c = (c - 0.5).mul_add(self.contrast, 0.5).clamp(0.0, 1.0);
+            c.powf(gamma.recip())
+        });
+
+        if (self.saturation - 1.0).abs() > f32::EPSILON || self.hue.abs() > f32::EPSILON {
+            let [lightness, a, b] = linear_to_oklab(rgb.map(srgb_to_linear));
+            let (sin, cos) = self.hue.to_radians().sin_cos();
+            let a = a * self.saturation;
+            let b = b * self.saturation;
+            let lab = [lightness, a.mul_add(cos, -b * sin), a.mul_add(sin, b * cos)];
+            rgb = oklab_to_linear(lab).map(linear_to_srgb);
+        }
+
+        if self.invert {
+            rgb = rgb.map(|c| 1.0 - c);
+        }
+        rgb
+    }
+
+    /// Adjusts every pixel of a floating-point image in place.
+    pub(super) fn apply_f32(&self, img: &mut Rgba32FImage) {
+        for px in img.pixels_mut() {
+            let [r, g, b] = self.apply_rgb([px[0], px[1], px[2]].map(|c| c.clamp(0.0, 1.0)));
+            px.0 = [r, g, b, px[3]];
+        }
+    }
+
+    /// Adjusts every pixel of an 8-bit image in place.
+    #[expect(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        reason = "Channels are clamped to 0.0..=255.0 before the cast"
+    )]
+    pub(super) fn apply_u8(&self, img: &mut RgbaImage) {
+        for px in img.pixels_mut() {
+            let rgb = [px[0], px[1], px[2]].map(|c| f32::from(c) / 255.0);
+            let [r, g, b] = self
+                .apply_rgb(rgb)
+                .map(|c| (c * 255.0).round().clamp(0.0, 255.0) as u8);
+            px.0 = [r, g, b, px[3]];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    fn adjust(adjust: Adjustments, rgb: [u8; 3]) -> [u8; 3] {
+        let mut img = RgbaImage::from_pixel(1, 1, Rgba([rgb[0], rgb[1], rgb[2], 255]));
+        adjust.apply_u8(&mut img);
+        let [r, g, b, _] = img.get_pixel(0, 0).0;
+        [r, g, b]
+    }
+
+    #[test]
+    fn default_is_identity() {
+        assert!(Adjustments::default().is_identity());
+        for rgb in [[0, 0, 0], [255, 255, 255], [200, 30, 90], [17, 128, 240]] {
+            assert_eq!(adjust(Adjustments::default(), rgb), rgb);
+        }
+    }
+
+    #[test]
+    fn brightness_and_contrast_act_per_channel() {
+        let brighter = Adjustments::default().brightness(2.0);
+        assert_eq!(adjust(brighter, [50, 100, 200]), [100, 200, 255]);
+        let flat = Adjustments::default().contrast(0.0);
+        assert_eq!(adjust(flat, [0, 90, 255]), [128, 128, 128]);
+        let punchy = Adjustments::default().contrast(2.0);
+        assert_eq!(adjust(punchy, [64, 128, 192]), [1, 129, 255]);
+    }
+
+    #[test]
+    fn gamma_lifts_midtones_but_keeps_the_ends() {
+        let lifted = adjust(Adjustments::default().gamma(2.2), [0, 64, 255]);
+        assert_eq!(lifted[0], 0);
+        assert!(lifted[1] > 64, "{lifted:?}");
+        assert_eq!(lifted[2], 255);
+        let ignored = Adjustments::default().gamma(0.0);
+        assert_eq!(adjust(ignored, [0, 64, 255]), [0, 64, 255]);
+    }
+
+    #[test]
+    fn zero_saturation_is_grey_with_the_same_lightness() {
+        let [r, g, b] = adjust(Adjustments::default().saturation(0.0), [200, 40, 40]);
+        assert!(r.abs_diff(g) <= 1 && g.abs_diff(b) <= 1, "{r} {g} {b}");
+        let before = crate::color::rgb_to_oklab(200, 40, 40)[0];
+        let after = crate::color::rgb_to_oklab(r, g, b)[0];
+        assert!((before - after).abs() < 0.01, "{before} {after}");
+    }
+
+    #[test]
+    fn hue_rotation_moves_red_towards_green_and_back() {
+        let [r, g, _] = adjust(Adjustments::default().hue_rotate(120.0), [220, 30, 30]);
+        assert!(g > r, "{r} {g}");
+        let full = adjust(Adjustments::default().hue_rotate(360.0), [220, 30, 30]);
+        assert!(
+            full.iter()
+                .zip([220, 30, 30])
+                .all(|(a, b)| a.abs_diff(b) <= 1)
+        );
+    }
+
+    #[test]
+    fn invert_runs_last_and_keeps_alpha() {
+        let mut img = RgbaImage::from_pixel(1, 1, Rgba([10, 100, 250, 7]));
+        Adjustments::default()
+            .brightness(0.0)
+            .invert(true)
+            .apply_u8(&mut img);
+        assert_eq!(img.get_pixel(0, 0).0, [255, 255, 255, 7]);
+    }
+}
@@ -0,0 +1,78 @@
+//! Minimal standard-alphabet base64 encoder for terminal graphics payloads.
+//!
+//! Graphics protocols only ever need to *send* base64, so pulling in a full
+//! crate for it is not worth the dependency.
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encodes `data` as padded base64 (RFC 4648, standard alphabet).
+pub(super) fn encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+
+    for chunk in data.chunks(3) {
+        let b0 = chunk[0];
+        let b1 = chunk.get(1).copied().unwrap_or(0);
+        let b2 = chunk.get(2).copied().unwrap_or(0);
+        let triple = (u32::from(b0) << 16) | (u32::from(b1) << 8) | u32::from(b2);
+
+        out.push(char::from(ALPHABET[(triple >> 18) as usize & 63]));
+        out.push(char::from(ALPHABET[(triple >> 12) as usize & 63]));
+        if chunk.len() > 1 {
+            out.push(char::from(ALPHABET[(triple >> 6) as usize & 63]));
+        } else {
+            out.push('=');
+        }
+        if chunk.len() > 2 {
+            out.push(char::from(ALPHABET[triple as usize & 63]));
+        } else {
+            out.push('=');
+        }
+    }
+
+    out
+}
+
+/// Decodes padded base64 back into bytes. Only used to verify encoder output.
+#[cfg(test)]
+pub(super) fn decode(text: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(text.len() / 4 * 3);
+    for quad in text.as_bytes().chunks(4) {
+        if quad.len() != 4 {
+            return None;
+        }
+        let mut triple = 0u32;
+        let mut pad = 0;
+        for &c in quad {
+            let v = if c == b'=' {
+                pad += 1;
+                0
+            } else {
+                u32::try_from(ALPHABET.iter().position(|&a| a == c)?).ok()?
+            };
+            triple = (triple << 6) | v;
+        }
+        let bytes = triple.to_be_bytes();
+        out.extend_from_slice(&bytes[1..4 - pad]);
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_rfc4648_vectors() {
+        assert_eq!(encode(b""), "");
+        assert_eq!(encode(b"f"), "Zg==");
+        assert_eq!(encode(b"fo"), "Zm8=");
+        assert_eq!(encode(b"foo"), "Zm9v");
+        assert_eq!(encode(b"foobar"), "Zm9vYmFy");
+    }
+
+    #[test]
+    fn decode_roundtrips_encode() {
+        let data: Vec<u8> = (0..=255).collect();
+        assert_eq!(decode(&encode(&data)), Some(data));
+    }
+}
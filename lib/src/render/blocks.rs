@@ -0,0 +1,364 @@
+//! Sub-cell block glyph fitting.
+//!
+//! Each terminal cell is split into a small grid of sub-pixels and covered by
+//! a block glyph whose filled parts use the foreground colour and whose empty
+//! parts use the background colour. Fitting picks the glyph and the two colours
+//! that best reproduce the sub-pixels of the cell.
+//!
+//! Sub-pixels are numbered row-major, two per row, so bit `row * 2 + col` of
+//! a mask is set when that sub-pixel is drawn in the foreground colour.
+
+/// Quadrant glyphs indexed by mask. Bits: top-left = 1, top-right = 2,
+/// bottom-left = 4, bottom-right = 8.
+const QUADRANT_GLYPHS: [char; 16] = [
+    ' ', '▘', '▝', '▀', '▖', '▌', '▞', '▛', '▗', '▚', '▐', '▜', '▄', '▙', '▟', '█',
+];
+
+/// Octant masks that Unicode 16 encodes outside the octant range because an
+/// equivalent block glyph already existed. Sorted by mask.
+const OCTANT_EXISTING: [(u8, char); 26] = [
+    (0x00, ' '),
+    (0x01, '\u{1CEA8}'),
+    (0x02, '\u{1CEAB}'),
+    (0x03, '\u{1FB82}'),
+    (0x05, '▘'),
+    (0x0A, '▝'),
+    (0x0F, '▀'),
+    (0x14, '\u{1FBE6}'),
+    (0x28, '\u{1FBE7}'),
+    (0x3F, '\u{1FB85}'),
+    (0x40, '\u{1CEA3}'),
+    (0x50, '▖'),
+    (0x55, '▌'),
+    (0x5A, '▞'),
+    (0x5F, '▛'),
+    (0x80, '\u{1CEA0}'),
+    (0xA0, '▗'),
+    (0xA5, '▚'),
+    (0xAA, '▐'),
+    (0xAF, '▜'),
+    (0xC0, '▂'),
+    (0xF0, '▄'),
+    (0xF5, '▙'),
+    (0xFA, '▟'),
+    (0xFC, '▆'),
+    (0xFF, '█'),
+];
+
+/// Sub-cell grid used by a block rendering mode. All shapes are two
+/// sub-pixels wide.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum BlockShape {
+    /// 2x2 quadrant blocks (U+2596..U+259F).
+    Quadrant,
+    /// 2x3 sextants from Symbols for Legacy Computing (U+1FB00..U+1FB3B).
+    Sextant,
+    /// 2x4 octants from Unicode 16 (U+1CD00..U+1CDE5).
+    Octant,
+}
+
+impl BlockShape {
+    /// Number of sub-pixel rows per cell.
+    pub(super) const fn rows(self) -> u32 {
+        match self {
+            Self::Quadrant => 2,
+            Self::Sextant => 3,
+            Self::Octant => 4,
+        }
+    }
+
+    /// Returns the glyph that draws exactly the sub-pixels set in `mask`.
+    pub(super) fn glyph(self, mask: u8) -> char {
+        match self {
+            Self::Quadrant => QUADRANT_GLYPHS[usize::from(mask & 0x0F)],
+            Self::Sextant => sextant_glyph(mask & 0x3F),
+            Self::Octant => octant_glyph(mask),
+        }
+    }
+}
+
+/// Returns the shape and mask of a block glyph, the inverse of
+/// [`BlockShape::glyph`], or `None` for any other character. Glyphs that
+/// several shapes share, like `▀` and `█`, come back as quadrants, and a
+/// space is the empty quadrant.
+#[cfg(feature = "rasterize")]
+pub(super) fn block_mask(glyph: char) -> Option<(BlockShape, u8)> {
+    if let Some(mask) = QUADRANT_GLYPHS.iter().position(|&g| g == glyph) {
+        return u8::try_from(mask).ok().map(|m| (BlockShape::Quadrant, m));
+    }
+    let shape = match u32::from(glyph) {
+        0x1FB00..=0x1FB3B => BlockShape::Sextant,
+        0x1CD00..=0x1CDE5 => BlockShape::Octant,
+        _ if OCTANT_EXISTING.iter().any(|&(_, g)| g == glyph) => BlockShape::Octant,
+        _ => return None,
+    };
+    (0..=u8::MAX)
+        .find(|&m| shape.glyph(m) == glyph)
+        .map(|m| (shape, m))
+}
+
+/// Sextants are encoded in mask order, skipping the three masks that are
+/// already covered by `▌`, `▐` and `█` (and the empty cell).
+fn sextant_glyph(mask: u8) -> char {
+    match mask {
+        0 => ' ',
+        21 => '▌',
+        42 => '▐',
+        63 => '█',
+        m => {
+            let skipped = u32::from(m > 21) + u32::from(m > 42);
+            char::from_u32(0x1FB00 + u32::from(m) - 1 - skipped).unwrap_or('█')
+        }
+    }
+}
+
+/// Octants are encoded in mask order, skipping the masks in
+/// [`OCTANT_EXISTING`].
+fn octant_glyph(mask: u8) -> char {
+    match OCTANT_EXISTING.binary_search_by_key(&mask, |&(m, _)| m) {
+        Ok(i) => OCTANT_EXISTING[i].1,
+        Err(skipped) => {
+            let index = u32::from(mask) - u32::try_from(skipped).unwrap_or(0);
+            char::from_u32(0x1CD00 + index).unwrap_or('█')
+        }
+    }
+}
+
+/// Luma above which a sub-pixel counts as "ink" when colour is disabled.
+const MONO_THRESHOLD: u32 = 128;
+
+/// A fitted cell: which sub-pixels are filled and the two colours to use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct BlockCell {
+    /// Bitmask of sub-pixels drawn with the foreground colour.
+    pub mask: u8,
+    /// Foreground colour, or `None` when no sub-pixel is filled.
+    pub fg: Option<[u8; 3]>,
+    /// Background colour, or `None` to keep the terminal's default background.
+    pub bg: Option<[u8; 3]>,
+}
+
+/// Fits up to eight row-major sub-pixels to a two-colour block. `None`
+/// entries are transparent or outside the image.
+///
+/// Fully opaque cells get the two-colour split with the lowest squared error,
+/// found by trying every mask, and oriented so the brighter group is the
+/// foreground. Cells with transparent sub-pixels fill only the opaque ones and
+/// leave the default background.
+pub(super) fn fit_cell(px: &[Option<[u8; 3]>]) -> BlockCell {
+    debug_assert!(px.len() <= 8, "masks are a single byte");
+    let full = u8::MAX >> (8 - px.len().min(8));
+    let opaque = px
+        .iter()
+        .enumerate()
+        .filter(|(_, p)| p.is_some())
+        .fold(0u8, |m, (i, _)| m | (1 << i));
+
+    if opaque == 0 {
+        return BlockCell {
+            mask: 0,
+            fg: None,
+            bg: None,
+        };
+    }
+    if opaque != full {
+        return BlockCell {
+            mask: opaque,
+            fg: Some(mean(px, opaque)),
+            bg: None,
+        };
+    }
+
+    // Maximising Σ |S_g|² / n_g over both groups minimises the within-group
+    // squared error. Scaling by 840 (lcm of 1..=8) keeps the score integral.
+    let score = |mask: u8| -> u64 {
+        let group = |m: u8| {
+            let n = u64::from(m.count_ones());
+            if n == 0 {
+                return 0;
+            }
+            let s = sums(px, m);
+            s.iter().map(|&c| c * c).sum::<u64>() * 840 / n
+        };
+        group(mask) + group(!mask & full)
+    };
+    let mut best = (1..=full).max_by_key(|&m| score(m)).unwrap_or(full);
+
+    let unset = !best & full;
+    if unset != 0 && luma(mean(px, best)) < luma(mean(px, unset)) {
+        best = unset;
+    }
+    let unset = !best & full;
+    BlockCell {
+        mask: best,
+        fg: (best != 0).then(|| mean(px, best)),
+        bg: (unset != 0).then(|| mean(px, unset)),
+    }
+}
+
+/// Thresholds each opaque sub-pixel by luma for output without colour.
+pub(super) fn mono_mask(px: &[Option<[u8; 3]>]) -> u8 {
+    px.iter()
+        .enumerate()
+        .filter(|(_, p)| p.is_some_and(|c| luma(c) >= MONO_THRESHOLD))
+        .fold(0u8, |m, (i, _)| m | (1 << i))
+}
+
+/// Per-channel sums over the sub-pixels selected by `mask`.
+fn sums(px: &[Option<[u8; 3]>], mask: u8) -> [u64; 3] {
+    let mut s = [0u64; 3];
+    for c in px
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| mask & (1 << i) != 0)
+        .filter_map(|(_, p)| *p)
+    {
+        for (acc, v) in s.iter_mut().zip(c) {
+            *acc += u64::from(v);
+        }
+    }
+    s
+}
+
+/// Rounded mean colour of the sub-pixels selected by `mask`.
+fn mean(px: &[Option<[u8; 3]>], mask: u8) -> [u8; 3] {
+    let n = u64::from(mask.count_ones()).max(1);
+    sums(px, mask).map(|s| u8::try_from((s + n / 2) / n).unwrap_or(u8::MAX))
+}
+
+/// Integer Rec. 709 luma, matching the braille renderer.
+fn luma([r, g, b]: [u8; 3]) -> u32 {
+    (2126 * u32::from(r) + 7152 * u32::from(g) + 722 * u32::from(b)) / 10000
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const W: Option<[u8; 3]> = Some([255, 255, 255]);
+    const K: Option<[u8; 3]> = Some([0, 0, 0]);
+
+    #[test]
+    fn glyph_table_matches_bit_layout() {
+        assert_eq!(QUADRANT_GLYPHS[0b0001], '▘');
+        assert_eq!(QUADRANT_GLYPHS[0b0010], '▝');
+        assert_eq!(QUADRANT_GLYPHS[0b0100], '▖');
+        assert_eq!(QUADRANT_GLYPHS[0b1000], '▗');
+        assert_eq!(QUADRANT_GLYPHS[0b0011], '▀');
+        assert_eq!(QUADRANT_GLYPHS[0b0101], '▌');
+        assert_eq!(QUADRANT_GLYPHS[0b1001], '▚');
+    }
+
+    #[test]
+    fn sextant_glyphs_skip_existing_blocks() {
+        let g = |m| BlockShape::Sextant.glyph(m);
+        assert_eq!(g(0b00_0001), '\u{1FB00}');
+        assert_eq!(g(0b01_0101), '▌');
+        assert_eq!(g(0b01_0110), '\u{1FB14}'); // first mask after the ▌ skip
+        assert_eq!(g(0b10_1010), '▐');
+        assert_eq!(g(0b11_1110), '\u{1FB3B}');
+        assert_eq!(g(0b11_1111), '█');
+    }
+
+    #[cfg(feature = "rasterize")]
+    #[test]
+    fn block_mask_inverts_every_glyph() {
+        for shape in [
+            BlockShape::Quadrant,
+            BlockShape::Sextant,
+            BlockShape::Octant,
+        ] {
+            let masks = if shape == BlockShape::Octant {
+                0..=255
+            } else {
+                0..=(1u8 << (shape.rows() * 2)) - 1
+            };
+            for mask in masks {
+                let (found, found_mask) = block_mask(shape.glyph(mask)).expect("block glyph");
+                assert_eq!(found.glyph(found_mask), shape.glyph(mask));
+            }
+        }
+        assert_eq!(block_mask('▀'), Some((BlockShape::Quadrant, 0b0011)));
+        assert_eq!(block_mask('▆'), Some((BlockShape::Octant, 0xFC)));
+        assert_eq!(block_mask('a'), None);
+        assert_eq!(block_mask('⣿'), None);
+    }
+
+    #[test]
+    fn octant_glyphs_cover_every_mask_once() {
+        let g = |m| BlockShape::Octant.glyph(m);
+        assert_eq!(g(0x04), '\u{1CD00}');
+        assert_eq!(g(0xFE), '\u{1CDE5}');
+        assert_eq!(g(0x0F), '▀');
+        assert_eq!(g(0x55), '▌');
+
+        let all: std::collections::HashSet<char> = (0..=255u8).map(g).collect();
+        assert_eq!(all.len(), 256);
+        let octants = all
+            .iter()
+            .filter(|&&c| ('\u{1CD00}'..='\u{1CDE5}').contains(&c))
+            .count();
+        assert_eq!(octants, 230);
+    }
+
+    #[test]
+    fn octant_fit_separates_a_vertical_edge() {
+        // Left column white, right column black over all four rows.
+        let px = [W, K, W, K, W, K, W, K];
+        let cell = fit_cell(&px);
+        assert_eq!(BlockShape::Octant.glyph(cell.mask), '▌');
+        assert_eq!((cell.fg, cell.bg), (W, K));
+    }
+
+    #[test]
+    fn two_colour_cell_splits_exactly() {
+        let cell = fit_cell(&[W, K, K, W]);
+        assert_eq!(BlockShape::Quadrant.glyph(cell.mask), '▚');
+        assert_eq!(cell.fg, W);
+        assert_eq!(cell.bg, K);
+    }
+
+    #[test]
+    fn brighter_group_is_foreground() {
+        let cell = fit_cell(&[K, K, K, W]);
+        assert_eq!(BlockShape::Quadrant.glyph(cell.mask), '▗');
+        assert_eq!(cell.fg, W);
+    }
+
+    #[test]
+    fn uniform_cell_is_a_full_block() {
+        let c = Some([10, 200, 30]);
+        let cell = fit_cell(&[c; 4]);
+        assert_eq!(cell.mask, 0b1111);
+        assert_eq!(cell.fg, c);
+        assert_eq!(cell.bg, None);
+    }
+
+    #[test]
+    fn three_colours_pick_the_lowest_error_split() {
+        // Two near-whites and two clearly different darks: the whites belong
+        // together, the darks get averaged.
+        let cell = fit_cell(&[
+            Some([250, 250, 250]),
+            Some([240, 240, 240]),
+            Some([0, 0, 100]),
+            Some([0, 0, 60]),
+        ]);
+        assert_eq!(BlockShape::Quadrant.glyph(cell.mask), '▀');
+        assert_eq!(cell.fg, Some([245, 245, 245]));
+        assert_eq!(cell.bg, Some([0, 0, 80]));
+    }
+
+    #[test]
+    fn transparent_subpixels_keep_default_background() {
+        let cell = fit_cell(&[W, None, None, K]);
+        assert_eq!(cell.mask, 0b1001);
+        assert_eq!(cell.bg, None);
+        assert_eq!(fit_cell(&[None; 4]).fg, None);
+    }
+
+    #[test]
+    fn mono_mask_thresholds_luma() {
+        assert_eq!(mono_mask(&[W, K, None, Some([200, 200, 200])]), 0b1001);
+    }
+}
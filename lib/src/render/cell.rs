@@ -0,0 +1,166 @@
+//! Terminal cell size detection for [`RenderOptions::cell_aspect`].
+//!
+//! The size comes from the pixel fields of the `TIOCGWINSZ` ioctl when the
+//! terminal fills them in, and otherwise from the terminal's reply to the
+//! `CSI 16 t` (report cell size) query on `/dev/tty`.
+//!
+//! [`RenderOptions::cell_aspect`]: super::RenderOptions::cell_aspect
+
+/// How long to wait for the terminal to answer `CSI 16 t`.
+#[cfg(unix)]
+const QUERY_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(200);
+
+/// Returns the size of one terminal cell in pixels as `(width, height)`, or
+/// `None` if the terminal does not report it.
+///
+/// Tries the `TIOCGWINSZ` pixel fields of stderr, stdout and stdin first,
+/// then asks the controlling terminal with `CSI 16 t`, waiting at most
+/// 200 ms for a reply. Always `None` on non-Unix platforms.
+#[must_use]
+pub fn detect_cell_size() -> Option<(u32, u32)> {
+    #[cfg(unix)]
+    {
+        winsize_cell_size().or_else(query_cell_size)
+    }
+    #[cfg(not(unix))]
+    {
+        None
+    }
+}
+
+/// Returns the height-to-width ratio of a terminal cell, as detected by
+/// [`detect_cell_size`].
+#[must_use]
+#[expect(
+    clippy::cast_precision_loss,
+    reason = "Cell sizes are a few dozen pixels"
+)]
+pub fn detect_cell_aspect() -> Option<f32> {
+    detect_cell_size().map(|(w, h)| h as f32 / w as f32)
+}
+
+/// Reads the cell size from the pixel fields of `TIOCGWINSZ`.
+#[cfg(unix)]
+fn winsize_cell_size() -> Option<(u32, u32)> {
+    use std::os::unix::io::AsRawFd;
+
+    let fds = [
+        std::io::stderr().as_raw_fd(),
+        std::io::stdout().as_raw_fd(),
+        std::io::stdin().as_raw_fd(),
+    ];
+    fds.into_iter().find_map(|fd| {
+        // SAFETY: `winsize` is plain data, and `TIOCGWINSZ` only writes into
+        // it; an fd that is not a terminal makes the call fail.
+        let mut ws: libc::winsize = unsafe { std::mem::zeroed() };
+        let ok = unsafe { libc::ioctl(fd, libc::TIOCGWINSZ, &raw mut ws) } == 0;
+        (ok && ws.ws_col > 0 && ws.ws_row > 0 && ws.ws_xpixel > 0 && ws.ws_ypixel > 0).then(|| {
+            (
+                (u32::from(ws.ws_xpixel) / u32::from(ws.ws_col)).max(1),
+                (u32::from(ws.ws_ypixel) / u32::from(ws.ws_row)).max(1),
+            )
+        })
+    })
+}
+
+/// Asks the controlling terminal for its cell size with `CSI 16 t`.
+///
+/// Echo and line buffering are turned off on `/dev/tty` while waiting for
+/// the reply and restored before returning.
+#[cfg(unix)]
+fn query_cell_size() -> Option<(u32, u32)> {
+    use std::io::Write;
+    use std::os::unix::io::AsRawFd;
+
+    let mut tty = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+        .ok()?;
+    let fd = tty.as_raw_fd();
+
+    // SAFETY: `termios` is plain data filled in by `tcgetattr`, and `fd`
+    // stays open for as long as `tty` is alive.
+    let mut saved: libc::termios = unsafe { std::mem::zeroed() };
+    if unsafe { libc::tcgetattr(fd, &raw mut saved) } != 0 {
+        return None;
+    }
+    let mut raw = saved;
+    raw.c_lflag &= !(libc::ICANON | libc::ECHO);
+    // Let each read return after 100 ms without input.
+    raw.c_cc[libc::VMIN] = 0;
+    raw.c_cc[libc::VTIME] = 1;
+    // SAFETY: as above; `raw` is a copy of the settings just read.
+    if unsafe { libc::tcsetattr(fd, libc::TCSANOW, &raw const raw) } != 0 {
+        return None;
+    }
+
+    let reply = tty
+        .write_all(b"\x1b[16t")
+        .and_then(|()| tty.flush())
+        .ok()
+        .map(|()| read_reply(&mut tty));
+
+    // SAFETY: restores the settings read above on the same open fd.
+    unsafe { libc::tcsetattr(fd, libc::TCSANOW, &raw const saved) };
+    parse_cell_size_report(&reply?)
+}
+
+/// Reads from `tty` until a `t` ends the report or [`QUERY_TIMEOUT`] passes.
+#[cfg(unix)]
+fn read_reply(tty: &mut std::fs::File) -> Vec<u8> {
+    use std::io::Read;
+
+    let deadline = std::time::Instant::now() + QUERY_TIMEOUT;
+    let mut reply = Vec::new();
+    let mut byte = [0u8; 1];
+    while std::time::Instant::now() < deadline {
+        match tty.read(&mut byte) {
+            Ok(1) => {
+                reply.push(byte[0]);
+                if byte[0] == b't' {
+                    break;
+                }
+            }
+            Ok(_) => {}
+            Err(_) => break,
+        }
+    }
+    reply
+}
+
+/// Parses a `CSI 6 ; height ; width t` cell size report into
+/// `(width, height)`.
+fn parse_cell_size_report(reply: &[u8]) -> Option<(u32, u32)> {
+    let reply = std::str::from_utf8(reply).ok()?;
+    let start = reply.find("\x1b[6;")?;
+    let body = reply[start + 4..].strip_suffix('t')?;
+    let (height, width) = body.split_once(';')?;
+    let (width, height) = (width.parse().ok()?, height.parse().ok()?);
+    (width > 0 && height > 0).then_some((width, height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_a_cell_size_report() {
+        assert_eq!(parse_cell_size_report(b"\x1b[6;20;10t"), Some((10, 20)));
+        assert_eq!(parse_cell_size_report(b"junk\x1b[6;17;8t"), Some((8, 17)));
+    }
+
+    #[test]
+    fn rejects_malformed_reports() {
+        for reply in [
+            &b""[..],
+            b"\x1b[6;20t",
+            b"\x1b[6;20;10",
+            b"\x1b[4;600;800t",
+            b"\x1b[6;0;10t",
+            b"\x1b[6;a;10t",
+        ] {
+            assert_eq!(parse_cell_size_report(reply), None, "{reply:?}");
+        }
+    }
+}
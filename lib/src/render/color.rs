@@ -1,6 +1,4 @@
-#[cfg(feature = "parallel")]
-use std::fmt::Write as FmtWrite;
-use std::io::Write;
+use std::fmt;
 
 use super::types::ColorMode;
 
@@ -16,101 +14,145 @@ pub(super) enum ColorState {
     TrueColor(u8, u8, u8),
     /// Xterm-style 256 color index
     Ansi256(u8),
+    /// Basic SGR color index (0–15), emitted as 30–37/90–97 or 40–47/100–107
+    Ansi16(u8),
+    /// Xterm grayscale ramp index (232–255)
+    Grayscale(u8),
 }
 
-/// Writes a single character (glyph) with the specified foreground color.
-///
-/// This function is "state-aware"—it checks `last` to see if a color
-/// change is actually necessary before writing the ANSI sequence.
-pub(super) fn write_colored_glyph<W: Write>(
-    writer: &mut W,
-    glyph: &str,
-    r: u8,
-    g: u8,
-    b: u8,
-    color_mode: ColorMode,
-    last: &mut ColorState,
-) -> std::io::Result<()> {
-    match color_mode {
-        ColorMode::TrueColor => {
-            // Only emit the 24-bit color sequence (\x1b[38;2;R;G;Bm) if the color changed
-            if !matches!(last, ColorState::TrueColor(lr, lg, lb) if *lr == r && *lg == g && *lb == b)
-            {
-                write!(writer, "\x1b[38;2;{r};{g};{b}m")?;
-                *last = ColorState::TrueColor(r, g, b);
+impl ColorState {
+    /// Quantizes an RGB color to the state the given mode would emit.
+    pub(super) fn from_rgb([r, g, b]: [u8; 3], color_mode: ColorMode) -> Self {
+        match color_mode {
+            ColorMode::TrueColor => Self::TrueColor(r, g, b),
+            ColorMode::Ansi256 => Self::Ansi256(crate::color::rgb_to_xterm256(r, g, b)),
+            ColorMode::Ansi16(palette) => {
+                Self::Ansi16(crate::color::rgb_to_ansi16(r, g, b, palette.colors()))
+            }
+            ColorMode::Grayscale => Self::Grayscale(crate::color::rgb_to_grayscale(r, g, b)),
+            ColorMode::Palette { palette, ansi256 } => {
+                let [r, g, b] = palette.nearest(r, g, b);
+                if ansi256 {
+                    Self::Ansi256(crate::color::rgb_to_xterm256(r, g, b))
+                } else {
+                    Self::TrueColor(r, g, b)
+                }
             }
-            writer.write_all(glyph.as_bytes())
+            ColorMode::None => Self::None,
         }
-        ColorMode::Ansi256 => {
-            // Convert RGB to the closest 8-bit color index (\x1b[38;5;Idxm)
-            let idx = crate::color::rgb_to_xterm256(r, g, b);
-            if !matches!(last, ColorState::Ansi256(li) if *li == idx) {
-                write!(writer, "\x1b[38;5;{idx}m")?;
-                *last = ColorState::Ansi256(idx);
+    }
+
+    /// The RGB colour a terminal shows for this state under `color_mode`,
+    /// or `None` for the default colour.
+    pub(super) fn to_rgb(self, color_mode: ColorMode) -> Option<[u8; 3]> {
+        match self {
+            Self::TrueColor(r, g, b) => Some([r, g, b]),
+            Self::Ansi16(idx) => Some(match color_mode {
+                ColorMode::Ansi16(palette) => palette.colors()[usize::from(idx)],
+                _ => crate::color::XTERM_256[usize::from(idx)],
+            }),
+            Self::Ansi256(idx) | Self::Grayscale(idx) => {
+                Some(crate::color::XTERM_256[usize::from(idx)])
             }
-            writer.write_all(glyph.as_bytes())
+            Self::None => None,
         }
-        ColorMode::None => writer.write_all(glyph.as_bytes()),
     }
 }
 
-/// Renders two vertical pixels using half-block characters (▀ or ▄).
-///
-/// This technique uses the foreground color for the top half and the
-/// background color for the bottom half, effectively doubling vertical resolution.
-pub(super) fn write_half_block<W: Write>(
-    out: &mut W,
-    top: image::Rgba<u8>,
-    bot: image::Rgba<u8>,
-) -> std::io::Result<()> {
-    match (top[3] > 0, bot[3] > 0) {
-        (true, true) => write!(
-            out,
-            "\x1b[38;2;{};{};{}m\x1b[48;2;{};{};{}m▀",
-            top[0], top[1], top[2], bot[0], bot[1], bot[2]
-        ),
-        (true, false) => write!(out, "\x1b[38;2;{};{};{}m\x1b[49m▀", top[0], top[1], top[2]),
-        (false, true) => write!(out, "\x1b[38;2;{};{};{}m\x1b[49m▄", bot[0], bot[1], bot[2]),
-        (false, false) => write!(out, "\x1b[0m "),
+/// Formats the SGR sequence for a [`ColorState`]; the `u8` is 38 (fg) or
+/// 48 (bg).
+pub(super) struct Sgr(pub(super) ColorState, pub(super) u8);
+
+impl fmt::Display for Sgr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let base = self.1;
+        match self.0 {
+            ColorState::TrueColor(r, g, b) => write!(f, "\x1b[{base};2;{r};{g};{b}m"),
+            ColorState::Ansi256(idx) | ColorState::Grayscale(idx) => {
+                write!(f, "\x1b[{base};5;{idx}m")
+            }
+            // 38 -> 30–37 / 90–97, 48 -> 40–47 / 100–107
+            ColorState::Ansi16(idx) if idx < 8 => write!(f, "\x1b[{}m", base - 8 + idx),
+            ColorState::Ansi16(idx) => write!(f, "\x1b[{}m", base + 52 + idx - 8),
+            ColorState::None => Ok(()),
+        }
     }
 }
 
-/// Renders a single pixel as a "double-wide" block (██).
-///
-/// This is used for modes that don't support sub-pixel resolution,
-/// providing a chunky, square-pixel look.
-pub(super) fn write_full_block<W: Write>(out: &mut W, px: image::Rgba<u8>) -> std::io::Result<()> {
-    if px[3] > 0 {
-        write!(out, "\x1b[38;2;{};{};{}m██", px[0], px[1], px[2])
-    } else {
-        write!(out, "  ")
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Ansi16Palette, ColorPalette};
+
+    fn sgr(state: ColorState, base: u8) -> String {
+        Sgr(state, base).to_string()
     }
-}
 
-/// A specialized version of `write_colored_glyph` for parallel rendering.
-///
-/// Since parallel threads render chunks into independent Strings, we don't
-/// track state between them (it would require complex locking). Instead,
-/// every glyph gets its own color sequence.
-#[cfg(feature = "parallel")]
-pub(super) fn write_colored_glyph_to_str(
-    buf: &mut String,
-    glyph: &str,
-    r: u8,
-    g: u8,
-    b: u8,
-    color_mode: ColorMode,
-) {
-    match color_mode {
-        ColorMode::TrueColor => {
-            let _ = write!(buf, "\x1b[38;2;{r};{g};{b}m{glyph}");
-        }
-        ColorMode::Ansi256 => {
-            let idx = crate::color::rgb_to_xterm256(r, g, b);
-            let _ = write!(buf, "\x1b[38;5;{idx}m{glyph}");
-        }
-        ColorMode::None => {
-            buf.push_str(glyph);
-        }
+    #[test]
+    fn ansi16_uses_basic_and_bright_codes() {
+        assert_eq!(sgr(ColorState::Ansi16(1), 38), "\x1b[31m");
+        assert_eq!(sgr(ColorState::Ansi16(9), 38), "\x1b[91m");
+        assert_eq!(sgr(ColorState::Ansi16(1), 48), "\x1b[41m");
+        assert_eq!(sgr(ColorState::Ansi16(9), 48), "\x1b[101m");
+        assert_eq!(sgr(ColorState::Ansi16(0), 38), "\x1b[30m");
+        assert_eq!(sgr(ColorState::Ansi16(15), 48), "\x1b[107m");
+    }
+
+    #[test]
+    fn ansi16_matches_against_the_palette() {
+        let mode = ColorMode::Ansi16(Ansi16Palette::XTERM);
+        assert_eq!(
+            ColorState::from_rgb([250, 10, 10], mode),
+            ColorState::Ansi16(9)
+        );
+        assert_eq!(
+            ColorState::from_rgb([120, 0, 0], mode),
+            ColorState::Ansi16(1)
+        );
+        // Dracula's red is the closest match for a salmon that xterm would
+        // call bright red.
+        let dracula = ColorMode::Ansi16(Ansi16Palette::DRACULA);
+        assert_eq!(
+            ColorState::from_rgb([240, 90, 90], dracula),
+            ColorState::Ansi16(1)
+        );
+    }
+
+    #[test]
+    fn grayscale_stays_on_the_ramp() {
+        assert_eq!(
+            ColorState::from_rgb([0, 0, 0], ColorMode::Grayscale),
+            ColorState::Grayscale(232)
+        );
+        assert_eq!(
+            ColorState::from_rgb([255, 255, 255], ColorMode::Grayscale),
+            ColorState::Grayscale(255)
+        );
+        assert_eq!(
+            ColorState::from_rgb([255, 0, 0], ColorMode::Grayscale),
+            ColorState::Grayscale(245)
+        );
+        assert_eq!(sgr(ColorState::Grayscale(244), 48), "\x1b[48;5;244m");
+    }
+
+    #[test]
+    fn palette_mode_locks_colours_in_either_emission() {
+        let palette = ColorPalette::PICO8;
+        let truecolor = ColorMode::Palette {
+            palette,
+            ansi256: false,
+        };
+        assert_eq!(
+            ColorState::from_rgb([250, 0, 70], truecolor),
+            ColorState::TrueColor(0xFF, 0x00, 0x4D)
+        );
+        let ansi256 = ColorMode::Palette {
+            palette,
+            ansi256: true,
+        };
+        assert_eq!(
+            ColorState::from_rgb([250, 0, 70], ansi256),
+            ColorState::Ansi256(crate::color::rgb_to_xterm256(0xFF, 0x00, 0x4D))
+        );
     }
 }
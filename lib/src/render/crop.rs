@@ -0,0 +1,112 @@
+//! Source regions for [`RenderOptions::crop`] and
+//! [`RenderOptions::auto_trim`].
+//!
+//! [`RenderOptions::crop`]: super::RenderOptions::crop
+//! [`RenderOptions::auto_trim`]: super::RenderOptions::auto_trim
+
+use image::{DynamicImage, Rgba};
+
+/// Channels may differ from the border colour by this much and still count
+/// as border, so compression noise doesn't stop a trim.
+const TRIM_TOLERANCE: u8 = 8;
+
+/// Clamps an `(x, y, width, height)` region to a `img_w` x `img_h` image.
+///
+/// Returns `None` if the region misses the image or covers all of it.
+pub(super) fn clamp_region(
+    (x, y, w, h): (u32, u32, u32, u32),
+    img_w: u32,
+    img_h: u32,
+) -> Option<(u32, u32, u32, u32)> {
+    if x >= img_w || y >= img_h || w == 0 || h == 0 {
+        return None;
+    }
+    let region = (x, y, w.min(img_w - x), h.min(img_h - y));
+    (region != (0, 0, img_w, img_h)).then_some(region)
+}
+
+/// The bounding box of everything that isn't border, as
+/// `(x, y, width, height)`.
+///
+/// The top-left pixel decides what the border is: fully transparent pixels
+/// if it is fully transparent, otherwise pixels within [`TRIM_TOLERANCE`] of
+/// its colour. Returns `None` if there is no border to trim, or nothing but
+/// border.
+pub(super) fn trim_bounds(img: &DynamicImage) -> Option<(u32, u32, u32, u32)> {
+    let rgba = img.to_rgba8();
+    let corner = *rgba.get_pixel_checked(0, 0)?;
+    let is_border = |p: &Rgba<u8>| {
+        if corner[3] == 0 {
+            p[3] == 0
+        } else {
+            p.0.iter()
+                .zip(corner.0)
+                .all(|(&c, b)| c.abs_diff(b) <= TRIM_TOLERANCE)
+        }
+    };
+
+    let (mut x0, mut y0, mut x1, mut y1) = (u32::MAX, u32::MAX, 0, 0);
+    for (x, y, p) in rgba.enumerate_pixels() {
+        if !is_border(p) {
+            x0 = x0.min(x);
+            y0 = y0.min(y);
+            x1 = x1.max(x);
+            y1 = y1.max(y);
+        }
+    }
+    if x0 > x1 {
+        return None;
+    }
+    clamp_region(
+        (x0, y0, x1 - x0 + 1, y1 - y0 + 1),
+        rgba.width(),
+        rgba.height(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    fn framed(border: Rgba<u8>) -> DynamicImage {
+        DynamicImage::ImageRgba8(RgbaImage::from_fn(10, 8, |x, y| {
+            if (2..7).contains(&x) && (3..5).contains(&y) {
+                Rgba([200, 30, 30, 255])
+            } else {
+                border
+            }
+        }))
+    }
+
+    #[test]
+    fn trims_transparent_padding() {
+        assert_eq!(trim_bounds(&framed(Rgba([9, 9, 9, 0]))), Some((2, 3, 5, 2)));
+    }
+
+    #[test]
+    fn trims_a_uniform_border_despite_noise() {
+        let mut img = framed(Rgba([250, 250, 250, 255])).into_rgba8();
+        img.put_pixel(9, 7, Rgba([246, 253, 250, 255]));
+        let img = DynamicImage::ImageRgba8(img);
+        assert_eq!(trim_bounds(&img), Some((2, 3, 5, 2)));
+    }
+
+    #[test]
+    fn leaves_borderless_and_blank_images_alone() {
+        let solid = DynamicImage::ImageRgba8(RgbaImage::from_pixel(4, 4, Rgba([1, 2, 3, 255])));
+        assert_eq!(trim_bounds(&solid), None);
+        let mut edge = RgbaImage::from_pixel(4, 4, Rgba([0, 0, 0, 0]));
+        edge.put_pixel(0, 0, Rgba([255, 255, 255, 255]));
+        edge.put_pixel(3, 3, Rgba([255, 0, 0, 255]));
+        assert_eq!(trim_bounds(&DynamicImage::ImageRgba8(edge)), None);
+    }
+
+    #[test]
+    fn clamps_regions_to_the_image() {
+        assert_eq!(clamp_region((2, 1, 100, 100), 10, 8), Some((2, 1, 8, 7)));
+        assert_eq!(clamp_region((10, 0, 5, 5), 10, 8), None);
+        assert_eq!(clamp_region((0, 0, 0, 5), 10, 8), None);
+        assert_eq!(clamp_region((0, 0, 10, 8), 10, 8), None);
+    }
+}
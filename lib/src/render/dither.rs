@@ -0,0 +1,758 @@
+//! Colour dithering for [`DitherMode`].
+//!
+//! The resized image arrives in floating point and leaves as 8-bit RGBA whose
+//! colours the renderer can reproduce exactly: every pixel is snapped to the
+//! nearest entry of a [`Palette`]. Error-diffusion modes push each pixel's
+//! quantization error onto its unprocessed neighbours in raster order;
+//! ordered modes offset each pixel by a position-dependent threshold before
+//! snapping, so the result never depends on the rest of the image.
+//!
+//! [`DitherMode`]: super::DitherMode
+
+use std::sync::OnceLock;
+
+use image::{Rgba, Rgba32FImage, RgbaImage};
+
+use super::palette::Ansi16Palette;
+use super::pixel::ALPHA_THRESHOLD;
+use super::types::DitherMode;
+use crate::color::{
+    PaletteDiffuser, XTERM_256, nearest_palette_index, rgb_to_ansi16, rgb_to_xterm256,
+};
+
+const LUMA_R: f32 = 0.2126;
+const LUMA_G: f32 = 0.7152;
+const LUMA_B: f32 = 0.0722;
+
+/// Average spacing of the xterm-256 colour cube, used as the ordered-dither
+/// amplitude for that palette.
+const XTERM_CUBE_STEP: f32 = 51.0;
+
+/// Rough spacing of a 16-colour palette, used as its ordered-dither amplitude.
+const ANSI16_STEP: f32 = 128.0;
+
+/// First and last level of the xterm grayscale ramp (colours 232–255).
+const GRAY_RAMP: (f32, f32) = (8.0, 238.0);
+
+/// Side of the blue-noise threshold map.
+const NOISE_SIZE: usize = 32;
+
+/// Width of the Gaussian used to find clusters and voids in the blue-noise map.
+const NOISE_SIGMA: f32 = 1.5;
+
+/// The colours a render can put on screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Palette {
+    /// 8 bits per channel.
+    TrueColor,
+    /// The xterm 256-colour palette.
+    Xterm256,
+    /// A terminal's 16 basic colours.
+    Ansi16(Ansi16Palette),
+    /// The 24 greys of the xterm grayscale ramp.
+    Grayscale,
+    /// A fixed palette from [`ColorMode::Palette`](super::ColorMode::Palette).
+    Fixed(&'static [[u8; 3]]),
+    /// One luminance level per glyph of a ramp, spread evenly between the
+    /// darkest and brightest opaque pixel as the renderer does, after tone
+    /// mapping when [`dither`] is given levels. Hue is kept by scaling RGB.
+    Ramp(usize),
+    /// Black and white.
+    Mono,
+}
+
+impl Palette {
+    /// Whether entries differ only in luminance, so dithering works on luma.
+    const fn is_luma(self) -> bool {
+        matches!(self, Self::Ramp(_) | Self::Grayscale | Self::Mono)
+    }
+}
+
+/// Error-diffusion weights as `(dx, dy, weight)`, plus their divisor.
+type Kernel = (&'static [(i64, i64, f32)], f32);
+
+const FLOYD_STEINBERG: Kernel = (&[(1, 0, 7.0), (-1, 1, 3.0), (0, 1, 5.0), (1, 1, 1.0)], 16.0);
+
+const ATKINSON: Kernel = (
+    &[
+        (1, 0, 1.0),
+        (2, 0, 1.0),
+        (-1, 1, 1.0),
+        (0, 1, 1.0),
+        (1, 1, 1.0),
+        (0, 2, 1.0),
+    ],
+    8.0,
+);
+
+const SIERRA: Kernel = (
+    &[
+        (1, 0, 5.0),
+        (2, 0, 3.0),
+        (-2, 1, 2.0),
+        (-1, 1, 4.0),
+        (0, 1, 5.0),
+        (1, 1, 4.0),
+        (2, 1, 2.0),
+        (-1, 2, 2.0),
+        (0, 2, 3.0),
+        (1, 2, 2.0),
+    ],
+    32.0,
+);
+
+const STUCKI: Kernel = (
+    &[
+        (1, 0, 8.0),
+        (2, 0, 4.0),
+        (-2, 1, 2.0),
+        (-1, 1, 4.0),
+        (0, 1, 8.0),
+        (1, 1, 4.0),
+        (2, 1, 2.0),
+        (-2, 2, 1.0),
+        (-1, 2, 2.0),
+        (0, 2, 4.0),
+        (1, 2, 2.0),
+        (2, 2, 1.0),
+    ],
+    42.0,
+);
+
+const JARVIS: Kernel = (
+    &[
+        (1, 0, 7.0),
+        (2, 0, 5.0),
+        (-2, 1, 3.0),
+        (-1, 1, 5.0),
+        (0, 1, 7.0),
+        (1, 1, 5.0),
+        (2, 1, 3.0),
+        (-2, 2, 1.0),
+        (-1, 2, 3.0),
+        (0, 2, 5.0),
+        (1, 2, 3.0),
+        (2, 2, 1.0),
+    ],
+    48.0,
+);
+
+/// Quantizes one working value (RGB, or luma in the first channel, on a
+/// `0..=255` scale) to a palette entry.
+struct Quantizer {
+    palette: Palette,
+    /// Darkest and brightest luma level, for the luma palettes.
+    lo: f32,
+    hi: f32,
+}
+
+impl Quantizer {
+    /// Builds the quantizer for the working values `work` of the pixels
+    /// marked `opaque`.
+    fn new(work: &[[f32; 3]], opaque: &[bool], palette: Palette) -> Self {
+        let (lo, hi) = match palette {
+            Palette::Ramp(_) => work
+                .iter()
+                .zip(opaque)
+                .filter(|&(_, &opaque)| opaque)
+                .map(|(value, _)| value[0])
+                .fold((f32::MAX, f32::MIN), |(lo, hi), l| (lo.min(l), hi.max(l))),
+            Palette::Grayscale => GRAY_RAMP,
+            _ => (0.0, 255.0),
+        };
+        Self {
+            palette,
+            lo,
+            hi: hi.max(lo),
+        }
+    }
+
+    const fn is_luma(&self) -> bool {
+        self.palette.is_luma()
+    }
+
+    /// Number of luma levels, for the luma palettes.
+    const fn levels(&self) -> usize {
+        match self.palette {
+            Palette::Ramp(n) => n,
+            Palette::Grayscale => 24,
+            _ => 2,
+        }
+    }
+
+    /// Distance between neighbouring palette entries, the amplitude of
+    /// ordered dithering.
+    #[expect(clippy::cast_precision_loss, reason = "Glyph ramps are short")]
+    fn step(&self) -> f32 {
+        match self.palette {
+            Palette::TrueColor => 1.0,
+            Palette::Xterm256 => XTERM_CUBE_STEP,
+            Palette::Ansi16(_) => ANSI16_STEP,
+            // As if the colours were spread evenly over the RGB cube.
+            Palette::Fixed(colors) => 255.0 / (colors.len().max(1) as f32).cbrt(),
+            Palette::Ramp(_) | Palette::Grayscale | Palette::Mono => {
+                (self.hi - self.lo) / (self.levels().max(2) - 1) as f32
+            }
+        }
+    }
+
+    /// Snaps `value` to the nearest palette entry.
+    #[expect(
+        clippy::cast_possible_truncation,
+        clippy::cast_precision_loss,
+        clippy::cast_sign_loss,
+        reason = "Values are clamped to 0..=255 before conversion"
+    )]
+    fn quantize(&self, value: [f32; 3]) -> [f32; 3] {
+        match self.palette {
+            Palette::TrueColor => value.map(|c| c.clamp(0.0, 255.0).round()),
+            Palette::Xterm256 => {
+                let [r, g, b] = value.map(|c| c.clamp(0.0, 255.0).round() as u8);
+                XTERM_256[usize::from(rgb_to_xterm256(r, g, b))].map(f32::from)
+            }
+            Palette::Ansi16(palette) => {
+                let [r, g, b] = value.map(|c| c.clamp(0.0, 255.0).round() as u8);
+                palette.colors()[usize::from(rgb_to_ansi16(r, g, b, palette.colors()))]
+                    .map(f32::from)
+            }
+            Palette::Fixed(colors) => {
+                let [r, g, b] = value.map(|c| c.clamp(0.0, 255.0).round() as u8);
+                colors
+                    .get(nearest_palette_index(r, g, b, colors))
+                    .map_or([r, g, b], |&c| c)
+                    .map(f32::from)
+            }
+            Palette::Ramp(_) | Palette::Grayscale | Palette::Mono => {
+                let step = self.step();
+                if step <= 0.0 {
+                    return [self.lo; 3];
+                }
+                let last = (self.levels().max(2) - 1) as f32;
+                let level = ((value[0] - self.lo) / step).round().clamp(0.0, last);
+                [level.mul_add(step, self.lo); 3]
+            }
+        }
+    }
+
+    /// Turns a quantized working value back into a pixel of `original`.
+    ///
+    /// Interior ramp levels are written at the centre of their glyph's luma
+    /// band so the renderer's truncating lookup lands on the intended glyph.
+    fn emit(&self, quantized: [f32; 3], original: [f32; 3]) -> [u8; 3] {
+        if !self.is_luma() {
+            return quantized.map(to_u8);
+        }
+        let mut target = quantized[0];
+        let step = self.step();
+        if matches!(self.palette, Palette::Ramp(_))
+            && target > self.lo + step / 2.0
+            && target < self.hi - step / 2.0
+        {
+            target += step / 2.0;
+        }
+        let orig = luma(original);
+        if matches!(self.palette, Palette::Grayscale | Palette::Mono) || orig <= 0.0 {
+            [to_u8(target); 3]
+        } else {
+            original.map(|c| to_u8(c * target / orig))
+        }
+    }
+}
+
+/// Dithers `img` (channels in `0.0..=1.0`) down to `palette`.
+///
+/// For [`Palette::Ramp`], `levels` holds the renderer's tone-mapped level of
+/// every pixel, row-major; when set, error diffuses between those levels
+/// rather than luma, and each pixel is written with its chosen level as its
+/// luma, so the image is ready for a linear stretch.
+///
+/// Pixels below the renderer's alpha threshold are copied through and take
+/// no part in error diffusion.
+pub(super) fn dither(
+    img: &Rgba32FImage,
+    mode: DitherMode,
+    palette: Palette,
+    levels: Option<&[u8]>,
+) -> RgbaImage {
+    let (width, height) = img.dimensions();
+    let originals: Vec<[f32; 3]> = img.pixels().map(scaled).collect();
+    let levels = levels.filter(|_| matches!(palette, Palette::Ramp(_)));
+    let mut work: Vec<[f32; 3]> = originals
+        .iter()
+        .enumerate()
+        .map(|(idx, &rgb)| match levels {
+            Some(levels) => [f32::from(levels[idx]); 3],
+            None if palette.is_luma() => [luma(rgb); 3],
+            None => rgb,
+        })
+        .collect();
+    let opaque: Vec<bool> = img.pixels().map(opaque).collect();
+    let quantizer = Quantizer::new(&work, &opaque, palette);
+
+    let kernel = match mode {
+        DitherMode::FloydSteinberg => Some(FLOYD_STEINBERG),
+        DitherMode::Atkinson => Some(ATKINSON),
+        DitherMode::Sierra => Some(SIERRA),
+        DitherMode::Stucki => Some(STUCKI),
+        DitherMode::Jarvis => Some(JARVIS),
+        _ => None,
+    };
+    let step = quantizer.step();
+
+    let mut out = RgbaImage::new(width, height);
+    for (idx, (x, y, px)) in img.enumerate_pixels().enumerate() {
+        let alpha = to_channel(px.0[3]);
+        let [red, green, blue] = if opaque[idx] {
+            let value = threshold(mode, x, y)
+                .map_or(work[idx], |t| work[idx].map(|c| (t - 0.5).mul_add(step, c)));
+            let snapped = quantizer.quantize(value);
+            if let Some(kernel) = kernel {
+                let error = [0, 1, 2].map(|c| value[c] - snapped[c]);
+                diffuse(&mut work, &opaque, img.dimensions(), (x, y), error, kernel);
+            }
+            quantizer.emit(snapped, originals[idx])
+        } else {
+            originals[idx].map(to_u8)
+        };
+        out.put_pixel(x, y, Rgba([red, green, blue, alpha]));
+    }
+    out
+}
+
+/// Snaps every opaque pixel of `img` to `palette` through a
+/// [`PaletteDiffuser`], one cell per `x_step` columns. Columns covered by the
+/// second half of a wide glyph take the colour of the cell they belong to.
+pub(super) fn diffuse_palette(img: &mut RgbaImage, x_step: u32, palette: &[[u8; 3]]) {
+    let (width, height) = img.dimensions();
+    let mut diffuser = PaletteDiffuser::new(width.div_ceil(x_step) as usize, palette);
+    for y in 0..height {
+        for x in (0..width).step_by(x_step as usize) {
+            let Rgba([red, green, blue, alpha]) = *img.get_pixel(x, y);
+            if alpha < ALPHA_THRESHOLD {
+                diffuser.skip();
+                continue;
+            }
+            let [red, green, blue] = palette[diffuser.quantize(red, green, blue)];
+            for cx in x..(x + x_step).min(width) {
+                img.put_pixel(cx, y, Rgba([red, green, blue, alpha]));
+            }
+        }
+    }
+}
+
+/// Spreads `error` from the pixel at `(x, y)` onto its opaque neighbours.
+fn diffuse(
+    work: &mut [[f32; 3]],
+    opaque: &[bool],
+    (width, height): (u32, u32),
+    (x, y): (u32, u32),
+    error: [f32; 3],
+    (weights, divisor): Kernel,
+) {
+    let (cols, rows) = (i64::from(width), i64::from(height));
+    for &(dx, dy, weight) in weights {
+        let (nx, ny) = (i64::from(x) + dx, i64::from(y) + dy);
+        if !(0..cols).contains(&nx) || !(0..rows).contains(&ny) {
+            continue;
+        }
+        let neighbour = index(nx, ny, cols);
+        if opaque[neighbour] {
+            for (channel, err) in work[neighbour].iter_mut().zip(error) {
+                *channel = (err / divisor).mul_add(weight, *channel);
+            }
+        }
+    }
+}
+
+/// Ordered-dither threshold in `0.0..1.0` for a pixel, or `None` for modes
+/// that don't use one.
+#[expect(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    reason = "Matrix ranks are below 1024"
+)]
+fn threshold(mode: DitherMode, x: u32, y: u32) -> Option<f32> {
+    let (rank, size) = match mode {
+        DitherMode::Bayer2 => (bayer(x % 2, y % 2, 2), 2),
+        DitherMode::Bayer4 => (bayer(x % 4, y % 4, 4), 4),
+        DitherMode::Bayer8 => (bayer(x % 8, y % 8, 8), 8),
+        DitherMode::BlueNoise => {
+            let (nx, ny) = (x as usize % NOISE_SIZE, y as usize % NOISE_SIZE);
+            (
+                u32::from(blue_noise()[ny * NOISE_SIZE + nx]),
+                NOISE_SIZE as u32,
+            )
+        }
+        _ => return None,
+    };
+    Some((rank as f32 + 0.5) / (size * size) as f32)
+}
+
+/// Rank of `(x, y)` in a `size`x`size` Bayer matrix, built recursively from
+/// the 2x2 pattern `[[0, 2], [3, 1]]`.
+const fn bayer(x: u32, y: u32, size: u32) -> u32 {
+    if size <= 1 {
+        return 0;
+    }
+    let half = size / 2;
+    let quadrant = match (x / half, y / half) {
+        (0, 0) => 0,
+        (1, 0) => 2,
+        (0, 1) => 3,
+        _ => 1,
+    };
+    4 * bayer(x % half, y % half, half) + quadrant
+}
+
+static BLUE_NOISE: OnceLock<Vec<u16>> = OnceLock::new();
+
+/// Returns the blue-noise rank map, building it on first use.
+fn blue_noise() -> &'static [u16] {
+    BLUE_NOISE.get_or_init(build_blue_noise)
+}
+
+/// Builds a tileable blue-noise rank map with Ulichney's void-and-cluster
+/// method: points are repeatedly moved out of the tightest cluster into the
+/// largest void until the initial pattern is even, then ranked by removing
+/// clusters and filling voids one point at a time.
+#[expect(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    reason = "The map holds 1024 entries"
+)]
+fn build_blue_noise() -> Vec<u16> {
+    const CELLS: usize = NOISE_SIZE * NOISE_SIZE;
+
+    // Toroidal Gaussian indexed by offset.
+    let kernel: Vec<f32> = (0..CELLS)
+        .map(|i| {
+            let (dx, dy) = (i % NOISE_SIZE, i / NOISE_SIZE);
+            let dx = dx.min(NOISE_SIZE - dx) as f32;
+            let dy = dy.min(NOISE_SIZE - dy) as f32;
+            (-dx.mul_add(dx, dy * dy) / (2.0 * NOISE_SIGMA * NOISE_SIGMA)).exp()
+        })
+        .collect();
+    let toggle = |on: &mut [bool], energy: &mut [f32], p: usize| {
+        on[p] = !on[p];
+        let sign: f32 = if on[p] { 1.0 } else { -1.0 };
+        let (px, py) = (p % NOISE_SIZE, p / NOISE_SIZE);
+        for (q, e) in energy.iter_mut().enumerate() {
+            let dx = (q % NOISE_SIZE + NOISE_SIZE - px) % NOISE_SIZE;
+            let dy = (q / NOISE_SIZE + NOISE_SIZE - py) % NOISE_SIZE;
+            *e = sign.mul_add(kernel[dy * NOISE_SIZE + dx], *e);
+        }
+    };
+    // The tightest cluster is the densest set point; the largest void the
+    // emptiest unset one. Ties go to the lowest index.
+    let pick = |on: &[bool], energy: &[f32], set: bool| {
+        (0..CELLS)
+            .filter(|&p| on[p] == set)
+            .fold(None, |best: Option<usize>, p| match best {
+                Some(b) if set && energy[p] <= energy[b] => Some(b),
+                Some(b) if !set && energy[p] >= energy[b] => Some(b),
+                _ => Some(p),
+            })
+    };
+
+    // Seed a tenth of the cells from a fixed LCG so the map is reproducible.
+    let mut on = vec![false; CELLS];
+    let mut energy = vec![0.0; CELLS];
+    let mut state: u64 = 0x853c_49e6_748f_ea9b;
+    let mut seeded = 0;
+    while seeded < CELLS / 10 {
+        state = state
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        let p = (state >> 33) as usize % CELLS;
+        if !on[p] {
+            toggle(&mut on, &mut energy, p);
+            seeded += 1;
+        }
+    }
+
+    for _ in 0..CELLS {
+        let Some(cluster) = pick(&on, &energy, true) else {
+            break;
+        };
+        toggle(&mut on, &mut energy, cluster);
+        let void = pick(&on, &energy, false).unwrap_or(cluster);
+        toggle(&mut on, &mut energy, void);
+        if void == cluster {
+            break;
+        }
+    }
+
+    let mut ranks = vec![0; CELLS];
+    let (mut thinned, mut thinned_energy) = (on.clone(), energy.clone());
+    for rank in (0..seeded).rev() {
+        if let Some(cluster) = pick(&thinned, &thinned_energy, true) {
+            toggle(&mut thinned, &mut thinned_energy, cluster);
+            ranks[cluster] = rank as u16;
+        }
+    }
+    for rank in seeded..CELLS {
+        if let Some(void) = pick(&on, &energy, false) {
+            toggle(&mut on, &mut energy, void);
+            ranks[void] = rank as u16;
+        }
+    }
+    ranks
+}
+
+const fn index(x: i64, y: i64, w: i64) -> usize {
+    #[expect(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        reason = "Callers bound x and y to the image"
+    )]
+    let i = (y * w + x) as usize;
+    i
+}
+
+fn opaque(p: &Rgba<f32>) -> bool {
+    to_channel(p.0[3]) >= ALPHA_THRESHOLD
+}
+
+/// RGB of a float pixel on a `0..=255` scale.
+fn scaled(p: &Rgba<f32>) -> [f32; 3] {
+    [p.0[0], p.0[1], p.0[2]].map(|c| c * 255.0)
+}
+
+fn luma([r, g, b]: [f32; 3]) -> f32 {
+    LUMA_R.mul_add(r, LUMA_G.mul_add(g, LUMA_B * b))
+}
+
+#[expect(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    reason = "Values are clamped to 0..=255 before conversion"
+)]
+fn to_u8(c: f32) -> u8 {
+    c.clamp(0.0, 255.0).round() as u8
+}
+
+fn to_channel(c: f32) -> u8 {
+    to_u8(c * 255.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A horizontal grey ramp from black to white, `width` pixels wide.
+    fn gradient(width: u32, height: u32) -> Rgba32FImage {
+        Rgba32FImage::from_fn(width, height, |x, _| {
+            #[expect(clippy::cast_precision_loss, reason = "Tiny test images")]
+            let v = x as f32 / (width - 1) as f32;
+            Rgba([v, v, v, 1.0])
+        })
+    }
+
+    fn grey(width: u32, height: u32, v: f32) -> Rgba32FImage {
+        Rgba32FImage::from_pixel(width, height, Rgba([v, v, v, 1.0]))
+    }
+
+    /// Renders luma as `#` (bright) and `.` (dark), one string per row.
+    fn mono_rows(img: &RgbaImage) -> Vec<String> {
+        img.rows()
+            .map(|row| row.map(|p| if p.0[0] > 127 { '#' } else { '.' }).collect())
+            .collect()
+    }
+
+    #[test]
+    fn error_diffusion_mid_grey_is_a_checkerboard() {
+        for mode in [
+            DitherMode::FloydSteinberg,
+            DitherMode::Sierra,
+            DitherMode::Stucki,
+            DitherMode::Jarvis,
+        ] {
+            let rows = mono_rows(&dither(&grey(4, 2, 0.5), mode, Palette::Mono, None));
+            assert_eq!(rows, ["#.#.", ".#.#"], "{mode:?}");
+        }
+    }
+
+    #[test]
+    fn kernels_produce_fixed_gradients() {
+        let cases = [
+            (
+                DitherMode::FloydSteinberg,
+                [
+                    ".....#.#.#######",
+                    "....#..#.#.#.###",
+                    ".....#.#.##.####",
+                    "...#...#.#.#####",
+                ],
+            ),
+            (
+                DitherMode::Atkinson,
+                [
+                    ".......##.######",
+                    ".....#..########",
+                    ".....##..#.#####",
+                    ".......###.#####",
+                ],
+            ),
+            (
+                DitherMode::Bayer4,
+                [
+                    ".......#.#.#.###",
+                    "..#.#.#.#.######",
+                    ".....#.#.#.#####",
+                    "....#.#.########",
+                ],
+            ),
+        ];
+        for (mode, expected) in cases {
+            let rows = mono_rows(&dither(&gradient(16, 4), mode, Palette::Mono, None));
+            assert_eq!(rows, expected, "{mode:?}");
+        }
+    }
+
+    #[test]
+    fn bayer_matrix_matches_the_standard_4x4() {
+        let matrix: Vec<Vec<u32>> = (0..4)
+            .map(|y| (0..4).map(|x| bayer(x, y, 4)).collect())
+            .collect();
+        assert_eq!(
+            matrix,
+            [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]]
+        );
+    }
+
+    #[test]
+    fn blue_noise_ranks_every_cell_once() {
+        let mut ranks = blue_noise().to_vec();
+        ranks.sort_unstable();
+        assert!(ranks.iter().copied().eq(0..1024));
+
+        let rows = mono_rows(&dither(
+            &grey(8, 4, 0.5),
+            DitherMode::BlueNoise,
+            Palette::Mono,
+            None,
+        ));
+        assert_eq!(rows, ["#.#.#.##", ".#...#..", "#.##.#.#", ".#.#.##."]);
+    }
+
+    #[test]
+    fn xterm256_diffuses_colour_error_between_palette_entries() {
+        let img = Rgba32FImage::from_pixel(4, 1, Rgba([0.3, 0.6, 0.9, 1.0]));
+        let out = dither(&img, DitherMode::FloydSteinberg, Palette::Xterm256, None);
+        let pixels: Vec<[u8; 4]> = out.pixels().map(|p| p.0).collect();
+        assert_eq!(
+            pixels,
+            [
+                [95, 135, 215, 255],
+                [0, 175, 255, 255],
+                [95, 135, 215, 255],
+                [0, 175, 255, 255],
+            ]
+        );
+    }
+
+    #[test]
+    fn ramp_levels_land_inside_glyph_bands() {
+        let out = dither(
+            &gradient(6, 1),
+            DitherMode::FloydSteinberg,
+            Palette::Ramp(3),
+            None,
+        );
+        let luma: Vec<u8> = out.pixels().map(|p| p.0[0]).collect();
+        assert_eq!(luma, [0, 0, 191, 191, 255, 255]);
+    }
+
+    #[test]
+    fn ramp_dithers_between_tone_mapped_levels() {
+        // A flat grey has no luma range, but tone mapping can still split
+        // it; the levels, not luma, decide each pixel's band.
+        let levels = [0, 0, 0, 255, 255, 255];
+        let out = dither(
+            &grey(6, 1, 0.5),
+            DitherMode::FloydSteinberg,
+            Palette::Ramp(2),
+            Some(&levels),
+        );
+        let luma: Vec<u8> = out.pixels().map(|p| p.0[0]).collect();
+        assert_eq!(luma, levels);
+    }
+
+    #[test]
+    fn truecolor_spreads_fractional_levels() {
+        let out = dither(
+            &grey(6, 1, 100.4 / 255.0),
+            DitherMode::FloydSteinberg,
+            Palette::TrueColor,
+            None,
+        );
+        let luma: Vec<u8> = out.pixels().map(|p| p.0[0]).collect();
+        assert_eq!(luma, [100, 101, 100, 100, 101, 100]);
+    }
+
+    #[test]
+    fn transparent_pixels_pass_through() {
+        let mut img = grey(3, 1, 0.5);
+        img.put_pixel(1, 0, Rgba([0.2, 0.4, 0.6, 0.0]));
+        let out = dither(&img, DitherMode::FloydSteinberg, Palette::Mono, None);
+        assert_eq!(out.get_pixel(1, 0).0, [51, 102, 153, 0]);
+        // The first pixel's error is dropped rather than carried past the
+        // transparent one, so the third rounds up just like the first.
+        assert_eq!(mono_rows(&out), ["#.#"]);
+    }
+
+    #[test]
+    fn xterm256_cells_diffuse_in_oklab() {
+        let mut img = RgbaImage::from_fn(8, 2, |x, _| {
+            let v = u8::try_from(100 + x * 4).unwrap_or(u8::MAX);
+            Rgba([v, v, v, 255])
+        });
+        img.put_pixel(0, 1, Rgba([0, 0, 0, 0]));
+        diffuse_palette(&mut img, 2, &XTERM_256);
+        let greys: Vec<[u8; 4]> = img.pixels().map(|p| p.0).collect();
+        // Each wide cell paints both of its columns; the transparent cell is
+        // left alone, including the column it would have covered.
+        assert_eq!(
+            greys,
+            [
+                [98, 98, 98, 255],
+                [98, 98, 98, 255],
+                [108, 108, 108, 255],
+                [108, 108, 108, 255],
+                [118, 118, 118, 255],
+                [118, 118, 118, 255],
+                [128, 128, 128, 255],
+                [128, 128, 128, 255],
+                [0, 0, 0, 0],
+                [104, 104, 104, 255],
+                [108, 108, 108, 255],
+                [108, 108, 108, 255],
+                [118, 118, 118, 255],
+                [118, 118, 118, 255],
+                [118, 118, 118, 255],
+                [118, 118, 118, 255],
+            ]
+        );
+    }
+
+    #[test]
+    fn grayscale_and_ansi16_snap_to_their_palettes() {
+        let img = gradient(16, 1);
+        let greys = dither(&img, DitherMode::Bayer4, Palette::Grayscale, None);
+        for p in greys.pixels() {
+            let [r, g, b, _] = p.0;
+            assert!(r == g && g == b && (8..=238).contains(&r) && (r - 8) % 10 == 0);
+        }
+        let palette = Ansi16Palette::VGA;
+        let basic = dither(
+            &img,
+            DitherMode::FloydSteinberg,
+            Palette::Ansi16(palette),
+            None,
+        );
+        for p in basic.pixels() {
+            let [r, g, b, _] = p.0;
+            assert!(palette.colors().contains(&[r, g, b]), "{:?}", p.0);
+        }
+    }
+}
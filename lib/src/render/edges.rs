@@ -0,0 +1,268 @@
+//! Edge-directed line art for [`CharsetMode::Edges`].
+//!
+//! A Sobel operator runs over the alpha-weighted luma of the prepared image,
+//! one pixel per cell. Gradients are thinned with non-maximum suppression
+//! along their direction (the Canny thinning step), then thresholded against
+//! the strongest edge in the image. Each surviving pixel is drawn with the
+//! line glyph that runs perpendicular to its gradient; everything else is
+//! left blank.
+//!
+//! [`CharsetMode::Edges`]: super::CharsetMode::Edges
+
+use image::RgbaImage;
+
+/// Fraction of the strongest gradient an edge must reach to be drawn.
+const EDGE_THRESHOLD: f32 = 0.2;
+
+/// Orientation of an edge, quantized to 45° steps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum EdgeDir {
+    /// `─`: brightness changes vertically.
+    Horizontal,
+    /// `│`: brightness changes horizontally.
+    Vertical,
+    /// `/`: runs from bottom-left to top-right.
+    Rising,
+    /// `\`: runs from top-left to bottom-right.
+    Falling,
+}
+
+impl EdgeDir {
+    /// The line glyph drawn for this orientation.
+    pub(super) const fn glyph(self) -> char {
+        match self {
+            Self::Horizontal => '─',
+            Self::Vertical => '│',
+            Self::Rising => '/',
+            Self::Falling => '\\',
+        }
+    }
+
+    /// Quantizes a gradient (in pixel units, y pointing down) to the edge
+    /// orientation perpendicular to it, on cells `cell_aspect` times as tall
+    /// as they are wide.
+    fn from_gradient(gx: f32, gy: f32, cell_aspect: f32) -> Self {
+        // Angle of the gradient in cell space, folded into 0°..180°.
+        let mut angle = (gy / cell_aspect).atan2(gx).to_degrees();
+        if angle < 0.0 {
+            angle += 180.0;
+        }
+        match angle {
+            a if !(22.5..157.5).contains(&a) => Self::Vertical,
+            a if a < 67.5 => Self::Rising,
+            a if a < 112.5 => Self::Horizontal,
+            _ => Self::Falling,
+        }
+    }
+
+    /// Component of a gradient along [`Self::normal`], up to scale.
+    fn project(self, gx: f32, gy: f32) -> f32 {
+        match self {
+            Self::Vertical => gx,
+            Self::Horizontal => gy,
+            Self::Rising => gx + gy,
+            Self::Falling => gy - gx,
+        }
+    }
+
+    /// Pixel step along the gradient, used for suppression and colour.
+    const fn normal(self) -> (i64, i64) {
+        match self {
+            Self::Vertical => (1, 0),
+            Self::Horizontal => (0, 1),
+            Self::Rising => (1, 1),
+            Self::Falling => (-1, 1),
+        }
+    }
+}
+
+/// A detected edge pixel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct Edge {
+    /// Orientation of the contour through this pixel.
+    pub dir: EdgeDir,
+    /// The pixel on the brighter side of the edge, whose colour the glyph
+    /// takes so contours stay visible against a dark background.
+    pub source: (u32, u32),
+}
+
+/// Runs edge detection over `rgba`, returning one entry per pixel in
+/// row-major order. Each pixel is one cell, `cell_aspect` times as tall as
+/// it is wide, so a vertical step covers that many horizontal ones.
+#[expect(
+    clippy::cast_possible_wrap,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    reason = "Coordinates are bounded by image dimensions, which fit in i64 and back"
+)]
+pub(super) fn detect(rgba: &RgbaImage, cell_aspect: f32) -> Vec<Option<Edge>> {
+    let (width, height) = rgba.dimensions();
+    let (w, h) = (i64::from(width), i64::from(height));
+    let luma: Vec<f32> = rgba
+        .pixels()
+        .map(|p| {
+            let [red, green, blue, alpha] = p.0.map(f32::from);
+            let luma = 0.0722_f32.mul_add(blue, 0.2126_f32.mul_add(red, 0.7152 * green));
+            luma * alpha / 255.0
+        })
+        .collect();
+    let at = |x: i64, y: i64| luma[(y.clamp(0, h - 1) * w + x.clamp(0, w - 1)) as usize];
+
+    let mut gradients = Vec::with_capacity(luma.len());
+    for y in 0..h {
+        for x in 0..w {
+            let gx = smooth(at(x + 1, y - 1), at(x + 1, y), at(x + 1, y + 1))
+                - smooth(at(x - 1, y - 1), at(x - 1, y), at(x - 1, y + 1));
+            let gy = smooth(at(x - 1, y + 1), at(x, y + 1), at(x + 1, y + 1))
+                - smooth(at(x - 1, y - 1), at(x, y - 1), at(x + 1, y - 1));
+            gradients.push((gx, gy, gx.hypot(gy / cell_aspect)));
+        }
+    }
+
+    let peak = gradients.iter().map(|g| g.2).fold(0.0_f32, f32::max);
+    if peak <= 0.0 {
+        return vec![None; gradients.len()];
+    }
+    let magnitude = |x: i64, y: i64| {
+        if (0..w).contains(&x) && (0..h).contains(&y) {
+            gradients[(y * w + x) as usize].2
+        } else {
+            0.0
+        }
+    };
+
+    let mut edges = Vec::with_capacity(gradients.len());
+    for (i, &(gx, gy, m)) in gradients.iter().enumerate() {
+        let (x, y) = (i as i64 % w, i as i64 / w);
+        if m < EDGE_THRESHOLD * peak {
+            edges.push(None);
+            continue;
+        }
+        let dir = EdgeDir::from_gradient(gx, gy, cell_aspect);
+        let (nx, ny) = dir.normal();
+        // Keep one pixel of a two-pixel plateau: ties go to the later side.
+        if m <= magnitude(x + nx, y + ny) || m < magnitude(x - nx, y - ny) {
+            edges.push(None);
+            continue;
+        }
+        // Step towards increasing luma.
+        let sign = if dir.project(gx, gy) >= 0.0 { 1 } else { -1 };
+        let (sx, sy) = (x + sign * nx, y + sign * ny);
+        let source = if (0..w).contains(&sx) && (0..h).contains(&sy) && at(sx, sy) > at(x, y) {
+            (sx as u32, sy as u32)
+        } else {
+            (x as u32, y as u32)
+        };
+        edges.push(Some(Edge { dir, source }));
+    }
+    edges
+}
+
+/// The `[1, 2, 1]` smoothing half of a Sobel kernel.
+fn smooth(before: f32, center: f32, after: f32) -> f32 {
+    center.mul_add(2.0, before + after)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::utils::DEFAULT_CELL_ASPECT;
+    use image::Rgba;
+
+    const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+    const BLACK: Rgba<u8> = Rgba([0, 0, 0, 255]);
+
+    fn glyphs(img: &RgbaImage) -> Vec<String> {
+        let edges = detect(img, DEFAULT_CELL_ASPECT);
+        edges
+            .chunks(img.width() as usize)
+            .map(|row| {
+                row.iter()
+                    .map(|e| e.map_or(' ', |e| e.dir.glyph()))
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn flat_image_has_no_edges() {
+        let img = RgbaImage::from_pixel(6, 6, WHITE);
+        assert!(
+            detect(&img, DEFAULT_CELL_ASPECT)
+                .iter()
+                .all(Option::is_none)
+        );
+    }
+
+    #[test]
+    fn vertical_step_draws_one_bar_per_row() {
+        let img = RgbaImage::from_fn(8, 4, |x, _| if x < 4 { BLACK } else { WHITE });
+        let rows = glyphs(&img);
+        assert!(rows.iter().all(|r| r == "    │   "), "{rows:?}");
+    }
+
+    #[test]
+    fn horizontal_step_draws_one_line() {
+        let img = RgbaImage::from_fn(4, 6, |_, y| if y < 3 { WHITE } else { BLACK });
+        let rows = glyphs(&img);
+        assert_eq!(rows.iter().filter(|r| r.as_str() == "────").count(), 1);
+        assert_eq!(rows.iter().filter(|r| r.trim().is_empty()).count(), 5);
+    }
+
+    #[test]
+    fn diagonals_follow_the_cell_aspect() {
+        // On 1:2 cells a 45° contour steps two columns per row.
+        let rising = RgbaImage::from_fn(12, 6, |x, y| if x + 2 * y < 12 { BLACK } else { WHITE });
+        let falling = RgbaImage::from_fn(12, 6, |x, y| if x < 2 * y { BLACK } else { WHITE });
+        assert!(glyphs(&rising).concat().contains('/'));
+        assert!(!glyphs(&rising).concat().contains('\\'));
+        assert!(glyphs(&falling).concat().contains('\\'));
+        assert!(!glyphs(&falling).concat().contains('/'));
+    }
+
+    #[test]
+    fn taller_cells_steepen_diagonals() {
+        // A 45° contour in pixels stays diagonal on 1:2 cells, but stands
+        // almost upright on cells four times as tall as they are wide.
+        let img = RgbaImage::from_fn(8, 8, |x, y| if x + y < 8 { BLACK } else { WHITE });
+        let drawn = |aspect| -> String {
+            detect(&img, aspect)
+                .into_iter()
+                .flatten()
+                .map(|e| e.dir.glyph())
+                .collect()
+        };
+        let count = |text: &str, glyph| text.chars().filter(|&c| c == glyph).count();
+        let diagonal = drawn(DEFAULT_CELL_ASPECT);
+        assert!(count(&diagonal, '/') > count(&diagonal, '│'), "{diagonal}");
+        let upright = drawn(4.0);
+        assert!(count(&upright, '│') > count(&upright, '/'), "{upright}");
+    }
+
+    #[test]
+    fn edges_take_the_bright_side_colour() {
+        let img = RgbaImage::from_fn(8, 1, |x, _| if x < 4 { BLACK } else { WHITE });
+        let edge = detect(&img, DEFAULT_CELL_ASPECT)
+            .into_iter()
+            .flatten()
+            .next()
+            .expect("one edge");
+        assert_eq!(img.get_pixel(edge.source.0, edge.source.1), &WHITE);
+    }
+
+    #[test]
+    fn transparent_background_outlines_sprites() {
+        let img = RgbaImage::from_fn(6, 6, |x, y| {
+            if (2..4).contains(&x) && (2..4).contains(&y) {
+                WHITE
+            } else {
+                Rgba([0, 0, 0, 0])
+            }
+        });
+        assert!(
+            detect(&img, DEFAULT_CELL_ASPECT)
+                .iter()
+                .any(Option::is_some)
+        );
+    }
+}
@@ -0,0 +1,214 @@
+//! Serialisation of a [`CellGrid`] into terminal output.
+//!
+//! [`AnsiEncoder`] writes SGR escape sequences at whatever depth its
+//! [`ColorMode`] asks for, tracking the pen across each row so unchanged
+//! colours and attributes are not repeated. Every row ends with the pen
+//! reset, so rows can be indented or reordered after encoding.
+
+use std::fmt::Write as _;
+use std::io::{self, Write};
+
+use super::color::{ColorState, Sgr};
+use super::grid::{Attributes, Cell, CellGrid};
+use super::types::ColorMode;
+
+/// Writes a [`CellGrid`] in some output format.
+///
+/// Implement this to export rendered art without parsing ANSI back; the
+/// grid carries each cell's glyph, colours, width and attributes.
+pub trait Encoder {
+    /// Writes `grid` to `writer`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing to `writer` fails.
+    fn encode(&self, grid: &CellGrid, writer: &mut dyn Write) -> io::Result<()>;
+}
+
+/// Encodes a grid as ANSI text for a terminal.
+///
+/// Colours are quantized per cell to the encoder's [`ColorMode`]: 24-bit,
+/// the xterm 256-colour cube, the 16 basic colours, the grayscale ramp or a
+/// locked palette. [`ColorMode::None`] writes the glyphs alone, without any
+/// escape sequences.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AnsiEncoder {
+    mode: ColorMode,
+}
+
+impl AnsiEncoder {
+    /// An encoder emitting colours for `mode`.
+    #[must_use]
+    pub const fn new(mode: ColorMode) -> Self {
+        Self { mode }
+    }
+
+    /// Returns the colour mode.
+    #[must_use]
+    pub const fn color_mode(&self) -> ColorMode {
+        self.mode
+    }
+
+    /// Appends one row, reset at the end, to `out`.
+    fn encode_row(&self, row: &[Cell], out: &mut String) {
+        if self.mode == ColorMode::None {
+            for cell in row {
+                out.push_str(&cell.glyph);
+            }
+            out.push('\n');
+            return;
+        }
+
+        let mut last = Pen::default();
+        for cell in row {
+            let pen = self.pen(cell, last);
+            if pen != last {
+                if pen == Pen::default() || !last.attributes.difference(pen.attributes).is_empty() {
+                    out.push_str("\x1b[0m");
+                    last = Pen::default();
+                }
+                for (attr, code) in Attributes::SGR {
+                    if pen.attributes.difference(last.attributes).contains(attr) {
+                        let _ = write!(out, "\x1b[{code}m");
+                    }
+                }
+                if pen.fg != last.fg {
+                    match pen.fg {
+                        ColorState::None => out.push_str("\x1b[39m"),
+                        fg => {
+                            let _ = write!(out, "{}", Sgr(fg, 38));
+                        }
+                    }
+                }
+                if pen.bg != last.bg {
+                    match pen.bg {
+                        ColorState::None => out.push_str("\x1b[49m"),
+                        bg => {
+                            let _ = write!(out, "{}", Sgr(bg, 48));
+                        }
+                    }
+                }
+                last = pen;
+            }
+            out.push_str(&cell.glyph);
+        }
+        if last != Pen::default() {
+            out.push_str("\x1b[0m");
+        }
+        out.push('\n');
+    }
+
+    /// The pen `cell` needs. A glyph without ink leaves the foreground as
+    /// it was, since no part of the cell shows it.
+    fn pen(&self, cell: &Cell, last: Pen) -> Pen {
+        let state =
+            |c: Option<[u8; 3]>| c.map_or(ColorState::None, |c| ColorState::from_rgb(c, self.mode));
+        let ink = !cell.is_blank() || !cell.attributes.is_empty();
+        Pen {
+            fg: if ink { state(cell.fg) } else { last.fg },
+            bg: state(cell.bg),
+            attributes: cell.attributes,
+        }
+    }
+}
+
+impl Encoder for AnsiEncoder {
+    fn encode(&self, grid: &CellGrid, writer: &mut dyn Write) -> io::Result<()> {
+        let mut out = String::new();
+        for row in grid.rows() {
+            out.clear();
+            self.encode_row(row, &mut out);
+            writer.write_all(out.as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+/// The SGR state a cell is drawn with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Pen {
+    fg: ColorState,
+    bg: ColorState,
+    attributes: Attributes,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Ansi16Palette;
+
+    fn encode(mode: ColorMode, row: Vec<Cell>) -> String {
+        let mut out = Vec::new();
+        AnsiEncoder::new(mode)
+            .encode(&CellGrid::from(vec![row]), &mut out)
+            .expect("write");
+        String::from_utf8(out).expect("utf-8")
+    }
+
+    fn ink(glyph: &'static str, fg: [u8; 3]) -> Cell {
+        Cell::new(glyph, 1).with_fg(Some(fg))
+    }
+
+    #[test]
+    fn repeated_colours_are_written_once() {
+        let mode = ColorMode::Ansi16(Ansi16Palette::XTERM);
+        let row = vec![ink("#", [0, 0, 255]), ink("#", [0, 0, 250])];
+        assert_eq!(encode(mode, row), "\x1b[94m##\x1b[0m\n");
+    }
+
+    #[test]
+    fn foreground_comes_before_background() {
+        let cell = ink("▚", [255, 255, 255]).with_bg(Some([0, 0, 0]));
+        assert_eq!(
+            encode(ColorMode::TrueColor, vec![cell]),
+            "\x1b[38;2;255;255;255m\x1b[48;2;0;0;0m▚\x1b[0m\n"
+        );
+    }
+
+    #[test]
+    fn blank_cells_keep_the_foreground() {
+        let row = vec![ink("a", [1, 2, 3]), Cell::blank(1), ink("b", [1, 2, 3])];
+        assert_eq!(
+            encode(ColorMode::TrueColor, row),
+            "\x1b[38;2;1;2;3ma b\x1b[0m\n"
+        );
+    }
+
+    #[test]
+    fn default_colours_are_restored() {
+        let solid = Cell::blank(1).with_bg(Some([9, 9, 9]));
+        let row = vec![solid.clone(), Cell::blank(1), solid, Cell::new("x", 1)];
+        assert_eq!(
+            encode(ColorMode::TrueColor, row),
+            "\x1b[48;2;9;9;9m \x1b[0m \x1b[48;2;9;9;9m \x1b[0mx\n"
+        );
+        let row = vec![
+            ink("a", [1, 2, 3]).with_bg(Some([9, 9, 9])),
+            ink("b", [1, 2, 3]),
+        ];
+        assert_eq!(
+            encode(ColorMode::TrueColor, row),
+            "\x1b[38;2;1;2;3m\x1b[48;2;9;9;9ma\x1b[49mb\x1b[0m\n"
+        );
+    }
+
+    #[test]
+    fn attributes_are_set_and_cleared() {
+        let bold = Cell::new("B", 1).with_attributes(Attributes::BOLD);
+        let both = Cell::new("U", 1).with_attributes(Attributes::BOLD | Attributes::UNDERLINE);
+        let row = vec![bold.clone(), both, bold];
+        assert_eq!(
+            encode(ColorMode::TrueColor, row),
+            "\x1b[1mB\x1b[4mU\x1b[0m\x1b[1mB\x1b[0m\n"
+        );
+    }
+
+    #[test]
+    fn no_colour_writes_glyphs_only() {
+        let row = vec![
+            ink("a", [1, 2, 3]).with_attributes(Attributes::BOLD),
+            Cell::blank(2),
+        ];
+        assert_eq!(encode(ColorMode::None, row), "a  \n");
+    }
+}
@@ -0,0 +1,281 @@
+//! The intermediate cell grid every text [`CharsetMode`] renders into.
+//!
+//! Renderers decide what each terminal cell shows; an [`Encoder`] decides
+//! how that is written out. Colours in the grid are always 24-bit, so one
+//! grid can be encoded for any [`ColorMode`].
+//!
+//! [`CharsetMode`]: super::CharsetMode
+//! [`ColorMode`]: super::ColorMode
+//! [`Encoder`]: super::Encoder
+
+use std::borrow::Cow;
+
+/// Text attributes of a [`Cell`], combined with `|`.
+///
+/// ```
+/// use px2ansi::Attributes;
+///
+/// let attrs = Attributes::BOLD | Attributes::UNDERLINE;
+/// assert!(attrs.contains(Attributes::BOLD));
+/// assert!(!attrs.contains(Attributes::ITALIC));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Attributes(u8);
+
+impl Attributes {
+    /// No attributes.
+    pub const NONE: Self = Self(0);
+    /// Bold or increased intensity (SGR 1).
+    pub const BOLD: Self = Self(1 << 0);
+    /// Faint or decreased intensity (SGR 2).
+    pub const DIM: Self = Self(1 << 1);
+    /// Italic (SGR 3).
+    pub const ITALIC: Self = Self(1 << 2);
+    /// Underline (SGR 4).
+    pub const UNDERLINE: Self = Self(1 << 3);
+    /// Swapped foreground and background (SGR 7).
+    pub const REVERSE: Self = Self(1 << 4);
+    /// Crossed-out (SGR 9).
+    pub const STRIKETHROUGH: Self = Self(1 << 5);
+
+    /// Every attribute with its SGR parameter, in emission order.
+    pub(super) const SGR: [(Self, u8); 6] = [
+        (Self::BOLD, 1),
+        (Self::DIM, 2),
+        (Self::ITALIC, 3),
+        (Self::UNDERLINE, 4),
+        (Self::REVERSE, 7),
+        (Self::STRIKETHROUGH, 9),
+    ];
+
+    /// Returns `true` if every attribute in `other` is set.
+    #[must_use]
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns `true` if no attribute is set.
+    #[must_use]
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// The attributes set in `self` but not in `other`.
+    #[must_use]
+    pub const fn difference(self, other: Self) -> Self {
+        Self(self.0 & !other.0)
+    }
+}
+
+impl std::ops::BitOr for Attributes {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl std::ops::BitOrAssign for Attributes {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+/// One terminal cell: a glyph with its colours and attributes.
+///
+/// `fg` and `bg` of `None` keep the terminal's default colours. `width` is
+/// the number of terminal columns the glyph covers, `2` for the wide glyphs
+/// of the Kanji and Chinese ramps and for `██`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cell {
+    /// The text drawn in the cell, usually a single character.
+    pub glyph: Cow<'static, str>,
+    /// Foreground (ink) colour.
+    pub fg: Option<[u8; 3]>,
+    /// Background (paper) colour.
+    pub bg: Option<[u8; 3]>,
+    /// Terminal columns covered by `glyph`.
+    pub width: u8,
+    /// Text attributes.
+    pub attributes: Attributes,
+}
+
+impl Cell {
+    /// A cell drawing `glyph` in the default colours.
+    #[must_use]
+    pub fn new(glyph: impl Into<Cow<'static, str>>, width: u8) -> Self {
+        Self {
+            glyph: glyph.into(),
+            fg: None,
+            bg: None,
+            width,
+            attributes: Attributes::NONE,
+        }
+    }
+
+    /// An empty cell of `width` spaces.
+    #[must_use]
+    pub fn blank(width: u8) -> Self {
+        Self::new(if width >= 2 { "  " } else { " " }, width)
+    }
+
+    /// A cell drawing the single character `glyph`.
+    pub(super) fn from_char(glyph: char, width: u8) -> Self {
+        let mut buf = [0u8; 4];
+        Self::new(glyph.encode_utf8(&mut buf).to_owned(), width)
+    }
+
+    /// Sets the foreground colour.
+    #[must_use]
+    pub const fn with_fg(mut self, fg: Option<[u8; 3]>) -> Self {
+        self.fg = fg;
+        self
+    }
+
+    /// Sets the background colour.
+    #[must_use]
+    pub const fn with_bg(mut self, bg: Option<[u8; 3]>) -> Self {
+        self.bg = bg;
+        self
+    }
+
+    /// Sets the text attributes.
+    #[must_use]
+    pub const fn with_attributes(mut self, attributes: Attributes) -> Self {
+        self.attributes = attributes;
+        self
+    }
+
+    /// Returns `true` if the glyph draws no ink, so its foreground colour is
+    /// never visible: spaces, the ideographic space and the empty braille
+    /// pattern.
+    #[must_use]
+    pub fn is_blank(&self) -> bool {
+        self.glyph
+            .chars()
+            .all(|c| c.is_whitespace() || c == '\u{2800}')
+    }
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Self::blank(1)
+    }
+}
+
+/// Rows of [`Cell`]s produced by [`render_grid`](super::render_grid).
+///
+/// ```
+/// use px2ansi::{AnsiEncoder, Cell, CellGrid, ColorMode, Encoder};
+///
+/// let mut grid = CellGrid::new();
+/// grid.push_row(vec![Cell::new("#", 1).with_fg(Some([255, 0, 0])), Cell::blank(1)]);
+/// assert_eq!(grid.width(), 2);
+///
+/// let mut out = Vec::new();
+/// AnsiEncoder::new(ColorMode::TrueColor).encode(&grid, &mut out)?;
+/// assert_eq!(out, b"\x1b[38;2;255;0;0m# \x1b[0m\n");
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CellGrid {
+    rows: Vec<Vec<Cell>>,
+}
+
+impl CellGrid {
+    /// An empty grid.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { rows: Vec::new() }
+    }
+
+    /// Appends a row below the existing ones.
+    pub fn push_row(&mut self, row: Vec<Cell>) {
+        self.rows.push(row);
+    }
+
+    /// The rows, top first.
+    #[must_use]
+    pub fn rows(&self) -> &[Vec<Cell>] {
+        &self.rows
+    }
+
+    /// The rows, for editing cells in place or adding and removing them.
+    pub const fn rows_mut(&mut self) -> &mut Vec<Vec<Cell>> {
+        &mut self.rows
+    }
+
+    /// The cell at `row`, `col`, counting cells rather than columns.
+    #[must_use]
+    pub fn get(&self, row: usize, col: usize) -> Option<&Cell> {
+        self.rows.get(row)?.get(col)
+    }
+
+    /// Mutable access to the cell at `row`, `col`.
+    pub fn get_mut(&mut self, row: usize, col: usize) -> Option<&mut Cell> {
+        self.rows.get_mut(row)?.get_mut(col)
+    }
+
+    /// Number of rows.
+    #[must_use]
+    pub fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Terminal columns of the widest row.
+    #[must_use]
+    pub fn width(&self) -> usize {
+        self.rows
+            .iter()
+            .map(|row| row.iter().map(|c| usize::from(c.width)).sum())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Consumes the grid, returning its rows.
+    #[must_use]
+    pub fn into_rows(self) -> Vec<Vec<Cell>> {
+        self.rows
+    }
+}
+
+impl From<Vec<Vec<Cell>>> for CellGrid {
+    fn from(rows: Vec<Vec<Cell>>) -> Self {
+        Self { rows }
+    }
+}
+
+impl FromIterator<Vec<Cell>> for CellGrid {
+    fn from_iter<I: IntoIterator<Item = Vec<Cell>>>(iter: I) -> Self {
+        Self {
+            rows: iter.into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blank_glyphs_have_no_ink() {
+        for glyph in [" ", "  ", "\u{3000}", "\u{2800}"] {
+            assert!(Cell::new(glyph, 1).is_blank(), "{glyph:?}");
+        }
+        for glyph in ["▀", "\u{2801}", "a", "██"] {
+            assert!(!Cell::new(glyph, 1).is_blank(), "{glyph:?}");
+        }
+    }
+
+    #[test]
+    fn width_counts_columns_of_the_widest_row() {
+        let grid: CellGrid = [
+            vec![Cell::new("龍", 2), Cell::blank(1)],
+            vec![Cell::blank(1); 2],
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(CellGrid::new().width(), 0);
+    }
+}
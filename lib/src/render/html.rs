@@ -0,0 +1,576 @@
+//! Serialisation of rendered art as HTML.
+//!
+//! [`HtmlEncoder`] writes a [`CellGrid`] as a `<pre>` block, so the art stays
+//! copy-pasteable text. Runs of identically styled cells share one `<span>`,
+//! coloured either with inline styles or with classes from a compact palette
+//! of the colours actually used. Colours are quantized to the encoder's
+//! [`ColorMode`] the same way [`AnsiEncoder`](super::AnsiEncoder) does, so the
+//! page shows what the terminal would. [`write_html`] also covers the pixel
+//! protocols by embedding the prepared image as a PNG.
+
+use std::borrow::Cow;
+use std::fmt::Write as _;
+use std::io::{self, Cursor, Write};
+
+use image::{DynamicImage, ImageFormat, RgbaImage};
+
+use super::base64;
+use super::color::ColorState;
+use super::encode::Encoder;
+use super::grid::{Attributes, Cell, CellGrid};
+use super::options::RenderOptions;
+use super::renderer::render_grid;
+use super::types::{CharsetMode, ColorMode};
+use super::utils::{CELL_PX_H, CELL_PX_W, composite_bg};
+#[cfg(feature = "rasterize")]
+use crate::themes::RasterTheme;
+
+const DEFAULT_FONT_FAMILY: &str =
+    "ui-monospace, 'Cascadia Mono', Menlo, Consolas, 'DejaVu Sans Mono', monospace";
+
+/// Font size in CSS pixels, matching the rasterizer's cell height.
+const DEFAULT_FONT_SIZE: f32 = 14.0;
+
+/// Class of the `<pre>` element; palette rules are scoped under it.
+const ROOT_CLASS: &str = "px2ansi";
+
+/// Encodes a grid as an HTML `<pre>` block or a standalone page.
+///
+/// The block uses `line-height: 1` so half blocks and braille rows tile
+/// without gaps. Bold, italic, underline and strikethrough map to the usual
+/// CSS properties, dim to half opacity, and reverse swaps the colours.
+///
+/// ```
+/// use px2ansi::{Cell, CellGrid, ColorMode, Encoder, HtmlEncoder};
+///
+/// let grid = CellGrid::from(vec![vec![Cell::new("<>", 1).with_fg(Some([255, 0, 0]))]]);
+/// let mut out = Vec::new();
+/// HtmlEncoder::new(ColorMode::TrueColor).encode(&grid, &mut out)?;
+/// let html = String::from_utf8(out).unwrap();
+/// assert!(html.contains(r#"<span style="color:#ff0000">&lt;&gt;</span>"#));
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct HtmlEncoder {
+    mode: ColorMode,
+    classes: bool,
+    standalone: bool,
+    font_family: Cow<'static, str>,
+    font_size: f32,
+    background: Option<[u8; 3]>,
+    foreground: Option<[u8; 3]>,
+    title: Cow<'static, str>,
+}
+
+impl HtmlEncoder {
+    /// An encoder quantizing colours for `mode`, writing an inline-styled
+    /// `<pre>` fragment. [`ColorMode::None`] writes the glyphs alone.
+    #[must_use]
+    pub const fn new(mode: ColorMode) -> Self {
+        Self {
+            mode,
+            classes: false,
+            standalone: false,
+            font_family: Cow::Borrowed(DEFAULT_FONT_FAMILY),
+            font_size: DEFAULT_FONT_SIZE,
+            background: None,
+            foreground: None,
+            title: Cow::Borrowed("px2ansi"),
+        }
+    }
+
+    /// Returns the colour mode.
+    #[must_use]
+    pub const fn color_mode(&self) -> ColorMode {
+        self.mode
+    }
+
+    /// Colours spans with classes from a `<style>` palette instead of inline
+    /// styles. Class names carry the colour, so fragments from different
+    /// images can share a page.
+    #[must_use]
+    pub const fn with_classes(mut self, classes: bool) -> Self {
+        self.classes = classes;
+        self
+    }
+
+    /// Wraps the block in a complete HTML document.
+    #[must_use]
+    pub const fn with_standalone(mut self, standalone: bool) -> Self {
+        self.standalone = standalone;
+        self
+    }
+
+    /// Sets the CSS `font-family` list.
+    #[must_use]
+    pub fn with_font_family(mut self, family: impl Into<Cow<'static, str>>) -> Self {
+        self.font_family = family.into();
+        self
+    }
+
+    /// Sets the font size in CSS pixels. Non-positive or non-finite sizes
+    /// are ignored.
+    #[must_use]
+    pub fn with_font_size(mut self, px: f32) -> Self {
+        if px.is_finite() && px > 0.0 {
+            self.font_size = px;
+        }
+        self
+    }
+
+    /// Sets the colour behind cells without a background of their own.
+    #[must_use]
+    pub const fn with_background(mut self, color: Option<[u8; 3]>) -> Self {
+        self.background = color;
+        self
+    }
+
+    /// Sets the colour of glyphs without a foreground of their own.
+    #[must_use]
+    pub const fn with_foreground(mut self, color: Option<[u8; 3]>) -> Self {
+        self.foreground = color;
+        self
+    }
+
+    /// Sets the `<title>` of a standalone page.
+    #[must_use]
+    pub fn with_title(mut self, title: impl Into<Cow<'static, str>>) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    /// Uses `theme`'s background with white text, as
+    /// [`rasterize_ansi_with_theme`](crate::rasterize_ansi_with_theme) does.
+    #[cfg(feature = "rasterize")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "rasterize")))]
+    #[must_use]
+    pub const fn with_theme(self, theme: RasterTheme) -> Self {
+        let [r, g, b, _] = theme.color().0;
+        self.with_background(Some([r, g, b]))
+            .with_foreground(Some([255, 255, 255]))
+    }
+
+    /// Writes `rgba` as a PNG `<img>` inside the block, sized to the cell
+    /// box the terminal image protocols would give it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if PNG encoding or writing to `writer` fails.
+    pub fn encode_image(&self, rgba: &RgbaImage, writer: &mut dyn Write) -> io::Result<()> {
+        let mut png = Vec::new();
+        rgba.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .map_err(|e| io::Error::other(e.to_string()))?;
+        let cols = rgba.width().div_ceil(CELL_PX_W).max(1);
+        let rows = rgba.height().div_ceil(CELL_PX_H).max(1);
+        let body = format!(
+            "<img alt=\"\" style=\"display:block;width:{cols}ch;height:{rows}em;\
+             object-fit:contain\" src=\"data:image/png;base64,{}\">",
+            base64::encode(&png)
+        );
+        writer.write_all(self.document("", &body).as_bytes())
+    }
+
+    /// The style of `cell`. A glyph without ink keeps the foreground of the
+    /// cell before it, so it joins that cell's run.
+    fn style(&self, cell: &Cell, last: Style) -> Style {
+        if self.mode == ColorMode::None {
+            return Style::default();
+        }
+        let rgb = |c: Option<[u8; 3]>| {
+            c.and_then(|c| ColorState::from_rgb(c, self.mode).to_rgb(self.mode))
+        };
+        let ink = !cell.is_blank() || !cell.attributes.is_empty();
+        Style {
+            fg: if ink { rgb(cell.fg) } else { last.fg },
+            bg: rgb(cell.bg),
+            attributes: cell.attributes,
+        }
+    }
+
+    /// The text and background colours `style` is drawn with.
+    fn colors(&self, style: Style) -> (Option<[u8; 3]>, Option<[u8; 3]>) {
+        if style.attributes.contains(Attributes::REVERSE) {
+            (style.bg.or(self.background), style.fg.or(self.foreground))
+        } else {
+            (style.fg, style.bg)
+        }
+    }
+
+    /// Splits a row into runs of cells drawn with the same style.
+    fn runs(&self, row: &[Cell]) -> Vec<(Style, String)> {
+        let mut runs: Vec<(Style, String)> = Vec::new();
+        let mut last = Style::default();
+        for cell in row {
+            let style = self.style(cell, last);
+            match runs.last_mut() {
+                Some((run, text)) if *run == style => text.push_str(&cell.glyph),
+                _ => runs.push((style, cell.glyph.to_string())),
+            }
+            last = style;
+        }
+        runs
+    }
+
+    /// The `<style>` rules for every colour and attribute in `rows`.
+    fn palette(&self, rows: &[Vec<(Style, String)>]) -> String {
+        let mut fgs = Vec::new();
+        let mut bgs = Vec::new();
+        let mut attributes = Attributes::NONE;
+        for &(style, _) in rows.iter().flatten() {
+            let (fg, bg) = self.colors(style);
+            if let Some(fg) = fg.filter(|c| !fgs.contains(c)) {
+                fgs.push(fg);
+            }
+            if let Some(bg) = bg.filter(|c| !bgs.contains(c)) {
+                bgs.push(bg);
+            }
+            attributes |= style.attributes;
+        }
+
+        let mut rules = String::new();
+        for fg in fgs {
+            let _ = writeln!(rules, ".{ROOT_CLASS} .px-f{}{{color:{}}}", hex(fg), Hex(fg));
+        }
+        for bg in bgs {
+            let _ = writeln!(
+                rules,
+                ".{ROOT_CLASS} .px-b{}{{background:{}}}",
+                hex(bg),
+                Hex(bg)
+            );
+        }
+        for (attr, class, declaration) in ATTRIBUTE_CSS {
+            if attributes.contains(attr) {
+                let _ = writeln!(rules, ".{ROOT_CLASS} .{class}{{{declaration}}}");
+            }
+        }
+        if attributes.contains(Attributes::UNDERLINE | Attributes::STRIKETHROUGH) {
+            let _ = writeln!(
+                rules,
+                ".{ROOT_CLASS} .px-u.px-s{{text-decoration:underline line-through}}"
+            );
+        }
+        rules
+    }
+
+    /// Appends `text` to `out`, wrapped in a span if `style` isn't the
+    /// default.
+    fn write_run(&self, style: Style, text: &str, out: &mut String) {
+        let (fg, bg) = self.colors(style);
+        if fg.is_none() && bg.is_none() && style.attributes.is_empty() {
+            escape(text, out);
+            return;
+        }
+        if self.classes {
+            let mut classes = Vec::new();
+            classes.extend(fg.map(|c| format!("px-f{}", hex(c))));
+            classes.extend(bg.map(|c| format!("px-b{}", hex(c))));
+            for (attr, class, _) in ATTRIBUTE_CSS {
+                if style.attributes.contains(attr) {
+                    classes.push(class.to_owned());
+                }
+            }
+            let _ = write!(out, "<span class=\"{}\">", classes.join(" "));
+        } else {
+            let mut declarations = Vec::new();
+            declarations.extend(fg.map(|c| format!("color:{}", Hex(c))));
+            declarations.extend(bg.map(|c| format!("background:{}", Hex(c))));
+            for (attr, _, declaration) in ATTRIBUTE_CSS {
+                if style.attributes.contains(attr) && !declaration.starts_with("text-") {
+                    declarations.push(declaration.to_owned());
+                }
+            }
+            let decorations: Vec<_> = [
+                (Attributes::UNDERLINE, "underline"),
+                (Attributes::STRIKETHROUGH, "line-through"),
+            ]
+            .into_iter()
+            .filter(|&(attr, _)| style.attributes.contains(attr))
+            .map(|(_, line)| line)
+            .collect();
+            if !decorations.is_empty() {
+                declarations.push(format!("text-decoration:{}", decorations.join(" ")));
+            }
+            let _ = write!(out, "<span style=\"{}\">", declarations.join(";"));
+        }
+        escape(text, out);
+        out.push_str("</span>");
+    }
+
+    /// Wraps `body` in the `<pre>` block, with the palette `rules`, and in a
+    /// full page if standalone.
+    fn document(&self, rules: &str, body: &str) -> String {
+        let mut pre = format!(
+            "<pre class=\"{ROOT_CLASS}\" style=\"display:inline-block;margin:0;font-family:"
+        );
+        escape(&self.font_family, &mut pre);
+        let _ = write!(pre, ";font-size:{}px;line-height:1", self.font_size);
+        if let Some(fg) = self.foreground {
+            let _ = write!(pre, ";color:{}", Hex(fg));
+        }
+        if let Some(bg) = self.background {
+            let _ = write!(pre, ";background:{}", Hex(bg));
+        }
+        let _ = writeln!(pre, "\">{body}</pre>");
+
+        if !self.standalone {
+            return if rules.is_empty() {
+                pre
+            } else {
+                format!("<style>\n{rules}</style>\n{pre}")
+            };
+        }
+        let mut page =
+            String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>");
+        escape(&self.title, &mut page);
+        page.push_str("</title>\n<style>\nbody{margin:0");
+        if let Some(bg) = self.background {
+            let _ = write!(page, ";background:{}", Hex(bg));
+        }
+        let _ = write!(
+            page,
+            "}}\n{rules}</style>\n</head>\n<body>\n{pre}</body>\n</html>\n"
+        );
+        page
+    }
+}
+
+impl Default for HtmlEncoder {
+    fn default() -> Self {
+        Self::new(ColorMode::TrueColor)
+    }
+}
+
+impl Encoder for HtmlEncoder {
+    fn encode(&self, grid: &CellGrid, writer: &mut dyn Write) -> io::Result<()> {
+        let rows: Vec<_> = grid.rows().iter().map(|row| self.runs(row)).collect();
+        let rules = if self.classes {
+            self.palette(&rows)
+        } else {
+            String::new()
+        };
+        let mut body = String::new();
+        for (i, row) in rows.iter().enumerate() {
+            if i > 0 {
+                body.push('\n');
+            }
+            for (style, text) in row {
+                self.write_run(*style, text, &mut body);
+            }
+        }
+        writer.write_all(self.document(&rules, &body).as_bytes())
+    }
+}
+
+/// The colours and attributes a run of cells is drawn with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Style {
+    fg: Option<[u8; 3]>,
+    bg: Option<[u8; 3]>,
+    attributes: Attributes,
+}
+
+/// Every attribute with its palette class and CSS declaration. Reverse has
+/// no declaration of its own; it swaps the colours instead.
+const ATTRIBUTE_CSS: [(Attributes, &str, &str); 5] = [
+    (Attributes::BOLD, "px-bold", "font-weight:bold"),
+    (Attributes::DIM, "px-dim", "opacity:0.5"),
+    (Attributes::ITALIC, "px-italic", "font-style:italic"),
+    (Attributes::UNDERLINE, "px-u", "text-decoration:underline"),
+    (
+        Attributes::STRIKETHROUGH,
+        "px-s",
+        "text-decoration:line-through",
+    ),
+];
+
+/// Formats a colour as a CSS `#rrggbb` literal.
+pub(super) struct Hex(pub(super) [u8; 3]);
+
+impl std::fmt::Display for Hex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{}", hex(self.0))
+    }
+}
+
+/// The six hex digits of a colour, for class names.
+fn hex([r, g, b]: [u8; 3]) -> String {
+    format!("{r:02x}{g:02x}{b:02x}")
+}
+
+/// Appends `text` to `out` with the HTML special characters escaped.
+pub(super) fn escape(text: &str, out: &mut String) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+}
+
+/// Renders a prepared image to `writer` as HTML using the mode specified in
+/// `options`.
+///
+/// Text modes are rendered into a [`CellGrid`] with [`render_grid`] and
+/// written by `encoder`. The pixel protocols ([`CharsetMode::Sixel`],
+/// [`CharsetMode::Kitty`] and [`CharsetMode::Iterm2`]) have no text to keep,
+/// so the image is embedded as a PNG instead, composited against
+/// [`RenderOptions::bg_color`] when one is set.
+///
+/// # Errors
+///
+/// Returns an error if writing to `writer` fails, or an
+/// [`io::ErrorKind::Unsupported`] error for [`CharsetMode::Shape`] without
+/// the `rasterize` feature.
+pub fn write_html<W: Write>(
+    img: &DynamicImage,
+    writer: &mut W,
+    options: RenderOptions,
+    encoder: &HtmlEncoder,
+) -> io::Result<()> {
+    if matches!(
+        options.charset(),
+        CharsetMode::Sixel | CharsetMode::Kitty | CharsetMode::Iterm2
+    ) {
+        return encoder.encode_image(&composite_bg(img, &options), writer);
+    }
+    let grid = render_grid(img, options).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Shape-matched ASCII requires the 'rasterize' feature",
+        )
+    })?;
+    encoder.encode(&grid, writer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Ansi16Palette;
+
+    fn encode(encoder: &HtmlEncoder, rows: Vec<Vec<Cell>>) -> String {
+        let mut out = Vec::new();
+        encoder
+            .encode(&CellGrid::from(rows), &mut out)
+            .expect("write");
+        String::from_utf8(out).expect("utf-8")
+    }
+
+    /// The text between the `<pre>` tags.
+    fn body(html: &str) -> &str {
+        let start = html.find("\">").expect("pre open") + 2;
+        let end = html.rfind("</pre>").expect("pre close");
+        &html[start..end]
+    }
+
+    fn ink(glyph: &'static str, fg: [u8; 3]) -> Cell {
+        Cell::new(glyph, 1).with_fg(Some(fg))
+    }
+
+    #[test]
+    fn runs_share_a_span_and_text_is_escaped() {
+        let row = vec![
+            ink("a", [1, 2, 3]),
+            Cell::blank(1),
+            ink("&", [1, 2, 3]),
+            ink("b", [1, 2, 3]).with_bg(Some([255, 255, 255])),
+        ];
+        let html = encode(&HtmlEncoder::default(), vec![row, vec![Cell::new("<", 1)]]);
+        assert_eq!(
+            body(&html),
+            "<span style=\"color:#010203\">a &amp;</span>\
+             <span style=\"color:#010203;background:#ffffff\">b</span>\n&lt;"
+        );
+    }
+
+    #[test]
+    fn colours_are_quantized_to_the_mode() {
+        let encoder = HtmlEncoder::new(ColorMode::Ansi16(Ansi16Palette::XTERM));
+        let html = encode(&encoder, vec![vec![ink("#", [20, 30, 230])]]);
+        assert_eq!(body(&html), "<span style=\"color:#0000ff\">#</span>");
+        let html = encode(
+            &HtmlEncoder::new(ColorMode::None),
+            vec![vec![ink("#", [9, 9, 9])]],
+        );
+        assert_eq!(body(&html), "#");
+    }
+
+    #[test]
+    #[expect(
+        clippy::literal_string_with_formatting_args,
+        reason = "The braces are CSS rule bodies"
+    )]
+    fn classes_come_from_a_palette_of_used_colours() {
+        let encoder = HtmlEncoder::default().with_classes(true);
+        let bold = ink("B", [255, 0, 0]).with_attributes(Attributes::BOLD);
+        let html = encode(&encoder, vec![vec![bold, ink("r", [255, 0, 0])]]);
+        assert!(html.starts_with(
+            "<style>\n.px2ansi .px-fff0000{color:#ff0000}\n\
+             .px2ansi .px-bold{font-weight:bold}\n</style>\n<pre"
+        ));
+        assert_eq!(
+            body(&html),
+            "<span class=\"px-fff0000 px-bold\">B</span><span class=\"px-fff0000\">r</span>"
+        );
+    }
+
+    #[test]
+    fn attributes_map_to_css() {
+        let cell = ink("x", [1, 1, 1])
+            .with_bg(Some([2, 2, 2]))
+            .with_attributes(
+                Attributes::REVERSE | Attributes::UNDERLINE | Attributes::STRIKETHROUGH,
+            );
+        let html = encode(&HtmlEncoder::default(), vec![vec![cell]]);
+        assert_eq!(
+            body(&html),
+            "<span style=\"color:#020202;background:#010101;\
+             text-decoration:underline line-through\">x</span>"
+        );
+    }
+
+    #[test]
+    fn standalone_pages_carry_font_and_background() {
+        let encoder = HtmlEncoder::default()
+            .with_standalone(true)
+            .with_title("a <b>")
+            .with_font_family("Iosevka")
+            .with_font_size(20.0)
+            .with_background(Some([26, 27, 38]));
+        let html = encode(&encoder, vec![vec![Cell::blank(1)]]);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<title>a &lt;b&gt;</title>"));
+        assert!(html.contains("body{margin:0;background:#1a1b26}"));
+        assert!(
+            html.contains("font-family:Iosevka;font-size:20px;line-height:1;background:#1a1b26")
+        );
+        assert!(html.ends_with("</body>\n</html>\n"));
+    }
+
+    #[test]
+    fn pixel_protocols_embed_a_png() {
+        let img = DynamicImage::ImageRgba8(RgbaImage::from_pixel(
+            16,
+            32,
+            image::Rgba([10, 20, 30, 255]),
+        ));
+        let options = RenderOptions::default().with_charset(CharsetMode::Kitty);
+        let mut out = Vec::new();
+        write_html(&img, &mut out, options, &HtmlEncoder::default()).expect("write");
+        let html = String::from_utf8(out).expect("utf-8");
+        assert!(html.contains("width:2ch;height:2em"));
+        let payload = html
+            .split("base64,")
+            .nth(1)
+            .and_then(|rest| rest.split('"').next())
+            .expect("data uri");
+        let png = base64::decode(payload).expect("valid base64");
+        let decoded = image::load_from_memory_with_format(&png, ImageFormat::Png)
+            .expect("valid png")
+            .to_rgba8();
+        assert_eq!(decoded, img.to_rgba8());
+    }
+}
@@ -0,0 +1,404 @@
+//! Error-minimising glyph selection for [`CharsetMode::Hybrid`].
+//!
+//! Every cell covers a 2x4 grid of source pixels. Each candidate glyph is
+//! described by how much of every grid position its ink covers (1.0 for a
+//! solid block part, fractions for sextant rows that straddle grid rows,
+//! braille dots and shades). For a candidate with coverage `w`, the predicted
+//! colour of a pixel is `w * fg + (1 - w) * bg`. `fg` and `bg` are the
+//! coverage-weighted Oklab means of the pixels under the ink and the paper,
+//! and the candidate with the lowest residual wins. Weighted means never
+//! extrapolate, so fractional glyphs cannot pick colours outside the cell's
+//! range; for solid block glyphs they are exactly the least-squares fit.
+//!
+//! [`CharsetMode::Hybrid`]: super::CharsetMode::Hybrid
+
+use super::blocks::BlockShape;
+use super::types::SymbolSet;
+use crate::color::{oklab_distance, oklab_to_rgb, rgb_to_oklab};
+
+/// Source pixels per cell, row-major, two per row.
+pub(super) const CELL_PIXELS: usize = 8;
+
+/// `(dx, dy)` of each source pixel within a cell, in row-major order.
+pub(super) const CELL_OFFSETS: [(u32, u32); CELL_PIXELS] = [
+    (0, 0),
+    (1, 0),
+    (0, 1),
+    (1, 1),
+    (0, 2),
+    (1, 2),
+    (0, 3),
+    (1, 3),
+];
+
+/// Fraction of a 1x1 grid position covered by one braille dot.
+const BRAILLE_DOT_COVERAGE: f32 = 0.3;
+
+/// Errors closer than this are ties, resolved in favour of the earlier glyph.
+const TIE_EPSILON: f32 = 1e-6;
+
+/// Braille dot bits by row-major grid position (see `Renderer::braille`).
+const BRAILLE_BITS: [u8; CELL_PIXELS] = [0x01, 0x08, 0x02, 0x10, 0x04, 0x20, 0x40, 0x80];
+
+/// A glyph together with its per-pixel ink coverage and the total ink and
+/// paper weight, which only depend on the coverage.
+#[derive(Debug, Clone, Copy)]
+struct Candidate {
+    glyph: char,
+    w: [f32; CELL_PIXELS],
+    ink: f32,
+    paper: f32,
+}
+
+impl Candidate {
+    fn new(glyph: char, w: [f32; CELL_PIXELS]) -> Self {
+        let ink = w.iter().sum::<f32>();
+        Self {
+            glyph,
+            w,
+            ink,
+            #[expect(clippy::cast_precision_loss, reason = "CELL_PIXELS is 8")]
+            paper: CELL_PIXELS as f32 - ink,
+        }
+    }
+
+    fn is_blank(&self) -> bool {
+        is_uniform(&self.w, 0.0)
+    }
+}
+
+/// A fitted hybrid cell ready to be written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct GlyphCell {
+    pub glyph: char,
+    /// Foreground colour, or `None` when the glyph has no ink.
+    pub fg: Option<[u8; 3]>,
+    /// Background colour, or `None` to keep the terminal's default background.
+    pub bg: Option<[u8; 3]>,
+}
+
+/// The candidate glyphs for one [`SymbolSet`], built once per render.
+#[derive(Debug, Clone)]
+pub(super) struct HybridFitter {
+    candidates: Vec<Candidate>,
+}
+
+impl HybridFitter {
+    /// Builds the candidate table. Glyphs whose coverage duplicates an earlier
+    /// candidate (for example `▌`, which is both a quadrant and an octant) are
+    /// only kept once.
+    pub(super) fn new(set: SymbolSet) -> Self {
+        let mut candidates = vec![Candidate::new(' ', [0.0; CELL_PIXELS])];
+        let mut push = |glyph: char, w: [f32; CELL_PIXELS]| {
+            if is_uniform(&w, 1.0) || candidates.iter().any(|c| same_coverage(&c.w, &w)) {
+                return;
+            }
+            candidates.push(Candidate::new(glyph, w));
+        };
+
+        if set.half {
+            push('▀', rows_coverage(&[1.0, 1.0, 0.0, 0.0]));
+            push('▄', rows_coverage(&[0.0, 0.0, 1.0, 1.0]));
+        }
+        if set.quadrant {
+            for mask in 1..15u8 {
+                push(
+                    BlockShape::Quadrant.glyph(mask),
+                    grid_coverage(mask, 2, |bit, row| (row / 2 == bit / 2).into()),
+                );
+            }
+        }
+        if set.octant {
+            for mask in 1..=u8::MAX {
+                push(
+                    BlockShape::Octant.glyph(mask),
+                    grid_coverage(mask, 4, |bit, row| (row == bit / 2).into()),
+                );
+            }
+        }
+        if set.sextant {
+            for mask in 1..63u8 {
+                push(
+                    BlockShape::Sextant.glyph(mask),
+                    grid_coverage(mask, 3, |bit, row| sextant_overlap(bit / 2, row)),
+                );
+            }
+        }
+        if set.braille {
+            for byte in 1..=u8::MAX {
+                let w = BRAILLE_BITS.map(|bit| {
+                    if byte & bit == 0 {
+                        0.0
+                    } else {
+                        BRAILLE_DOT_COVERAGE
+                    }
+                });
+                push(char::from_u32(0x2800 + u32::from(byte)).unwrap_or(' '), w);
+            }
+        }
+        if set.shade {
+            push('░', [0.25; CELL_PIXELS]);
+            push('▒', [0.5; CELL_PIXELS]);
+            push('▓', [0.75; CELL_PIXELS]);
+        }
+
+        Self { candidates }
+    }
+
+    /// Fits a cell of row-major Oklab pixels; `None` entries are transparent.
+    ///
+    /// Opaque cells pick the glyph and colour pair with the lowest Oklab
+    /// error. Cells with transparent pixels keep the default background and
+    /// pick the glyph whose coverage best matches the opaque pixels.
+    pub(super) fn fit(&self, px: &[Option<[f32; 3]>; CELL_PIXELS]) -> GlyphCell {
+        let opaque: Vec<[f32; 3]> = px.iter().flatten().copied().collect();
+        if opaque.is_empty() {
+            return GlyphCell {
+                glyph: ' ',
+                fg: None,
+                bg: None,
+            };
+        }
+        if opaque.len() < CELL_PIXELS {
+            let best = self.best_by(|c| {
+                c.w.iter()
+                    .zip(px)
+                    .map(|(w, p)| (w - if p.is_some() { 1.0 } else { 0.0 }).abs())
+                    .sum()
+            });
+            return GlyphCell {
+                glyph: best.glyph,
+                fg: (!best.is_blank()).then(|| oklab_to_rgb(mean(&opaque))),
+                bg: None,
+            };
+        }
+
+        let px = opaque;
+        let (mut best, mut fg, mut bg) = (&self.candidates[0], [0.0; 3], mean(&px));
+        let mut best_err = f32::INFINITY;
+        for c in &self.candidates {
+            let (ink, paper) = solve(c, &px);
+            let err =
+                c.w.iter()
+                    .zip(&px)
+                    .map(|(&w, &p)| oklab_distance(mix(ink, paper, w), p))
+                    .sum::<f32>();
+            // Rounding noise must not let a later glyph beat an exact match.
+            if err < best_err - TIE_EPSILON {
+                (best, fg, bg, best_err) = (c, ink, paper, err);
+            }
+        }
+
+        GlyphCell {
+            glyph: best.glyph,
+            fg: (!best.is_blank()).then(|| oklab_to_rgb(fg)),
+            bg: Some(oklab_to_rgb(bg)),
+        }
+    }
+
+    /// Picks a glyph for output without colour, assuming light ink on a dark
+    /// background: the predicted lightness of a pixel is its coverage.
+    pub(super) fn fit_mono(&self, px: &[Option<[f32; 3]>; CELL_PIXELS]) -> char {
+        self.best_by(|c| {
+            c.w.iter()
+                .zip(px)
+                .map(|(w, p)| {
+                    let d = w - p.map_or(0.0, |p| p[0]);
+                    d * d
+                })
+                .sum()
+        })
+        .glyph
+    }
+
+    /// The first candidate with the lowest `cost`.
+    fn best_by(&self, cost: impl Fn(&Candidate) -> f32) -> &Candidate {
+        let mut best = (&self.candidates[0], f32::INFINITY);
+        for c in &self.candidates {
+            let err = cost(c);
+            if err < best.1 - TIE_EPSILON {
+                best = (c, err);
+            }
+        }
+        best.0
+    }
+}
+
+/// Coverage values are built from exact fractions, so a small tolerance is
+/// enough to treat them as equal.
+fn same_coverage(a: &[f32; CELL_PIXELS], b: &[f32; CELL_PIXELS]) -> bool {
+    a.iter().zip(b).all(|(x, y)| (x - y).abs() < 1e-6)
+}
+
+fn is_uniform(w: &[f32; CELL_PIXELS], value: f32) -> bool {
+    same_coverage(w, &[value; CELL_PIXELS])
+}
+
+/// Converts an sRGB pixel to Oklab for fitting.
+pub(super) fn to_oklab([r, g, b]: [u8; 3]) -> [f32; 3] {
+    rgb_to_oklab(r, g, b)
+}
+
+/// Coverage-weighted mean colours under the ink (`fg`) and the paper (`bg`).
+/// A side with no weight takes the other side's colour.
+fn solve(c: &Candidate, px: &[[f32; 3]]) -> ([f32; 3], [f32; 3]) {
+    let mut a = [0.0f32; 3];
+    let mut b = [0.0f32; 3];
+    for (&w, p) in c.w.iter().zip(px) {
+        for ch in 0..3 {
+            a[ch] = w.mul_add(p[ch], a[ch]);
+            b[ch] = (1.0 - w).mul_add(p[ch], b[ch]);
+        }
+    }
+    let fg = a.map(|v| v / c.ink.max(f32::EPSILON));
+    let bg = b.map(|v| v / c.paper.max(f32::EPSILON));
+    match (c.ink > TIE_EPSILON, c.paper > TIE_EPSILON) {
+        (true, true) => (fg, bg),
+        (true, false) => (fg, fg),
+        _ => (bg, bg),
+    }
+}
+
+fn mix(fg: [f32; 3], bg: [f32; 3], w: f32) -> [f32; 3] {
+    [0, 1, 2].map(|ch| w.mul_add(fg[ch] - bg[ch], bg[ch]))
+}
+
+#[expect(
+    clippy::cast_precision_loss,
+    reason = "At most eight pixels are averaged"
+)]
+fn mean(px: &[[f32; 3]]) -> [f32; 3] {
+    let n = px.len().max(1) as f32;
+    [0, 1, 2].map(|ch| px.iter().map(|p| p[ch]).sum::<f32>() / n)
+}
+
+/// Coverage for a glyph made of full-width horizontal bands, one value per
+/// grid row.
+fn rows_coverage(rows: &[f32; 4]) -> [f32; CELL_PIXELS] {
+    std::array::from_fn(|i| rows[i / 2])
+}
+
+/// Coverage for a two-column block glyph with `rows` rows of sub-blocks.
+/// `overlap(bit, grid_row)` is how much of grid row `grid_row` the sub-block
+/// row holding mask bit `bit` covers.
+fn grid_coverage(
+    mask: u8,
+    rows: usize,
+    overlap: impl Fn(usize, usize) -> f32,
+) -> [f32; CELL_PIXELS] {
+    std::array::from_fn(|i| {
+        let (row, col) = (i / 2, i % 2);
+        (0..rows)
+            .map(|r| r * 2 + col)
+            .filter(|&bit| mask & (1 << bit) != 0)
+            .map(|bit| overlap(bit, row))
+            .sum()
+    })
+}
+
+/// Fraction of 4-row grid row `grid_row` covered by 3-row sextant row `row`.
+#[expect(clippy::cast_precision_loss, reason = "Row indices are below 4")]
+fn sextant_overlap(row: usize, grid_row: usize) -> f32 {
+    // Work in twelfths of the cell height: sextant rows span 4, grid rows 3.
+    let (s0, s1) = (row * 4, row * 4 + 4);
+    let (g0, g1) = (grid_row * 3, grid_row * 3 + 3);
+    s1.min(g1).saturating_sub(s0.max(g0)) as f32 / 3.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WHITE: [u8; 3] = [255, 255, 255];
+    const BLACK: [u8; 3] = [0, 0, 0];
+
+    fn cell(rgb: [[u8; 3]; CELL_PIXELS]) -> [Option<[f32; 3]>; CELL_PIXELS] {
+        rgb.map(|p| Some(to_oklab(p)))
+    }
+
+    #[test]
+    fn sextant_rows_split_grid_rows_by_area() {
+        // Sextant row 0 covers all of grid row 0 and a third of grid row 1.
+        let w = grid_coverage(0b00_0011, 3, |bit, row| sextant_overlap(bit / 2, row));
+        assert!(same_coverage(
+            &w,
+            &[1.0, 1.0, 1.0 / 3.0, 1.0 / 3.0, 0.0, 0.0, 0.0, 0.0]
+        ));
+    }
+
+    #[test]
+    fn duplicate_coverage_is_kept_once() {
+        let fitter = HybridFitter::new(SymbolSet::ALL);
+        let halves = fitter
+            .candidates
+            .iter()
+            .filter(|c| same_coverage(&c.w, &rows_coverage(&[1.0, 1.0, 0.0, 0.0])))
+            .count();
+        assert_eq!(halves, 1);
+    }
+
+    #[test]
+    fn picks_half_block_for_horizontal_split() {
+        let fitter = HybridFitter::new(SymbolSet::default());
+        let px = cell([WHITE, WHITE, WHITE, WHITE, BLACK, BLACK, BLACK, BLACK]);
+        let out = fitter.fit(&px);
+        assert_eq!(out.glyph, '▀');
+        assert_eq!((out.fg, out.bg), (Some(WHITE), Some(BLACK)));
+    }
+
+    #[test]
+    fn picks_quadrant_for_corner() {
+        let fitter = HybridFitter::new(SymbolSet::default());
+        let px = cell([BLACK, BLACK, BLACK, BLACK, WHITE, BLACK, WHITE, BLACK]);
+        let out = fitter.fit(&px);
+        assert_eq!(out.glyph, '▖');
+        assert_eq!((out.fg, out.bg), (Some(WHITE), Some(BLACK)));
+    }
+
+    #[test]
+    fn octants_are_opt_in() {
+        // Only the bottom grid row is lit: exact with the lower one-quarter
+        // block, which is only reachable through the octant table.
+        let px = cell([BLACK, BLACK, BLACK, BLACK, BLACK, BLACK, WHITE, WHITE]);
+        // Either orientation is exact: ▂ in white on black or 🮅 in black on white.
+        let exact = ['▂', '\u{1FB85}'];
+        let glyph = HybridFitter::new(SymbolSet::default()).fit(&px).glyph;
+        assert!(!exact.contains(&glyph), "{glyph}");
+        let glyph = HybridFitter::new(SymbolSet::ALL).fit(&px).glyph;
+        assert!(exact.contains(&glyph), "{glyph}");
+    }
+
+    #[test]
+    fn uniform_cell_is_solid_background() {
+        let fitter = HybridFitter::new(SymbolSet::default());
+        let out = fitter.fit(&cell([[40, 90, 200]; CELL_PIXELS]));
+        assert_eq!(out.glyph, ' ');
+        assert_eq!(out.fg, None);
+        assert_eq!(out.bg, Some([40, 90, 200]));
+    }
+
+    #[test]
+    fn restricted_set_falls_back_to_solid() {
+        let fitter = HybridFitter::new(SymbolSet::NONE);
+        let px = cell([WHITE, WHITE, WHITE, WHITE, BLACK, BLACK, BLACK, BLACK]);
+        assert_eq!(fitter.fit(&px).glyph, ' ');
+    }
+
+    #[test]
+    fn transparent_pixels_keep_default_background() {
+        let fitter = HybridFitter::new(SymbolSet::default());
+        let mut px = [None; CELL_PIXELS];
+        px[..4].fill(Some(to_oklab(WHITE)));
+        let out = fitter.fit(&px);
+        assert_eq!(out.glyph, '▀');
+        assert_eq!(out.bg, None);
+    }
+
+    #[test]
+    fn mono_uses_shades_for_mid_grey() {
+        let fitter = HybridFitter::new(SymbolSet::default());
+        let grey = rgb_to_oklab(128, 128, 128)[0];
+        let glyph = fitter.fit_mono(&[Some([grey, 0.0, 0.0]); CELL_PIXELS]);
+        assert!(['▒', '▓'].contains(&glyph), "{glyph}");
+    }
+}
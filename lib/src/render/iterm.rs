@@ -0,0 +1,120 @@
+//! iTerm2 inline image protocol output.
+//!
+//! The prepared image is encoded as PNG and sent in a single
+//! `OSC 1337 ; File=... : <base64> BEL` sequence. The width and height keys are
+//! given in terminal cells so the terminal scales the bitmap into a known cell
+//! box. Supported by iTerm2, `WezTerm`, Konsole and mintty.
+
+use std::io::{self, Cursor, Write};
+
+use image::{DynamicImage, ImageFormat, RgbaImage};
+
+use super::base64;
+use super::options::RenderOptions;
+use super::utils::{CELL_PX_H, CELL_PX_W, composite_bg};
+
+/// Renders an image using the iTerm2 inline image protocol.
+///
+/// The image is placed at the cursor and sized to `ceil(width / 8)` ×
+/// `ceil(height / 16)` cells with `preserveAspectRatio=1`, matching the pixel
+/// budget used by [`RenderOptions::calculate_dimensions`]. Transparent pixels
+/// are composited against [`RenderOptions::bg_color`] when one is set.
+///
+/// # Errors
+///
+/// Returns an error if PNG encoding fails or if writing to `writer` fails.
+pub fn write_iterm2<W: Write>(
+    img: &DynamicImage,
+    options: &RenderOptions,
+    writer: &mut W,
+) -> io::Result<()> {
+    let rgba = composite_bg(img, options);
+
+    let cols = rgba.width().div_ceil(CELL_PX_W).max(1);
+    let rows = rgba.height().div_ceil(CELL_PX_H).max(1);
+
+    writer.write_all(&encode_iterm2(&rgba, cols, rows)?)?;
+    writeln!(writer)?;
+    writer.flush()
+}
+
+/// Encodes `rgba` as a PNG inside an `OSC 1337` inline file sequence placed in
+/// a `cols` × `rows` cell box.
+fn encode_iterm2(rgba: &RgbaImage, cols: u32, rows: u32) -> io::Result<Vec<u8>> {
+    let mut png = Vec::new();
+    rgba.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .map_err(|e| io::Error::other(e.to_string()))?;
+
+    let payload = base64::encode(&png);
+    let mut out = Vec::with_capacity(payload.len() + 96);
+    write!(
+        out,
+        "\x1b]1337;File=inline=1;size={};width={cols};height={rows};preserveAspectRatio=1:",
+        png.len()
+    )?;
+    out.extend_from_slice(payload.as_bytes());
+    out.push(0x07);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    /// Splits an `OSC 1337` sequence into its `File=` arguments and decoded image.
+    fn decode_iterm2(bytes: &[u8]) -> (Vec<(String, String)>, RgbaImage) {
+        let text = std::str::from_utf8(bytes).expect("iTerm2 output is ASCII");
+        let body = text
+            .strip_prefix("\x1b]1337;File=")
+            .and_then(|s| s.strip_suffix('\x07'))
+            .expect("sequence is wrapped in OSC 1337 ... BEL");
+        let (args, data) = body.split_once(':').expect("args/payload separator");
+        let args = args
+            .split(';')
+            .filter_map(|kv| kv.split_once('='))
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let png = base64::decode(data).expect("payload is valid base64");
+        let img = image::load_from_memory_with_format(&png, ImageFormat::Png)
+            .expect("payload is a valid PNG")
+            .to_rgba8();
+        (args, img)
+    }
+
+    fn arg<'a>(args: &'a [(String, String)], name: &str) -> Option<&'a str> {
+        args.iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    #[test]
+    fn roundtrips_pixels_through_png_payload() {
+        let img = RgbaImage::from_fn(6, 4, |x, y| {
+            Rgba([
+                u8::try_from(x * 40).unwrap_or(0),
+                u8::try_from(y * 60).unwrap_or(0),
+                128,
+                255,
+            ])
+        });
+        let out = encode_iterm2(&img, 1, 1).expect("PNG encoding succeeds");
+        let (args, decoded) = decode_iterm2(&out);
+
+        assert_eq!(decoded, img);
+        assert_eq!(arg(&args, "inline"), Some("1"));
+        assert_eq!(arg(&args, "preserveAspectRatio"), Some("1"));
+    }
+
+    #[test]
+    fn placement_is_sized_in_cells() {
+        let img = DynamicImage::ImageRgba8(RgbaImage::new(20, 40));
+        let opts = RenderOptions::default();
+        let mut buf = Vec::new();
+        write_iterm2(&img, &opts, &mut buf).expect("write to Vec never fails");
+
+        let (args, _) = decode_iterm2(buf.trim_ascii_end());
+        assert_eq!(arg(&args, "width"), Some("3")); // ceil(20 / 8)
+        assert_eq!(arg(&args, "height"), Some("3")); // ceil(40 / 16)
+    }
+}
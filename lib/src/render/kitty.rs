@@ -0,0 +1,194 @@
+//! Kitty graphics protocol output.
+//!
+//! The prepared image is sent as raw 32-bit RGBA, base64 encoded and split
+//! into APC (`ESC _ G ... ESC \`) chunks of at most [`CHUNK_SIZE`] bytes. The
+//! first chunk carries the control keys, including the placement size in
+//! terminal cells, so the terminal scales the bitmap to a known cell box.
+//! Supported by kitty, `WezTerm`, Ghostty and Konsole.
+
+use std::io::{self, Write};
+
+use image::{DynamicImage, RgbaImage};
+
+use super::base64;
+use super::options::RenderOptions;
+use super::utils::{CELL_PX_H, CELL_PX_W, composite_bg};
+
+/// Maximum base64 payload per escape sequence, as required by the protocol.
+const CHUNK_SIZE: usize = 4096;
+
+/// Renders an image using the kitty graphics protocol.
+///
+/// The image is transmitted and displayed in one step (`a=T`), placed at the
+/// cursor, and sized to `ceil(width / 8)` × `ceil(height / 16)` cells to match
+/// the pixel budget used by [`RenderOptions::calculate_dimensions`].
+/// Transparent pixels are composited against [`RenderOptions::bg_color`] when
+/// one is set; otherwise the alpha channel is sent as-is.
+///
+/// # Errors
+///
+/// Returns an error if writing to `writer` fails.
+pub fn write_kitty<W: Write>(
+    img: &DynamicImage,
+    options: &RenderOptions,
+    writer: &mut W,
+) -> io::Result<()> {
+    let rgba = composite_bg(img, options);
+
+    let cols = rgba.width().div_ceil(CELL_PX_W).max(1);
+    let rows = rgba.height().div_ceil(CELL_PX_H).max(1);
+
+    writer.write_all(&encode_kitty(&rgba, cols, rows))?;
+    writeln!(writer)?;
+    writer.flush()
+}
+
+/// Encodes `rgba` as a sequence of kitty graphics APC chunks placed in a
+/// `cols` × `rows` cell box.
+fn encode_kitty(rgba: &RgbaImage, cols: u32, rows: u32) -> Vec<u8> {
+    let payload = base64::encode(rgba.as_raw());
+    let (width, height) = rgba.dimensions();
+
+    let chunks: Vec<&[u8]> = payload.as_bytes().chunks(CHUNK_SIZE).collect();
+    let last = chunks.len().saturating_sub(1);
+    let mut out = Vec::with_capacity(payload.len() + chunks.len() * 16 + 64);
+
+    // An empty image still needs one (empty) chunk so the command terminates.
+    if chunks.is_empty() {
+        out.extend_from_slice(
+            format!("\x1b_Ga=T,f=32,s={width},v={height},c={cols},r={rows},q=2;\x1b\\").as_bytes(),
+        );
+        return out;
+    }
+
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(i != last);
+        if i == 0 {
+            out.extend_from_slice(
+                format!("\x1b_Ga=T,f=32,s={width},v={height},c={cols},r={rows},q=2,m={more};")
+                    .as_bytes(),
+            );
+        } else {
+            out.extend_from_slice(format!("\x1b_Gm={more};").as_bytes());
+        }
+        out.extend_from_slice(chunk);
+        out.extend_from_slice(b"\x1b\\");
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    /// Parsed form of a kitty graphics transmission.
+    struct Decoded {
+        keys: Vec<(String, String)>,
+        pixels: Vec<u8>,
+        chunks: usize,
+    }
+
+    impl Decoded {
+        fn key(&self, name: &str) -> Option<&str> {
+            self.keys
+                .iter()
+                .find(|(k, _)| k == name)
+                .map(|(_, v)| v.as_str())
+        }
+    }
+
+    /// Splits the APC stream back into control keys and decoded pixel bytes.
+    fn decode_kitty(bytes: &[u8]) -> Decoded {
+        let text = std::str::from_utf8(bytes).expect("kitty output is ASCII");
+        let mut keys = Vec::new();
+        let mut payload = String::new();
+        let mut chunks = 0;
+
+        for apc in text.split("\x1b\\").filter(|s| !s.is_empty()) {
+            let body = apc
+                .strip_prefix("\x1b_G")
+                .expect("chunk starts with ESC _ G");
+            let (control, data) = body.split_once(';').expect("control/payload separator");
+            if chunks == 0 {
+                keys = control
+                    .split(',')
+                    .filter_map(|kv| kv.split_once('='))
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect();
+            }
+            payload.push_str(data);
+            chunks += 1;
+        }
+
+        let pixels = base64::decode(&payload).expect("payload is valid base64");
+        Decoded {
+            keys,
+            pixels,
+            chunks,
+        }
+    }
+
+    fn gradient(width: u32, height: u32) -> RgbaImage {
+        RgbaImage::from_fn(width, height, |x, y| {
+            Rgba([
+                u8::try_from(x % 256).unwrap_or(0),
+                u8::try_from(y % 256).unwrap_or(0),
+                u8::try_from((x + y) % 256).unwrap_or(0),
+                u8::try_from((x * 7) % 256).unwrap_or(0),
+            ])
+        })
+    }
+
+    #[test]
+    fn roundtrips_pixels_through_decoder() {
+        let img = gradient(5, 3);
+        let decoded = decode_kitty(&encode_kitty(&img, 1, 1));
+
+        assert_eq!(decoded.pixels, img.as_raw().as_slice());
+        assert_eq!(decoded.key("f"), Some("32"));
+        assert_eq!(decoded.key("s"), Some("5"));
+        assert_eq!(decoded.key("v"), Some("3"));
+        assert_eq!(decoded.key("m"), Some("0"));
+    }
+
+    #[test]
+    fn large_images_are_chunked_and_still_roundtrip() {
+        let img = gradient(64, 64);
+        let out = encode_kitty(&img, 8, 4);
+        let decoded = decode_kitty(&out);
+
+        // 64 * 64 * 4 bytes → 21848 base64 chars → 6 chunks of ≤ 4096.
+        assert_eq!(decoded.chunks, 6);
+        assert_eq!(decoded.pixels, img.as_raw().as_slice());
+        assert_eq!(decoded.key("m"), Some("1"));
+
+        let text = String::from_utf8_lossy(&out);
+        assert_eq!(text.matches("m=1;").count(), 5);
+        assert_eq!(text.matches("\x1b_Gm=0;").count(), 1);
+    }
+
+    #[test]
+    fn placement_is_sized_in_cells() {
+        let img = DynamicImage::ImageRgba8(gradient(20, 40));
+        let opts = RenderOptions::default();
+        let mut buf = Vec::new();
+        write_kitty(&img, &opts, &mut buf).expect("write to Vec never fails");
+
+        let decoded = decode_kitty(buf.trim_ascii_end());
+        assert_eq!(decoded.key("c"), Some("3")); // ceil(20 / 8)
+        assert_eq!(decoded.key("r"), Some("3")); // ceil(40 / 16)
+    }
+
+    #[test]
+    fn bg_color_composites_transparent_pixels() {
+        let img = DynamicImage::ImageRgba8(RgbaImage::from_pixel(2, 2, Rgba([0, 0, 0, 0])));
+        let opts = RenderOptions::builder().bg_color([10, 20, 30]).build();
+        let mut buf = Vec::new();
+        write_kitty(&img, &opts, &mut buf).expect("write to Vec never fails");
+
+        let decoded = decode_kitty(buf.trim_ascii_end());
+        assert_eq!(&decoded.pixels[..4], &[10, 20, 30, 255]);
+    }
+}
@@ -3,32 +3,67 @@
 //! The primary way to use this module is through [`write_ansi_art`], which
 //! handles the internal rendering state and dispatches the image data
 //! to the appropriate strategy based on the provided [`RenderOptions`].
+//! Text modes go through a [`CellGrid`] that [`render_grid`] exposes for
+//! post-processing and custom [`Encoder`]s.
 
-/// ANSI color state tracking and glyph-writing helpers for both
-/// serial (deduplicating) and parallel (stateless) render paths.
+/// ANSI color state tracking: quantizing colours to a [`ColorMode`] and
+/// formatting their SGR sequences.
 pub mod color;
 
 /// [`RenderOptions`] and [`RenderOptionsBuilder`]: the primary configuration
 /// types for controlling charset, resize filter, color mode, and output width.
 pub mod options;
 
+mod adjust;
+mod base64;
+mod blocks;
+mod cell;
+mod crop;
+mod dither;
+mod edges;
+mod encode;
+mod grid;
+mod html;
+mod hybrid;
+mod iterm;
+mod kitty;
+mod palette;
 #[cfg(feature = "parallel")]
 mod parallel;
 mod pixel;
+mod pixel_art;
+mod quantize;
 mod renderer;
+mod resize;
 mod serial;
+#[cfg(feature = "rasterize")]
+mod shape;
+#[cfg(feature = "rasterize")]
+mod svg;
+mod tone;
+mod upscale;
 
 /// Core rendering types: [`CharsetMode`], [`ColorMode`], [`Density`],
-/// [`RenderStyle`], and [`RenderError`].
+/// [`DitherMode`], [`RenderStyle`], [`ToneMap`], and [`RenderError`].
 pub mod types;
 
 /// Terminal size detection and dimension calculation for fitting images
 /// to the current terminal viewport.
 pub mod utils;
 
+pub use adjust::Adjustments;
+pub use cell::{detect_cell_aspect, detect_cell_size};
+pub use encode::{AnsiEncoder, Encoder};
+pub use grid::{Attributes, Cell, CellGrid};
+pub use html::{HtmlEncoder, write_html};
+pub use iterm::write_iterm2;
+pub use kitty::write_kitty;
 pub use options::*;
-pub use renderer::write_ansi_art;
+pub use palette::{Ansi16Palette, ColorPalette};
 #[cfg(feature = "sixel")]
 pub use renderer::write_sixel;
+pub use renderer::{ansi_encoder, render_grid, write_ansi_art};
+#[cfg(feature = "rasterize")]
+pub use svg::{SvgEncoder, write_svg};
 pub use types::*;
 pub use utils::*;
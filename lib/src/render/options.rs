@@ -1,8 +1,19 @@
-use super::types::{CharsetMode, Density, RenderStyle};
+use super::adjust::Adjustments;
+use super::crop::{clamp_region, trim_bounds};
+use super::dither::{Palette, diffuse_palette, dither};
+use super::pixel_art::{majority, native_grid, pixel_art_size};
+use super::quantize::quantize;
+use super::renderer::glyph_ramp;
+use super::resize::resize_linear;
+use super::tone::levels;
+use super::types::{CharsetMode, Density, DitherMode, RenderStyle, SymbolSet, ToneMap};
+use super::upscale::{upscale, upscale_passes};
 use crate::RenderError;
-use crate::cli_enums::{RenderStylePreset, ResizeFilter};
+use crate::cli_enums::{FitMode, RenderStylePreset, ResizeFilter, ResizeSpace};
+use crate::color::XTERM_256;
 use crate::{ColorMode, get_terminal_size};
-use image::{DynamicImage, imageops::FilterType};
+use image::{DynamicImage, Rgba32FImage, RgbaImage, imageops::FilterType};
+use std::borrow::Cow;
 use std::io::Write;
 
 /// The master configuration for the rendering pipeline.
@@ -12,10 +23,23 @@ use std::io::Write;
 #[derive(Clone, Copy, Debug)]
 pub struct RenderOptions {
     width: Option<u32>,
-    filter: FilterType,
+    height: Option<u32>,
+    max_width: Option<u32>,
+    max_height: Option<u32>,
+    fit: FitMode,
+    cell_aspect: f32,
+    crop: Option<(u32, u32, u32, u32)>,
+    auto_trim: bool,
+    filter: ResizeFilter,
+    resize_space: ResizeSpace,
     charset: CharsetMode,
     style: RenderStyle,
+    symbols: SymbolSet,
+    tone_map: ToneMap,
     color_mode: ColorMode,
+    palette_dither: bool,
+    text_max_colors: Option<u16>,
+    adjustments: Adjustments,
     bg_color: Option<[u8; 3]>,
     sixel_max_colors: u16,
     sixel_diffusion: f32,
@@ -25,10 +49,23 @@ impl Default for RenderOptions {
     fn default() -> Self {
         Self {
             width: None,
-            filter: FilterType::Nearest,
+            height: None,
+            max_width: None,
+            max_height: None,
+            fit: FitMode::Contain,
+            cell_aspect: super::utils::DEFAULT_CELL_ASPECT,
+            crop: None,
+            auto_trim: false,
+            filter: ResizeFilter::Nearest,
+            resize_space: ResizeSpace::Srgb,
             charset: CharsetMode::Ansi,
             style: RenderStyle::default(),
+            symbols: SymbolSet::default(),
+            tone_map: ToneMap::Linear,
             color_mode: ColorMode::detect(),
+            palette_dither: false,
+            text_max_colors: None,
+            adjustments: Adjustments::default(),
             bg_color: None,
             sixel_max_colors: 64,
             sixel_diffusion: 0.0,
@@ -43,8 +80,14 @@ impl From<RenderStylePreset> for RenderOptions {
             RenderStylePreset::Ansi => opts.charset = CharsetMode::Ansi,
             RenderStylePreset::Unicode => opts.charset = CharsetMode::Unicode,
             RenderStylePreset::Braille => opts.charset = CharsetMode::Braille,
+            RenderStylePreset::Quadrant => opts.charset = CharsetMode::Quadrant,
+            RenderStylePreset::Sextant => opts.charset = CharsetMode::Sextant,
+            RenderStylePreset::Octant => opts.charset = CharsetMode::Octant,
+            RenderStylePreset::Hybrid => opts.charset = CharsetMode::Hybrid,
             RenderStylePreset::Fade => opts.charset = CharsetMode::Fade,
             RenderStylePreset::Ascii => opts.charset = CharsetMode::Ascii,
+            RenderStylePreset::Shape => opts.charset = CharsetMode::Shape,
+            RenderStylePreset::Edges => opts.charset = CharsetMode::Edges,
             RenderStylePreset::Kanji => opts.charset = CharsetMode::Kanji,
             RenderStylePreset::Chinese => opts.charset = CharsetMode::Chinese,
             RenderStylePreset::FullBlock => {
@@ -56,6 +99,8 @@ impl From<RenderStylePreset> for RenderOptions {
                 opts.style.density = Density::Heavy;
             }
             RenderStylePreset::Sixel => opts.charset = CharsetMode::Sixel,
+            RenderStylePreset::Kitty => opts.charset = CharsetMode::Kitty,
+            RenderStylePreset::Iterm2 => opts.charset = CharsetMode::Iterm2,
         }
         opts
     }
@@ -67,13 +112,27 @@ impl From<RenderStylePreset> for RenderOptions {
 #[derive(Default, Clone)]
 pub struct RenderOptionsBuilder {
     preset: Option<RenderStylePreset>,
+    charset: Option<CharsetMode>,
     density: Option<Density>,
+    symbols: Option<SymbolSet>,
+    tone_map: Option<ToneMap>,
     width: Option<u32>,
+    height: Option<u32>,
+    max_width: Option<u32>,
+    max_height: Option<u32>,
+    fit: Option<FitMode>,
+    cell_aspect: Option<f32>,
+    crop: Option<(u32, u32, u32, u32)>,
+    auto_trim: Option<bool>,
     filter: Option<ResizeFilter>,
+    resize_space: Option<ResizeSpace>,
     color_mode: Option<ColorMode>,
-    dither: Option<bool>,
+    palette_dither: Option<bool>,
+    dither: Option<DitherMode>,
     bg_color: Option<[u8; 3]>,
     sixel_max_colors: Option<u16>,
+    text_max_colors: Option<u16>,
+    adjustments: Adjustments,
     sixel_diffusion: Option<f32>,
 }
 
@@ -91,6 +150,13 @@ impl RenderOptionsBuilder {
         self
     }
 
+    /// Overrides the preset's charset, e.g. with a [`CharsetMode::Custom`] ramp.
+    #[must_use]
+    pub const fn charset(mut self, charset: CharsetMode) -> Self {
+        self.charset = Some(charset);
+        self
+    }
+
     /// Sets the character density for the rendering output.
     #[must_use]
     pub const fn density(mut self, density: Density) -> Self {
@@ -98,14 +164,39 @@ impl RenderOptionsBuilder {
         self
     }
 
-    /// Whether to enable dithering or not
+    /// Sets the glyph families [`CharsetMode::Hybrid`] may choose from.
+    #[must_use]
+    pub const fn symbols(mut self, symbols: SymbolSet) -> Self {
+        self.symbols = Some(symbols);
+        self
+    }
+
+    /// Sets how luma is spread over glyph ramps in the ASCII, Fade, Kanji,
+    /// Chinese and custom charsets.
     #[must_use]
-    pub const fn dither(mut self, enabled: bool) -> Self {
-        self.dither = Some(enabled);
+    pub const fn tone_map(mut self, tone_map: ToneMap) -> Self {
+        self.tone_map = Some(tone_map);
         self
     }
 
-    /// Sets the target width for the rendered output.
+    /// Sets the [`DitherMode`] applied after resizing.
+    #[must_use]
+    pub const fn dither(mut self, mode: DitherMode) -> Self {
+        self.dither = Some(mode);
+        self
+    }
+
+    /// Diffuses colour error in Oklab across neighbouring cells when mapping to
+    /// the palette of [`ColorMode::Ansi256`], [`ColorMode::Ansi16`],
+    /// [`ColorMode::Grayscale`] or [`ColorMode::Palette`], instead of snapping
+    /// each cell on its own.
+    #[must_use]
+    pub const fn palette_dither(mut self, enabled: bool) -> Self {
+        self.palette_dither = Some(enabled);
+        self
+    }
+
+    /// Sets the target width for the rendered output, in terminal cells.
     /// If `None`, the output may scale to the terminal width.
     #[must_use]
     pub const fn width(mut self, width: u32) -> Self {
@@ -113,6 +204,63 @@ impl RenderOptionsBuilder {
         self
     }
 
+    /// Sets the target height for the rendered output, in terminal cells.
+    /// With a width as well, the two form the box the [`FitMode`] fits the
+    /// image into.
+    #[must_use]
+    pub const fn height(mut self, height: u32) -> Self {
+        self.height = Some(height);
+        self
+    }
+
+    /// Caps the rendered width in terminal cells, whatever the fit mode.
+    #[must_use]
+    pub const fn max_width(mut self, max_width: u32) -> Self {
+        self.max_width = Some(max_width);
+        self
+    }
+
+    /// Caps the rendered height in terminal cells, whatever the fit mode.
+    #[must_use]
+    pub const fn max_height(mut self, max_height: u32) -> Self {
+        self.max_height = Some(max_height);
+        self
+    }
+
+    /// Sets how the image is fitted to the target size. See [`FitMode`].
+    #[must_use]
+    pub const fn fit(mut self, fit: FitMode) -> Self {
+        self.fit = Some(fit);
+        self
+    }
+
+    /// Sets the height-to-width ratio of a terminal cell, `2.0` by default.
+    /// Use [`detect_cell_aspect`](crate::detect_cell_aspect) to match the
+    /// terminal's font. Ratios that are not positive and finite are ignored.
+    #[must_use]
+    pub const fn cell_aspect(mut self, ratio: f32) -> Self {
+        self.cell_aspect = Some(ratio);
+        self
+    }
+
+    /// Renders only the `width` x `height` region at (`x`, `y`) of the source
+    /// image, in source pixels. The region is clamped to the image, and
+    /// ignored if it lies outside it.
+    #[must_use]
+    pub const fn crop(mut self, x: u32, y: u32, width: u32, height: u32) -> Self {
+        self.crop = Some((x, y, width, height));
+        self
+    }
+
+    /// Trims fully transparent or uniform-colour borders off the source image
+    /// (after any [`crop`](Self::crop)) before it is sized, so padding doesn't
+    /// take up columns.
+    #[must_use]
+    pub const fn auto_trim(mut self, enabled: bool) -> Self {
+        self.auto_trim = Some(enabled);
+        self
+    }
+
     /// Sets the resampling filter used when resizing the input image.
     #[must_use]
     pub const fn filter(mut self, filter: ResizeFilter) -> Self {
@@ -120,6 +268,13 @@ impl RenderOptionsBuilder {
         self
     }
 
+    /// Sets the colour space images are resampled in. See [`ResizeSpace`].
+    #[must_use]
+    pub const fn resize_space(mut self, space: ResizeSpace) -> Self {
+        self.resize_space = Some(space);
+        self
+    }
+
     /// Sets the specific color mode (e.g., `TrueColor`, 256-color) for the output.
     #[must_use]
     pub const fn color_mode(mut self, color_mode: ColorMode) -> Self {
@@ -140,6 +295,55 @@ impl RenderOptionsBuilder {
         self.sixel_max_colors = Some(n);
         self
     }
+
+    /// Limits text modes to at most `n` distinct colours.
+    ///
+    /// The prepared image is reduced with median cut and k-means in Oklab,
+    /// so fewer colour escapes are needed. Sixel, kitty and iTerm2 output is
+    /// left alone; see [`max_colors`](Self::max_colors) for the Sixel
+    /// palette size.
+    #[must_use]
+    pub const fn text_max_colors(mut self, n: u16) -> Self {
+        self.text_max_colors = Some(n);
+        self
+    }
+    /// Multiplies every channel by `factor` after resizing; `1.0` is
+    /// unchanged. See [`Adjustments`] for the order adjustments run in.
+    #[must_use]
+    pub const fn brightness(mut self, factor: f32) -> Self {
+        self.adjustments = self.adjustments.brightness(factor);
+        self
+    }
+    /// Scales contrast around mid-grey by `factor`; `1.0` is unchanged.
+    #[must_use]
+    pub const fn contrast(mut self, factor: f32) -> Self {
+        self.adjustments = self.adjustments.contrast(factor);
+        self
+    }
+    /// Applies a gamma curve; values above `1.0` lift the midtones.
+    #[must_use]
+    pub const fn gamma(mut self, gamma: f32) -> Self {
+        self.adjustments = self.adjustments.gamma(gamma);
+        self
+    }
+    /// Scales saturation by `factor`; `0.0` is grayscale.
+    #[must_use]
+    pub const fn saturation(mut self, factor: f32) -> Self {
+        self.adjustments = self.adjustments.saturation(factor);
+        self
+    }
+    /// Rotates hue by `degrees`.
+    #[must_use]
+    pub const fn hue_rotate(mut self, degrees: f32) -> Self {
+        self.adjustments = self.adjustments.hue_rotate(degrees);
+        self
+    }
+    /// Inverts the colours of the image.
+    #[must_use]
+    pub const fn invert(mut self, invert: bool) -> Self {
+        self.adjustments = self.adjustments.invert(invert);
+        self
+    }
     /// Sixel diffusion
     #[must_use]
     pub const fn diffusion(mut self, d: f32) -> Self {
@@ -158,18 +362,54 @@ impl RenderOptionsBuilder {
         let mut opts = self.preset.map(RenderOptions::from).unwrap_or_default();
 
         // 2. Apply explicit builder overrides
+        if let Some(c) = self.charset {
+            opts.charset = c;
+        }
         if let Some(d) = self.density {
             opts.style.density = d;
         }
+        if let Some(s) = self.symbols {
+            opts.symbols = s;
+        }
+        if let Some(t) = self.tone_map {
+            opts.tone_map = t;
+        }
         if let Some(w) = self.width {
             opts.width = Some(w);
         }
+        if let Some(h) = self.height {
+            opts.height = Some(h);
+        }
+        if let Some(w) = self.max_width {
+            opts.max_width = Some(w);
+        }
+        if let Some(h) = self.max_height {
+            opts.max_height = Some(h);
+        }
+        if let Some(fit) = self.fit {
+            opts.fit = fit;
+        }
+        if let Some(ratio) = self.cell_aspect {
+            opts = opts.with_cell_aspect(ratio);
+        }
+        if let Some(region) = self.crop {
+            opts.crop = Some(region);
+        }
+        if let Some(enabled) = self.auto_trim {
+            opts.auto_trim = enabled;
+        }
         if let Some(f) = self.filter {
-            opts.filter = f.into();
+            opts.filter = f;
+        }
+        if let Some(space) = self.resize_space {
+            opts.resize_space = space;
         }
         if let Some(cm) = self.color_mode {
             opts.color_mode = cm;
         }
+        if let Some(enabled) = self.palette_dither {
+            opts.palette_dither = enabled;
+        }
         if let Some(dither_val) = self.dither {
             opts.style.dither = dither_val;
         }
@@ -180,9 +420,11 @@ impl RenderOptionsBuilder {
         if let Some(n) = self.sixel_max_colors {
             opts.sixel_max_colors = n;
         }
+        opts.text_max_colors = self.text_max_colors;
         if let Some(d) = self.sixel_diffusion {
             opts.sixel_diffusion = d;
         }
+        opts.adjustments = self.adjustments;
         opts
     }
 }
@@ -200,18 +442,73 @@ impl RenderOptions {
         Self::from(preset)
     }
 
-    /// Returns the target width for rendering, if set.
+    /// Returns the target width in terminal cells, if set.
     #[must_use]
     pub const fn width(&self) -> Option<u32> {
         self.width
     }
 
+    /// Returns the target height in terminal cells, if set.
+    #[must_use]
+    pub const fn height(&self) -> Option<u32> {
+        self.height
+    }
+
+    /// Returns the maximum width in terminal cells, if set.
+    #[must_use]
+    pub const fn max_width(&self) -> Option<u32> {
+        self.max_width
+    }
+
+    /// Returns the maximum height in terminal cells, if set.
+    #[must_use]
+    pub const fn max_height(&self) -> Option<u32> {
+        self.max_height
+    }
+
+    /// Returns how the image is fitted to the target size.
+    #[must_use]
+    pub const fn fit(&self) -> FitMode {
+        self.fit
+    }
+
+    /// Returns the height-to-width ratio assumed for a terminal cell.
+    #[must_use]
+    pub const fn cell_aspect(&self) -> f32 {
+        self.cell_aspect
+    }
+
+    /// Returns the `(x, y, width, height)` source region to render, if set.
+    #[must_use]
+    pub const fn crop(&self) -> Option<(u32, u32, u32, u32)> {
+        self.crop
+    }
+
+    /// Returns whether borders are trimmed off the source image.
+    #[must_use]
+    pub const fn auto_trim(&self) -> bool {
+        self.auto_trim
+    }
+
     /// Returns the current image resizing filter.
     #[must_use]
-    pub const fn filter(&self) -> FilterType {
+    pub fn filter(&self) -> FilterType {
+        self.filter.into()
+    }
+
+    /// Returns the resizing filter as chosen, including the filters that
+    /// [`FilterType`] has no equivalent for.
+    #[must_use]
+    pub const fn resize_filter(&self) -> ResizeFilter {
         self.filter
     }
 
+    /// Returns the colour space images are resampled in.
+    #[must_use]
+    pub const fn resize_space(&self) -> ResizeSpace {
+        self.resize_space
+    }
+
     /// Returns the character set mode used for the output.
     #[must_use]
     pub const fn charset(&self) -> CharsetMode {
@@ -224,17 +521,77 @@ impl RenderOptions {
         self.style
     }
 
+    /// Returns the glyph families used by [`CharsetMode::Hybrid`].
+    #[must_use]
+    pub const fn symbols(&self) -> SymbolSet {
+        self.symbols
+    }
+
+    /// Returns how luma is spread over glyph ramps.
+    #[must_use]
+    pub const fn tone_map(&self) -> ToneMap {
+        self.tone_map
+    }
+
     /// Returns the current color mode configuration.
     #[must_use]
     pub const fn color_mode(&self) -> ColorMode {
         self.color_mode
     }
 
+    /// Returns `true` if palette color modes diffuse palette error across
+    /// cells.
+    #[must_use]
+    pub const fn palette_dither(&self) -> bool {
+        self.palette_dither
+    }
+
     /// Returns the bg color of your terminal
     #[must_use]
     pub const fn bg_color(&self) -> Option<[u8; 3]> {
         self.bg_color
     }
+    /// Returns a copy of these options with the charset overridden.
+    #[must_use]
+    pub const fn with_charset(self, charset: CharsetMode) -> Self {
+        Self { charset, ..self }
+    }
+
+    /// Returns a copy of these options with the resize colour space overridden.
+    #[must_use]
+    pub const fn with_resize_space(self, resize_space: ResizeSpace) -> Self {
+        Self {
+            resize_space,
+            ..self
+        }
+    }
+
+    /// Returns a copy of these options with the tone map overridden.
+    #[must_use]
+    pub const fn with_tone_map(self, tone_map: ToneMap) -> Self {
+        Self { tone_map, ..self }
+    }
+
+    /// Returns a copy of these options with palette dithering toggled.
+    #[must_use]
+    pub const fn with_palette_dither(self, palette_dither: bool) -> Self {
+        Self {
+            palette_dither,
+            ..self
+        }
+    }
+
+    /// Returns a copy of these options with the text-mode colour cap
+    /// overridden; see [`RenderOptionsBuilder::text_max_colors`]. `None`
+    /// lifts the cap.
+    #[must_use]
+    pub const fn with_text_max_colors(self, text_max_colors: Option<u16>) -> Self {
+        Self {
+            text_max_colors,
+            ..self
+        }
+    }
+
     /// Returns a copy of these options with the target width overridden.
     #[must_use]
     pub const fn with_width(self, width: u32) -> Self {
@@ -243,6 +600,88 @@ impl RenderOptions {
             ..self
         }
     }
+
+    /// Returns a copy of these options with the target height overridden.
+    #[must_use]
+    pub const fn with_height(self, height: u32) -> Self {
+        Self {
+            height: Some(height),
+            ..self
+        }
+    }
+
+    /// Returns a copy of these options with the fit mode overridden.
+    #[must_use]
+    pub const fn with_fit(self, fit: FitMode) -> Self {
+        Self { fit, ..self }
+    }
+
+    /// Returns a copy of these options with the source region overridden; see
+    /// [`RenderOptionsBuilder::crop`].
+    #[must_use]
+    pub const fn with_crop(self, crop: Option<(u32, u32, u32, u32)>) -> Self {
+        Self { crop, ..self }
+    }
+
+    /// Returns a copy of these options with border trimming toggled.
+    #[must_use]
+    pub const fn with_auto_trim(self, auto_trim: bool) -> Self {
+        Self { auto_trim, ..self }
+    }
+
+    /// Returns the cropped region of `img` that gets rendered: the
+    /// [`crop`](Self::crop) region, then with its borders trimmed if
+    /// [`auto_trim`](Self::auto_trim) is on. Borrows `img` when neither
+    /// changes it.
+    #[must_use]
+    pub fn crop_image<'a>(&self, img: &'a DynamicImage) -> Cow<'a, DynamicImage> {
+        let mut img = Cow::Borrowed(img);
+        if let Some((x, y, w, h)) = self
+            .crop
+            .and_then(|region| clamp_region(region, img.width(), img.height()))
+        {
+            img = Cow::Owned(img.crop_imm(x, y, w, h));
+        }
+        if self.auto_trim
+            && let Some((x, y, w, h)) = trim_bounds(&img)
+        {
+            img = Cow::Owned(img.crop_imm(x, y, w, h));
+        }
+        img
+    }
+
+    /// Returns a copy of these options with the cell aspect ratio overridden;
+    /// see [`RenderOptionsBuilder::cell_aspect`].
+    #[must_use]
+    pub fn with_cell_aspect(self, ratio: f32) -> Self {
+        if ratio.is_finite() && ratio > 0.0 {
+            Self {
+                cell_aspect: ratio,
+                ..self
+            }
+        } else {
+            self
+        }
+    }
+    /// Returns the brightness, contrast and colour adjustments.
+    #[must_use]
+    pub const fn adjustments(&self) -> Adjustments {
+        self.adjustments
+    }
+
+    /// Returns a copy of these options with the adjustments overridden.
+    #[must_use]
+    pub const fn with_adjustments(self, adjustments: Adjustments) -> Self {
+        Self {
+            adjustments,
+            ..self
+        }
+    }
+    /// Returns the cap on distinct colours in text modes, if any.
+    #[must_use]
+    pub const fn text_max_colors(&self) -> Option<u16> {
+        self.text_max_colors
+    }
     /// Sixel max colors
     #[must_use]
     pub const fn sixel_max_colors(&self) -> u16 {
@@ -268,144 +707,163 @@ impl RenderOptions {
     /// Prepares a [`DynamicImage`] for terminal rendering through resizing and optional dithering.
     ///
     /// This method handles the core image transformation pipeline:
-    /// 1. Resizes the image to fit calculated terminal dimensions using the configured filter.
-    /// 2. If enabled, applies a Floyd-Steinberg dither to the luminance channel.
-    /// 3. In color modes, performs a luminance-preserving remap to distribute dithered
-    ///    high-frequency detail while maintaining original hue and saturation.
-    ///
-    /// If the `parallel` feature is enabled, the color remapping stage is executed
-    /// across multiple threads using `rayon`.
-    ///
-    /// # Panics
-    ///
-    /// This function will panic if:
-    /// * The calculated image dimensions exceed the capacity of a `u32` (though
-    ///   `calculate_dimensions` usually prevents this).
-    /// * An internal state inconsistency occurs where a pixel index cannot be mapped
-    ///   back to valid coordinate space during parallel processing.
+    /// 1. Crops the image to [`crop_image`](Self::crop_image), and further if the
+    ///    [`FitMode`] is [`FitMode::Cover`], then resizes it
+    ///    to fit calculated terminal dimensions using the configured filter,
+    ///    in linear light if the [`ResizeSpace`] asks for it, then applies any
+    ///    [`Adjustments`].
+    /// 2. If a [`DitherMode`] is set, dithers the floating-point resize result
+    ///    against the palette the output can show: the glyph ramp for
+    ///    luminance-mapped charsets, otherwise the 8-bit truecolor channels,
+    ///    the xterm-256 palette, the 16-color palette, the xterm grayscale
+    ///    ramp, a fixed [`ColorMode::Palette`], or black and white for
+    ///    [`ColorMode::None`].
+    /// 3. If palette dithering is on in a palette color mode, snaps every cell
+    ///    to that palette while diffusing the error in Oklab.
+    /// 4. If [`text_max_colors`](Self::text_max_colors) is set in a text
+    ///    mode, reduces the image to that many colours with median cut and
+    ///    k-means in Oklab.
     ///
     /// # Performance
     ///
-    /// Dither generation is inherently sequential due to the error-diffusion nature
-    /// of the Floyd-Steinberg algorithm. However, the color-scaling pass is
-    /// "embarrassingly parallel" and will scale linearly with available CPU cores
-    /// when the `parallel` feature is active.
+    /// Error-diffusion modes are inherently sequential. Dithering to
+    /// a palette color mode searches the palette once per pixel.
     #[must_use]
     pub fn prepare_image(&self, img: &DynamicImage) -> DynamicImage {
-        const LUMA_R: f32 = 0.2126;
-        const LUMA_G: f32 = 0.7152;
-        const LUMA_B: f32 = 0.0722;
-
-        let (width, height) = self.calculate_dimensions(img.width(), img.height());
-        let mut resized = img.resize_exact(width, height, self.filter);
-
-        if self.style.dither {
-            let mut luma_img = resized.to_luma8();
-            image::imageops::dither(&mut luma_img, &image::imageops::BiLevel);
-
-            if self.color_mode == ColorMode::None {
-                resized = DynamicImage::ImageLuma8(luma_img);
-            } else {
-                // Bind as immutable initially for the parallel path
-                let rgba = resized.to_rgba8();
-
-                #[cfg(feature = "parallel")]
-                {
-                    use rayon::prelude::*;
-                    let raw = rgba.as_raw();
-                    let w_usize = width as usize;
-
-                    let pixels: Vec<[u8; 4]> = raw
-                        .par_chunks_exact(4)
-                        .enumerate()
-                        .map(|(i, px)| {
-                            // let x = (i % w_usize) as u32;
-                            let x = u32::try_from(i % w_usize);
-                            let y = u32::try_from(i / w_usize);
-                            // let y = (i / w_usize) as u32;
-                            let [red, green, blue, alpha] = [px[0], px[1], px[2], px[3]];
-
-                            let orig_luma = LUMA_R.mul_add(
-                                f32::from(red),
-                                LUMA_G.mul_add(f32::from(green), LUMA_B * f32::from(blue)),
-                            );
-
-                            let dither_v =
-                                f32::from(luma_img.get_pixel(x.unwrap_or(0), y.unwrap_or(0)).0[0]);
-
-                            #[expect(
-                                clippy::cast_possible_truncation,
-                                clippy::cast_sign_loss,
-                                reason = "value is clamped to the 0..255 range prior to conversion"
-                            )]
-                            let (nr, ng, nb) = if orig_luma > 0.0 {
-                                let factor = dither_v / orig_luma;
-                                (
-                                    (f32::from(red) * factor).clamp(0.0, 255.0) as u8,
-                                    (f32::from(green) * factor).clamp(0.0, 255.0) as u8,
-                                    (f32::from(blue) * factor).clamp(0.0, 255.0) as u8,
-                                )
-                            } else if dither_v > 0.0 {
-                                (255, 255, 255)
-                            } else {
-                                (0, 0, 0)
-                            };
-
-                            [nr, ng, nb, alpha]
-                        })
-                        .collect();
-
-                    let mut new_raw = Vec::with_capacity(pixels.len() * 4);
-                    for p in pixels {
-                        new_raw.extend_from_slice(&p);
-                    }
-
-                    if let Some(buf) = image::ImageBuffer::from_raw(width, height, new_raw) {
-                        resized = DynamicImage::ImageRgba8(buf);
-                    } else {
-                        resized = DynamicImage::ImageRgba8(rgba);
-                    }
+        let region = self.crop_image(img);
+        let layout = self.layout(region.width(), region.height());
+        let cropped = layout.crop.map(|(x, y, w, h)| region.crop_imm(x, y, w, h));
+        let img = cropped.as_ref().unwrap_or(&region);
+        // Pixel art is scaled from its native grid, so upscaling passes see
+        // one pixel per block.
+        let native = (self.filter == ResizeFilter::PixelArt)
+            .then(|| native_grid(img))
+            .flatten();
+        let img = native.as_ref().unwrap_or(img);
+        let (width, height) = match self.filter {
+            ResizeFilter::PixelArt => pixel_art_size(img, layout.size),
+            _ => layout.size,
+        };
+        let passes = upscale_passes(
+            self.filter,
+            matches!(self.charset, CharsetMode::Braille | CharsetMode::Quadrant),
+            (img.width(), img.height()),
+            (width, height),
+        );
+        let upscaled = (!passes.is_empty())
+            .then(|| DynamicImage::ImageRgba8(upscale(&img.to_rgba8(), &passes)));
+        let img = upscaled.as_ref().unwrap_or(img);
+        let filter = self.filter();
+        let adjust = Some(self.adjustments).filter(|a| !a.is_identity());
+        let prepared = if self.style.dither == DitherMode::None
+            && self.resize_space == ResizeSpace::Srgb
+        {
+            let resized = match self.filter {
+                ResizeFilter::Majority => {
+                    DynamicImage::ImageRgba8(majority(&img.to_rgba8(), width, height))
                 }
-
-                #[cfg(not(feature = "parallel"))]
-                {
-                    let mut rgba = rgba;
-                    let (w, h) = rgba.dimensions();
-                    for y in 0..h {
-                        for x in 0..w {
-                            let p = rgba.get_pixel_mut(x, y);
-                            let [r, g, b, a] = p.0;
-
-                            let orig_luma = LUMA_R.mul_add(
-                                f32::from(r),
-                                LUMA_G.mul_add(f32::from(g), LUMA_B * f32::from(b)),
-                            );
-
-                            let dither_v = f32::from(luma_img.get_pixel(x, y).0[0]);
-
-                            #[expect(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
-                            let (nr, ng, nb) = if orig_luma > 0.0 {
-                                let factor = dither_v / orig_luma;
-                                (
-                                    (f32::from(r) * factor).clamp(0.0, 255.0) as u8,
-                                    (f32::from(g) * factor).clamp(0.0, 255.0) as u8,
-                                    (f32::from(b) * factor).clamp(0.0, 255.0) as u8,
-                                )
-                            } else if dither_v > 0.0 {
-                                (255, 255, 255)
-                            } else {
-                                (0, 0, 0)
-                            };
-
-                            *p = image::Rgba([nr, ng, nb, a]);
-                        }
-                    }
-                    resized = DynamicImage::ImageRgba8(rgba);
+                _ => img.resize_exact(width, height, filter),
+            };
+            match adjust {
+                Some(adjust) => {
+                    let mut rgba = resized.into_rgba8();
+                    adjust.apply_u8(&mut rgba);
+                    DynamicImage::ImageRgba8(rgba)
+                }
+                None => resized,
+            }
+        } else {
+            let mut resized = match self.resize_space {
+                // Majority picks existing colours, so the space is moot.
+                _ if self.filter == ResizeFilter::Majority => {
+                    DynamicImage::ImageRgba8(majority(&img.to_rgba8(), width, height))
+                        .into_rgba32f()
                 }
+                ResizeSpace::Srgb => DynamicImage::ImageRgba32F(img.to_rgba32f())
+                    .resize_exact(width, height, filter)
+                    .into_rgba32f(),
+                ResizeSpace::Linear => resize_linear(img, width, height, filter, false),
+                ResizeSpace::LinearPremultiplied => resize_linear(img, width, height, filter, true),
+            };
+            if let Some(adjust) = adjust {
+                adjust.apply_f32(&mut resized);
             }
+            if self.style.dither == DitherMode::None {
+                DynamicImage::ImageRgba8(DynamicImage::ImageRgba32F(resized).into_rgba8())
+            } else {
+                DynamicImage::ImageRgba8(self.dither_image(&resized))
+            }
+        };
+        let palette = self.cell_palette().filter(|_| self.palette_dither);
+        let max_colors = self.text_max_colors.filter(|_| {
+            self.color_mode != ColorMode::None
+                && !matches!(
+                    self.charset,
+                    CharsetMode::Sixel | CharsetMode::Kitty | CharsetMode::Iterm2
+                )
+        });
+        if palette.is_none() && max_colors.is_none() {
+            return prepared;
+        }
+        let mut rgba = prepared.into_rgba8();
+        if let Some(palette) = palette {
+            diffuse_palette(&mut rgba, self.cell_step(), palette);
+        }
+        if let Some(n) = max_colors {
+            quantize(&mut rgba, usize::from(n));
+        }
+        DynamicImage::ImageRgba8(rgba)
+    }
+
+    /// The fixed palette each cell is matched against, if the color mode has
+    /// one.
+    fn cell_palette(&self) -> Option<&[[u8; 3]]> {
+        match &self.color_mode {
+            ColorMode::Ansi256 => Some(&XTERM_256),
+            ColorMode::Ansi16(palette) => Some(palette.colors()),
+            ColorMode::Grayscale => Some(&XTERM_256[232..]),
+            ColorMode::Palette { palette, .. } => Some(palette.colors()),
+            ColorMode::TrueColor | ColorMode::None => None,
         }
+    }
 
-        resized
+    /// Image columns per terminal cell in the one-pixel-per-cell modes.
+    const fn cell_step(&self) -> u32 {
+        match self.charset {
+            CharsetMode::Kanji | CharsetMode::Chinese => 2,
+            CharsetMode::Custom(custom) if custom.is_wide() => 2,
+            _ => 1,
+        }
+    }
+
+    /// Dithers a resized image down to what the charset and color mode can
+    /// show. Glyph ramps dither between the tone-mapped levels the renderer
+    /// picks glyphs by, so the renderer skips tone mapping dithered images.
+    fn dither_image(&self, resized: &Rgba32FImage) -> RgbaImage {
+        let palette = self.dither_palette();
+        let levels = match palette {
+            Palette::Ramp(_) if self.tone_map != ToneMap::Linear => levels(
+                &DynamicImage::ImageRgba32F(resized.clone()).into_rgba8(),
+                self.tone_map,
+            ),
+            _ => None,
+        };
+        dither(resized, self.style.dither, palette, levels.as_deref())
+    }
+
+    /// The colours the configured charset and color mode can put on screen.
+    fn dither_palette(&self) -> Palette {
+        if let Some(ramp) = glyph_ramp(self.charset, self.style.density) {
+            return Palette::Ramp(ramp.len());
+        }
+        match self.color_mode {
+            ColorMode::TrueColor => Palette::TrueColor,
+            ColorMode::Ansi256 => Palette::Xterm256,
+            ColorMode::Ansi16(palette) => Palette::Ansi16(palette),
+            ColorMode::Grayscale => Palette::Grayscale,
+            ColorMode::Palette { palette, .. } => Palette::Fixed(palette.colors()),
+            ColorMode::None => Palette::Mono,
+        }
     }
 
     /// Renders a pre-processed image to the provided writer.
@@ -417,7 +875,8 @@ impl RenderOptions {
     /// # Arguments
     ///
     /// * `prepared_img` - A [`DynamicImage`] that should already be resized to the
-    ///   desired terminal dimensions.
+    ///   desired terminal dimensions, e.g. by [`RenderOptions::prepare_image`].
+    ///   Sixel, Kitty and iTerm2 output is sent at exactly this size.
     /// * `writer` - Any type implementing [`std::io::Write`] (e.g., `stdout`, a file, or a `Vec<u8>`).
     ///
     /// # Examples
@@ -448,12 +907,6 @@ impl RenderOptions {
         prepared_img: &DynamicImage,
         writer: &mut W,
     ) -> Result<(), RenderError> {
-        #[cfg(feature = "sixel")]
-        if self.charset == CharsetMode::Sixel {
-            let sized = self.prepare_image(prepared_img);
-            crate::render::write_sixel(&sized, self, writer)?;
-            return Ok(());
-        }
         crate::render::write_ansi_art(prepared_img, writer, *self)?;
         Ok(())
     }
@@ -476,12 +929,7 @@ impl RenderOptions {
         let prepared = self.prepare_image(img);
 
         // Get rendered width in terminal columns
-        let rendered_cols = match self.charset {
-            CharsetMode::Braille => prepared.width() / 2,
-            CharsetMode::Unicode if self.style.full => prepared.width() * 2,
-            CharsetMode::Kanji | CharsetMode::Chinese => prepared.width() * 2, // Kanji/Chinese is double-width
-            _ => prepared.width(),
-        };
+        let rendered_cols = self.rendered_cols(prepared.width());
 
         let (term_w, _) = get_terminal_size();
 
@@ -0,0 +1,542 @@
+//! Palettes behind [`ColorMode::Ansi16`] and [`ColorMode::Palette`].
+//!
+//! SGR 30–37 and 90–97 only name colours; what "red" looks like is up to the
+//! terminal's theme. Matching against the palette the user actually sees
+//! keeps 16-colour output faithful on themed terminals.
+//!
+//! A [`ColorPalette`] goes the other way: it locks output to a fixed set of
+//! colours regardless of what the terminal could show, for retro or themed
+//! art.
+//!
+//! [`ColorMode::Ansi16`]: super::ColorMode::Ansi16
+//! [`ColorMode::Palette`]: super::ColorMode::Palette
+
+use std::collections::HashSet;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::{Mutex, OnceLock, PoisonError};
+
+use crate::RenderError;
+use crate::color::nearest_palette_index;
+
+/// The 16 colours a terminal shows for SGR 30–37 and 90–97, in index order:
+/// black, red, green, yellow, blue, magenta, cyan, white, then their bright
+/// variants.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Ansi16Palette {
+    colors: [[u8; 3]; 16],
+}
+
+impl Ansi16Palette {
+    /// The xterm system colours, as used for indices 0–15 of
+    /// [`ColorMode::Ansi256`](super::ColorMode::Ansi256).
+    pub const XTERM: Self = Self::from_hex([
+        0x00_0000, 0x80_0000, 0x00_8000, 0x80_8000, 0x00_0080, 0x80_0080, 0x00_8080, 0xC0_C0C0,
+        0x80_8080, 0xFF_0000, 0x00_FF00, 0xFF_FF00, 0x00_00FF, 0xFF_00FF, 0x00_FFFF, 0xFF_FFFF,
+    ]);
+
+    /// The Linux virtual console (VGA text mode) colours.
+    pub const VGA: Self = Self::from_hex([
+        0x00_0000, 0xAA_0000, 0x00_AA00, 0xAA_5500, 0x00_00AA, 0xAA_00AA, 0x00_AAAA, 0xAA_AAAA,
+        0x55_5555, 0xFF_5555, 0x55_FF55, 0xFF_FF55, 0x55_55FF, 0xFF_55FF, 0x55_FFFF, 0xFF_FFFF,
+    ]);
+
+    /// Tokyo Night.
+    pub const TOKYO_NIGHT: Self = Self::from_hex([
+        0x15_161E, 0xF7_768E, 0x9E_CE6A, 0xE0_AF68, 0x7A_A2F7, 0xBB_9AF7, 0x7D_CFFF, 0xA9_B1D6,
+        0x41_4868, 0xF7_768E, 0x9E_CE6A, 0xE0_AF68, 0x7A_A2F7, 0xBB_9AF7, 0x7D_CFFF, 0xC0_CAF5,
+    ]);
+
+    /// Dracula.
+    pub const DRACULA: Self = Self::from_hex([
+        0x21_222C, 0xFF_5555, 0x50_FA7B, 0xF1_FA8C, 0xBD_93F9, 0xFF_79C6, 0x8B_E9FD, 0xF8_F8F2,
+        0x62_72A4, 0xFF_6E6E, 0x69_FF94, 0xFF_FFA5, 0xD6_ACFF, 0xFF_92DF, 0xA4_FFFF, 0xFF_FFFF,
+    ]);
+
+    /// Nord.
+    pub const NORD: Self = Self::from_hex([
+        0x3B_4252, 0xBF_616A, 0xA3_BE8C, 0xEB_CB8B, 0x81_A1C1, 0xB4_8EAD, 0x88_C0D0, 0xE5_E9F0,
+        0x4C_566A, 0xBF_616A, 0xA3_BE8C, 0xEB_CB8B, 0x81_A1C1, 0xB4_8EAD, 0x8F_BCBB, 0xEC_EFF4,
+    ]);
+
+    /// Gruvbox Dark.
+    pub const GRUVBOX_DARK: Self = Self::from_hex([
+        0x28_2828, 0xCC_241D, 0x98_971A, 0xD7_9921, 0x45_8588, 0xB1_6286, 0x68_9D6A, 0xA8_9984,
+        0x92_8374, 0xFB_4934, 0xB8_BB26, 0xFA_BD2F, 0x83_A598, 0xD3_869B, 0x8E_C07C, 0xEB_DBB2,
+    ]);
+
+    /// One Dark.
+    pub const ONE_DARK: Self = Self::from_hex([
+        0x28_2C34, 0xE0_6C75, 0x98_C379, 0xE5_C07B, 0x61_AFEF, 0xC6_78DD, 0x56_B6C2, 0xAB_B2BF,
+        0x5C_6370, 0xE0_6C75, 0x98_C379, 0xE5_C07B, 0x61_AFEF, 0xC6_78DD, 0x56_B6C2, 0xFF_FFFF,
+    ]);
+
+    /// Solarized Dark.
+    pub const SOLARIZED_DARK: Self = Self::from_hex([
+        0x07_3642, 0xDC_322F, 0x85_9900, 0xB5_8900, 0x26_8BD2, 0xD3_3682, 0x2A_A198, 0xEE_E8D5,
+        0x00_2B36, 0xCB_4B16, 0x58_6E75, 0x65_7B83, 0x83_9496, 0x6C_71C4, 0x93_A1A1, 0xFD_F6E3,
+    ]);
+
+    /// Creates a palette from 16 RGB colours in SGR index order.
+    #[must_use]
+    pub const fn new(colors: [[u8; 3]; 16]) -> Self {
+        Self { colors }
+    }
+
+    /// Returns the colours in SGR index order.
+    #[must_use]
+    pub const fn colors(&self) -> &[[u8; 3]; 16] {
+        &self.colors
+    }
+
+    #[expect(
+        clippy::cast_possible_truncation,
+        reason = "Each channel is masked to 8 bits"
+    )]
+    const fn from_hex(hex: [u32; 16]) -> Self {
+        let mut colors = [[0; 3]; 16];
+        let mut i = 0;
+        while i < 16 {
+            colors[i] = [(hex[i] >> 16) as u8, (hex[i] >> 8) as u8, hex[i] as u8];
+            i += 1;
+        }
+        Self { colors }
+    }
+}
+
+impl Default for Ansi16Palette {
+    fn default() -> Self {
+        Self::XTERM
+    }
+}
+
+impl FromStr for Ansi16Palette {
+    type Err = String;
+
+    /// Parses a preset name, or 16 comma-separated `#rrggbb` colours in SGR
+    /// index order.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "xterm" => return Ok(Self::XTERM),
+            "vga" | "linux" => return Ok(Self::VGA),
+            "tokyo-night" => return Ok(Self::TOKYO_NIGHT),
+            "dracula" => return Ok(Self::DRACULA),
+            "nord" => return Ok(Self::NORD),
+            "gruvbox-dark" => return Ok(Self::GRUVBOX_DARK),
+            "one-dark" => return Ok(Self::ONE_DARK),
+            "solarized-dark" => return Ok(Self::SOLARIZED_DARK),
+            _ => {}
+        }
+        let invalid = || {
+            format!(
+                "invalid 16-colour palette: '{s}'. (valid: xterm, vga, tokyo-night, dracula, nord, gruvbox-dark, one-dark, solarized-dark, or 16 comma-separated #rrggbb colours)"
+            )
+        };
+        let colors: Vec<[u8; 3]> = s
+            .split(',')
+            .map(parse_hex)
+            .collect::<Option<_>>()
+            .ok_or_else(invalid)?;
+        let colors: [[u8; 3]; 16] = colors.try_into().map_err(|_| invalid())?;
+        Ok(Self::new(colors))
+    }
+}
+
+/// A fixed set of colours that [`ColorMode::Palette`] locks all output to.
+///
+/// Colours are matched in Oklab, so the nearest entry is the one that looks
+/// closest rather than the one with the closest RGB values.
+///
+/// ```rust
+/// use px2ansi::ColorPalette;
+///
+/// let gb = ColorPalette::GAME_BOY;
+/// assert_eq!(gb.colors().len(), 4);
+/// assert_eq!(gb.nearest(255, 255, 255), [0x9B, 0xBC, 0x0F]);
+/// ```
+///
+/// [`ColorMode::Palette`]: super::ColorMode::Palette
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ColorPalette {
+    colors: &'static [[u8; 3]],
+}
+
+impl ColorPalette {
+    /// The 16 RGBI colours of the IBM CGA.
+    pub const CGA: Self = Self::new(&[
+        [0x00, 0x00, 0x00],
+        [0x00, 0x00, 0xAA],
+        [0x00, 0xAA, 0x00],
+        [0x00, 0xAA, 0xAA],
+        [0xAA, 0x00, 0x00],
+        [0xAA, 0x00, 0xAA],
+        [0xAA, 0x55, 0x00],
+        [0xAA, 0xAA, 0xAA],
+        [0x55, 0x55, 0x55],
+        [0x55, 0x55, 0xFF],
+        [0x55, 0xFF, 0x55],
+        [0x55, 0xFF, 0xFF],
+        [0xFF, 0x55, 0x55],
+        [0xFF, 0x55, 0xFF],
+        [0xFF, 0xFF, 0x55],
+        [0xFF, 0xFF, 0xFF],
+    ]);
+
+    /// The 64 colours of the IBM EGA, two bits per channel.
+    pub const EGA: Self = Self::new(&EGA_COLORS);
+
+    /// The four greens of the original Game Boy screen, darkest first.
+    pub const GAME_BOY: Self = Self::new(&[
+        [0x0F, 0x38, 0x0F],
+        [0x30, 0x62, 0x30],
+        [0x8B, 0xAC, 0x0F],
+        [0x9B, 0xBC, 0x0F],
+    ]);
+
+    /// The 16 colours of the PICO-8 fantasy console.
+    pub const PICO8: Self = Self::new(&[
+        [0x00, 0x00, 0x00],
+        [0x1D, 0x2B, 0x53],
+        [0x7E, 0x25, 0x53],
+        [0x00, 0x87, 0x51],
+        [0xAB, 0x52, 0x36],
+        [0x5F, 0x57, 0x4F],
+        [0xC2, 0xC3, 0xC7],
+        [0xFF, 0xF1, 0xE8],
+        [0xFF, 0x00, 0x4D],
+        [0xFF, 0xA3, 0x00],
+        [0xFF, 0xEC, 0x27],
+        [0x00, 0xE4, 0x36],
+        [0x29, 0xAD, 0xFF],
+        [0x83, 0x76, 0x9C],
+        [0xFF, 0x77, 0xA8],
+        [0xFF, 0xCC, 0xAA],
+    ]);
+
+    /// The NES PPU palette, without its duplicate blacks.
+    pub const NES: Self = Self::new(&[
+        [0x7C, 0x7C, 0x7C],
+        [0x00, 0x00, 0xFC],
+        [0x00, 0x00, 0xBC],
+        [0x44, 0x28, 0xBC],
+        [0x94, 0x00, 0x84],
+        [0xA8, 0x00, 0x20],
+        [0xA8, 0x10, 0x00],
+        [0x88, 0x14, 0x00],
+        [0x50, 0x30, 0x00],
+        [0x00, 0x78, 0x00],
+        [0x00, 0x68, 0x00],
+        [0x00, 0x58, 0x00],
+        [0x00, 0x40, 0x58],
+        [0x00, 0x00, 0x00],
+        [0xBC, 0xBC, 0xBC],
+        [0x00, 0x78, 0xF8],
+        [0x00, 0x58, 0xF8],
+        [0x68, 0x44, 0xFC],
+        [0xD8, 0x00, 0xCC],
+        [0xE4, 0x00, 0x58],
+        [0xF8, 0x38, 0x00],
+        [0xE4, 0x5C, 0x10],
+        [0xAC, 0x7C, 0x00],
+        [0x00, 0xB8, 0x00],
+        [0x00, 0xA8, 0x00],
+        [0x00, 0xA8, 0x44],
+        [0x00, 0x88, 0x88],
+        [0xF8, 0xF8, 0xF8],
+        [0x3C, 0xBC, 0xFC],
+        [0x68, 0x88, 0xFC],
+        [0x98, 0x78, 0xF8],
+        [0xF8, 0x78, 0xF8],
+        [0xF8, 0x58, 0x98],
+        [0xF8, 0x78, 0x58],
+        [0xFC, 0xA0, 0x44],
+        [0xF8, 0xB8, 0x00],
+        [0xB8, 0xF8, 0x18],
+        [0x58, 0xD8, 0x54],
+        [0x58, 0xF8, 0x98],
+        [0x00, 0xE8, 0xD8],
+        [0x78, 0x78, 0x78],
+        [0xFC, 0xFC, 0xFC],
+        [0xA4, 0xE4, 0xFC],
+        [0xB8, 0xB8, 0xF8],
+        [0xD8, 0xB8, 0xF8],
+        [0xF8, 0xB8, 0xF8],
+        [0xF8, 0xA4, 0xC0],
+        [0xF0, 0xD0, 0xB0],
+        [0xFC, 0xE0, 0xA8],
+        [0xF8, 0xD8, 0x78],
+        [0xD8, 0xF8, 0x78],
+        [0xB8, 0xF8, 0xB8],
+        [0xB8, 0xF8, 0xD8],
+        [0x00, 0xFC, 0xFC],
+        [0xF8, 0xD8, 0xF8],
+    ]);
+
+    /// Creates a palette from static colours.
+    ///
+    /// An empty palette leaves colours unchanged.
+    #[must_use]
+    pub const fn new(colors: &'static [[u8; 3]]) -> Self {
+        Self { colors }
+    }
+
+    /// Builds a palette from owned colours.
+    ///
+    /// Palettes are interned for the life of the process to obtain a
+    /// `'static` lifetime, so each distinct list of colours is stored once
+    /// no matter how often it is built.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError::InvalidPalette`] if `colors` is empty.
+    pub fn from_colors(colors: Vec<[u8; 3]>) -> Result<Self, RenderError> {
+        if colors.is_empty() {
+            return Err(RenderError::InvalidPalette(
+                "palette needs at least one colour".to_string(),
+            ));
+        }
+        Ok(Self::new(intern(colors)))
+    }
+
+    /// Parses a GIMP palette (`.gpl`): a `GIMP Palette` header, optional
+    /// `Name:`/`Columns:` lines and `#` comments, then one `R G B [name]`
+    /// line per colour.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError::InvalidPalette`] if the header is missing, a
+    /// colour line is malformed, or no colours are listed.
+    pub fn from_gpl(text: &str) -> Result<Self, RenderError> {
+        let mut lines = text.lines().map(str::trim);
+        if lines.next() != Some("GIMP Palette") {
+            return Err(RenderError::InvalidPalette(
+                "missing 'GIMP Palette' header".to_string(),
+            ));
+        }
+        let colors = lines
+            .filter(|line| {
+                !(line.is_empty()
+                    || line.starts_with('#')
+                    || line.starts_with("Name:")
+                    || line.starts_with("Columns:"))
+            })
+            .map(|line| {
+                let mut channels = line.split_whitespace().map(str::parse::<u8>);
+                match (channels.next(), channels.next(), channels.next()) {
+                    (Some(Ok(r)), Some(Ok(g)), Some(Ok(b))) => Ok([r, g, b]),
+                    _ => Err(RenderError::InvalidPalette(format!(
+                        "invalid GIMP palette line: '{line}'"
+                    ))),
+                }
+            })
+            .collect::<Result<_, _>>()?;
+        Self::from_colors(colors)
+    }
+
+    /// Parses a list of hex colours separated by commas, whitespace or
+    /// newlines, as in Lospec `.hex` files. The `#` is optional, `;` starts a
+    /// comment line, and 8-digit `AARRGGBB` entries (paint.net) drop their
+    /// alpha.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError::InvalidPalette`] if an entry is not a hex
+    /// colour or no colours are listed.
+    pub fn from_hex_list(text: &str) -> Result<Self, RenderError> {
+        let colors = text
+            .lines()
+            .filter(|line| !line.trim_start().starts_with(';'))
+            .flat_map(|line| line.split(|c: char| c == ',' || c.is_whitespace()))
+            .filter(|token| !token.is_empty())
+            .map(|token| {
+                let rgb = token.trim_start_matches('#');
+                let rgb = if rgb.len() == 8 {
+                    rgb.get(2..).unwrap_or(rgb)
+                } else {
+                    rgb
+                };
+                parse_hex(rgb).ok_or_else(|| {
+                    RenderError::InvalidPalette(format!("invalid hex colour: '{token}'"))
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Self::from_colors(colors)
+    }
+
+    /// Loads a palette file, reading it as a GIMP palette if it starts with
+    /// the `GIMP Palette` header and as a hex list otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError::Io`] if the file cannot be read, or
+    /// [`RenderError::InvalidPalette`] if it cannot be parsed.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, RenderError> {
+        let text = std::fs::read_to_string(path)?;
+        if text.trim_start().starts_with("GIMP Palette") {
+            Self::from_gpl(text.trim_start())
+        } else {
+            Self::from_hex_list(&text)
+        }
+    }
+
+    /// Returns the colours in file order.
+    #[must_use]
+    pub const fn colors(&self) -> &'static [[u8; 3]] {
+        self.colors
+    }
+
+    /// Returns the palette colour closest to `(r, g, b)` in Oklab.
+    #[must_use]
+    pub fn nearest(&self, r: u8, g: u8, b: u8) -> [u8; 3] {
+        self.colors
+            .get(nearest_palette_index(r, g, b, self.colors))
+            .copied()
+            .unwrap_or([r, g, b])
+    }
+}
+
+impl FromStr for ColorPalette {
+    type Err = String;
+
+    /// Parses a preset name.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "cga" => Ok(Self::CGA),
+            "ega" => Ok(Self::EGA),
+            "game-boy" | "gameboy" | "gb" => Ok(Self::GAME_BOY),
+            "pico-8" | "pico8" => Ok(Self::PICO8),
+            "nes" => Ok(Self::NES),
+            _ => Err(format!(
+                "invalid palette: '{s}'. (valid: cga, ega, game-boy, pico-8, nes)"
+            )),
+        }
+    }
+}
+
+const EGA_COLORS: [[u8; 3]; 64] = ega_colors();
+
+/// EGA colour `i` has red, green and blue primary bits 2, 1, 0 (worth `0xAA`)
+/// and secondary bits 5, 4, 3 (worth `0x55`).
+#[expect(
+    clippy::cast_possible_truncation,
+    reason = "Channels peak at 0xAA + 0x55 = 0xFF"
+)]
+const fn ega_colors() -> [[u8; 3]; 64] {
+    let mut colors = [[0; 3]; 64];
+    let mut i = 0;
+    while i < 64 {
+        let mut c = 0;
+        while c < 3 {
+            let primary = (i >> (2 - c)) & 1;
+            let secondary = (i >> (5 - c)) & 1;
+            colors[i][c] = (primary * 0xAA + secondary * 0x55) as u8;
+            c += 1;
+        }
+        i += 1;
+    }
+    colors
+}
+
+/// Palettes built by [`ColorPalette::from_colors`].
+static PALETTES: OnceLock<Mutex<HashSet<&'static [[u8; 3]]>>> = OnceLock::new();
+
+/// Returns the interned copy of `colors`, storing it on first use.
+fn intern(colors: Vec<[u8; 3]>) -> &'static [[u8; 3]] {
+    let mut palettes = PALETTES
+        .get_or_init(Mutex::default)
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    if let Some(&interned) = palettes.get(colors.as_slice()) {
+        return interned;
+    }
+    let interned: &'static [[u8; 3]] = Box::leak(colors.into_boxed_slice());
+    palettes.insert(interned);
+    interned
+}
+
+/// Parses `rrggbb`, with or without a leading `#`.
+fn parse_hex(s: &str) -> Option<[u8; 3]> {
+    let s = s.trim().trim_start_matches('#');
+    if s.len() != 6 || !s.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&s[i..i + 2], 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_parse_by_name() {
+        assert_eq!("Dracula".parse(), Ok(Ansi16Palette::DRACULA));
+        assert_eq!("linux".parse(), Ok(Ansi16Palette::VGA));
+        assert_eq!(Ansi16Palette::default(), Ansi16Palette::XTERM);
+    }
+
+    #[test]
+    fn hex_list_parses_in_index_order() {
+        let list = (0..16)
+            .map(|i| format!("#{:02x}0000", i * 16))
+            .collect::<Vec<_>>()
+            .join(",");
+        let palette: Ansi16Palette = list.parse().expect("16 colours");
+        assert_eq!(palette.colors()[0], [0, 0, 0]);
+        assert_eq!(palette.colors()[15], [240, 0, 0]);
+    }
+
+    #[test]
+    fn ega_has_every_two_bit_colour() {
+        let colors = ColorPalette::EGA.colors();
+        assert_eq!(colors[0], [0, 0, 0]);
+        assert_eq!(colors[6], [0xAA, 0xAA, 0x00]);
+        assert_eq!(colors[20], [0xAA, 0x55, 0x00]);
+        assert_eq!(colors[63], [0xFF, 0xFF, 0xFF]);
+        let mut unique = colors.to_vec();
+        unique.sort_unstable();
+        unique.dedup();
+        assert_eq!(unique.len(), 64);
+    }
+
+    #[test]
+    fn gpl_and_hex_files_parse() {
+        let gpl = "GIMP Palette\nName: Test\nColumns: 2\n# comment\n  0   0   0\tBlack\n255 128 0 Orange\n";
+        let palette = ColorPalette::from_gpl(gpl).expect("valid gpl");
+        assert_eq!(palette.colors(), &[[0, 0, 0], [255, 128, 0]]);
+
+        let hex = "; paint.net palette\nFF102030\n#405060, a0b0c0\n\n";
+        let palette = ColorPalette::from_hex_list(hex).expect("valid hex list");
+        assert_eq!(
+            palette.colors(),
+            &[[0x10, 0x20, 0x30], [0x40, 0x50, 0x60], [0xA0, 0xB0, 0xC0]]
+        );
+
+        ColorPalette::from_gpl("Name: nope\n1 2 3").expect_err("no header");
+        ColorPalette::from_gpl("GIMP Palette\n1 2").expect_err("two channels");
+        ColorPalette::from_hex_list("#12345").expect_err("short hex");
+        ColorPalette::from_hex_list("; only a comment").expect_err("empty");
+        ColorPalette::from_hex_list("aéééb").expect_err("non-ASCII entry");
+        // Loading the same colours again reuses the stored palette.
+        let again = ColorPalette::from_hex_list(hex).expect("valid hex list");
+        assert!(std::ptr::eq(palette.colors(), again.colors()));
+    }
+
+    #[test]
+    fn nearest_matches_perceptually() {
+        assert_eq!(ColorPalette::PICO8.nearest(250, 0, 70), [0xFF, 0x00, 0x4D]);
+        assert_eq!(ColorPalette::GAME_BOY.nearest(0, 0, 0), [0x0F, 0x38, 0x0F]);
+        assert_eq!("Game-Boy".parse(), Ok(ColorPalette::GAME_BOY));
+        assert_eq!(ColorPalette::new(&[]).nearest(1, 2, 3), [1, 2, 3]);
+    }
+
+    #[test]
+    fn hex_list_needs_sixteen_colours() {
+        "#000000,#ffffff"
+            .parse::<Ansi16Palette>()
+            .expect_err("only two colours");
+        "not-a-theme"
+            .parse::<Ansi16Palette>()
+            .expect_err("unknown preset");
+    }
+}
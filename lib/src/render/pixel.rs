@@ -1,41 +1,45 @@
 use image::RgbaImage;
-use std::io;
-use std::io::Write;
 
 #[cfg(feature = "parallel")]
 use rayon::prelude::*;
 
-use super::color::{ColorState, write_colored_glyph};
-use super::types::ColorMode;
+use super::grid::Cell;
 
 pub(super) const ALPHA_THRESHOLD: u8 = 30;
 
 #[derive(Clone, Copy)]
-pub(super) struct LumaParams {
+pub(super) struct LumaParams<'a> {
     pub(super) min: u32,
     pub(super) range: u32,
     pub(super) num_chars_minus_1: u32,
+    /// Tone-mapped level of every pixel, row-major; replaces the linear
+    /// stretch between `min` and `min + range` when set.
+    pub(super) levels: Option<&'a [u8]>,
 }
 
-#[derive(Clone, Copy)]
-pub(super) struct ColorParams<'a> {
-    pub(super) enabled: bool,
-    pub(super) mode: ColorMode,
-    pub(super) blank: &'a str,
+impl LumaParams<'_> {
+    /// Charset index for the pixel at row-major `offset` with luma `luma`.
+    #[inline]
+    pub(super) fn index(&self, luma: u32, offset: usize) -> usize {
+        let norm = self.levels.map_or_else(
+            || ((luma - self.min) * 255) / self.range,
+            |levels| u32::from(levels[offset]),
+        );
+        (norm * self.num_chars_minus_1 / 255) as usize
+    }
 }
 
 pub(super) struct RenderCtx<'a> {
     pub(super) rgba: &'a RgbaImage,
-    pub(super) charset: &'a [&'a str],
+    pub(super) charset: &'a [&'static str],
     pub(super) width: u32,
     pub(super) height: u32,
-    #[allow(dead_code)]
     pub(super) x_step: usize,
     pub(super) wide: bool,
 }
 
 impl<'a> RenderCtx<'a> {
-    pub(super) fn new(rgba: &'a RgbaImage, charset: &'a [&'a str], wide: bool) -> Self {
+    pub(super) fn new(rgba: &'a RgbaImage, charset: &'a [&'static str], wide: bool) -> Self {
         let (width, height) = rgba.dimensions();
         Self {
             rgba,
@@ -46,6 +50,11 @@ impl<'a> RenderCtx<'a> {
             wide,
         }
     }
+
+    /// Terminal columns of one glyph.
+    pub(super) const fn cell_width(&self) -> u8 {
+        if self.wide { 2 } else { 1 }
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -129,55 +138,35 @@ pub(super) fn luma_range_pass1(
     }
 }
 
-/// Scalar luma → index → write for one pixel.
-#[inline]
-pub(super) fn write_pixel_scalar<W: Write>(
-    writer: &mut W,
-    charset: &[&str],
-    px: PixelRgba,
-    lp: LumaParams,
-    cp: ColorParams<'_>,
-    last: &mut ColorState,
-) -> io::Result<()> {
-    if px.a < ALPHA_THRESHOLD {
-        return write_blank(writer, cp, last);
-    }
-    let luma = crate::simd::luma_scalar(px.r, px.g, px.b);
-    let norm = ((luma - lp.min) * 255) / lp.range;
-    let idx = ((norm * lp.num_chars_minus_1 / 255) as usize).min(charset.len() - 1);
-    write_glyph(writer, charset[idx], px.r, px.g, px.b, cp, last)
-}
-
-/// Writes a transparent cell (blank + optional reset escape).
-#[inline]
-pub(super) fn write_blank<W: Write>(
-    writer: &mut W,
-    cp: ColorParams<'_>,
-    last: &mut ColorState,
-) -> io::Result<()> {
-    if cp.enabled {
-        write!(writer, "\x1b[0m{}", cp.blank)?;
-        *last = ColorState::default();
-    } else {
-        write!(writer, "{}", cp.blank)?;
-    }
-    Ok(())
+/// The cells of image row `y`. Wide mode samples every other pixel column.
+pub(super) fn ramp_row(ctx: &RenderCtx<'_>, lp: LumaParams<'_>, y: u32) -> Vec<Cell> {
+    let row_offset = (y * ctx.width) as usize;
+    let row_bytes = &ctx.rgba.as_raw()[row_offset * 4..(row_offset + ctx.width as usize) * 4];
+    row_bytes
+        .as_chunks::<4>()
+        .0
+        .iter()
+        .enumerate()
+        .step_by(ctx.x_step)
+        .map(|(x, &[r, g, b, a])| {
+            let at = (PixelRgba { r, g, b, a }, row_offset + x);
+            ramp_cell(ctx.charset, at, lp, ctx.cell_width())
+        })
+        .collect()
 }
 
-/// Writes a colored or plain glyph.
+/// Scalar luma → index → cell for one pixel at row-major `offset`.
 #[inline]
-pub(super) fn write_glyph<W: Write>(
-    writer: &mut W,
-    glyph: &str,
-    r: u8,
-    g: u8,
-    b: u8,
-    cp: ColorParams<'_>,
-    last: &mut ColorState,
-) -> io::Result<()> {
-    if cp.enabled {
-        write_colored_glyph(writer, glyph, r, g, b, cp.mode, last)
-    } else {
-        write!(writer, "{glyph}")
+fn ramp_cell(
+    charset: &[&'static str],
+    (px, offset): (PixelRgba, usize),
+    lp: LumaParams<'_>,
+    width: u8,
+) -> Cell {
+    if px.a < ALPHA_THRESHOLD {
+        return Cell::blank(width);
     }
+    let luma = crate::simd::luma_scalar(px.r, px.g, px.b);
+    let idx = lp.index(luma, offset).min(charset.len() - 1);
+    Cell::new(charset[idx], width).with_fg(Some([px.r, px.g, px.b]))
 }
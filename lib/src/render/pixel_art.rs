@@ -0,0 +1,388 @@
+//! Resampling filters for pixel art: [`ResizeFilter::PixelArt`] and
+//! [`ResizeFilter::Majority`].
+//!
+//! Pixel art is usually shipped upscaled by an integer factor with nearest
+//! neighbour, so every source pixel is a uniform block. The block size is
+//! the greatest common divisor of the run lengths of identical pixels along
+//! each axis; dividing it out gives the native grid, and scaling that grid
+//! by whole numbers only keeps every pixel the same size.
+//!
+//! Below the native size, the majority filter gives each output pixel the
+//! most common colour of its source block rather than a blend or a single
+//! sample, and lets thin dark lines crossing the block win over the fill so
+//! outlines survive.
+//!
+//! [`ResizeFilter::PixelArt`]: crate::ResizeFilter::PixelArt
+//! [`ResizeFilter::Majority`]: crate::ResizeFilter::Majority
+
+use std::collections::HashMap;
+
+use image::{DynamicImage, Rgba, RgbaImage, imageops::FilterType};
+
+use super::pixel::ALPHA_THRESHOLD;
+
+/// Aspect ratios within this many natural-log units of the closest
+/// reachable one count as equally good, so a slightly less exact pair of
+/// factors can win on size (0.05 is about 5%).
+const ASPECT_TOLERANCE: f64 = 0.05;
+
+/// The size to resample `img` to with nearest neighbour so that its native
+/// pixels are scaled by whole numbers, as close to `width` x `height` as
+/// possible without exceeding it.
+///
+/// Each axis is scaled up by a whole factor or down by a whole divisor, and
+/// the two are picked together so the result keeps the aspect ratio of
+/// `width` x `height` as closely as whole numbers allow: when one axis has
+/// to round down, the other gives up size too rather than stretching the
+/// pixels. Among pairs that come equally close, the largest wins.
+pub(super) fn pixel_art_size(img: &DynamicImage, (width, height): (u32, u32)) -> (u32, u32) {
+    let (block_w, block_h) = block_size(&img.to_rgba8());
+    let widths = whole_scales(img.width() / block_w, width);
+    let heights = whole_scales(img.height() / block_h, height);
+    let aspect = f64::from(width.max(1)) / f64::from(height.max(1));
+    let error = |w: u32, h: u32| (f64::from(w) / f64::from(h) / aspect).ln().abs();
+
+    let candidates: Vec<(u32, u32)> = widths
+        .iter()
+        .flat_map(|&w| {
+            // The heights either side of the one that would match exactly.
+            let ideal = f64::from(w) / aspect;
+            let above = heights.partition_point(|&h| f64::from(h) <= ideal);
+            let below = above.checked_sub(1);
+            [below, Some(above)]
+                .into_iter()
+                .flatten()
+                .filter_map(|i| heights.get(i))
+                .map(move |&h| (w, h))
+        })
+        .collect();
+    let best = candidates
+        .iter()
+        .map(|&(w, h)| error(w, h))
+        .fold(f64::INFINITY, f64::min);
+    candidates
+        .into_iter()
+        .filter(|&(w, h)| error(w, h) <= best + ASPECT_TOLERANCE)
+        .max_by_key(|&(w, h)| u64::from(w) * u64::from(h))
+        .unwrap_or((1, 1))
+}
+
+/// `img` reduced to its native grid, one pixel per uniform block, or `None`
+/// if it already is.
+pub(super) fn native_grid(img: &DynamicImage) -> Option<DynamicImage> {
+    let (block_w, block_h) = block_size(&img.to_rgba8());
+    ((block_w, block_h) != (1, 1)).then(|| {
+        let (width, height) = (img.width() / block_w, img.height() / block_h);
+        img.resize_exact(width, height, FilterType::Nearest)
+    })
+}
+
+/// The sizes `native` reaches when multiplied or divided by a whole
+/// number, ascending, that are no larger than `target`. Always holds at
+/// least one size.
+fn whole_scales(native: u32, target: u32) -> Vec<u32> {
+    let native = native.max(1);
+    let mut sizes: Vec<u32> = (1..=native)
+        .map(|divisor| native / divisor)
+        .filter(|&size| size <= target)
+        .chain((2..=target / native).map(|factor| native * factor))
+        .collect();
+    sizes.sort_unstable();
+    sizes.dedup();
+    if sizes.is_empty() {
+        sizes.push(1);
+    }
+    sizes
+}
+
+/// The width and height of the uniform blocks `rgba` is made of: the GCD
+/// of the runs of identical pixels along each row, and of the runs of
+/// identical rows.
+fn block_size(rgba: &RgbaImage) -> (u32, u32) {
+    let (width, height) = rgba.dimensions();
+    if width == 0 || height == 0 {
+        return (1, 1);
+    }
+    let rows: Vec<&[u8]> = rgba.chunks_exact(width as usize * 4).collect();
+
+    let mut block_w = 0;
+    for row in &rows {
+        let mut run = 1;
+        let mut pixels = row.chunks_exact(4);
+        let mut prev = pixels.next();
+        for px in pixels {
+            if prev == Some(px) {
+                run += 1;
+            } else {
+                block_w = gcd(block_w, run);
+                run = 1;
+            }
+            prev = Some(px);
+        }
+        block_w = gcd(block_w, run);
+        if block_w == 1 {
+            break;
+        }
+    }
+
+    let mut block_h = 0;
+    let mut run = 1;
+    for pair in rows.windows(2) {
+        if pair[0] == pair[1] {
+            run += 1;
+        } else {
+            block_h = gcd(block_h, run);
+            run = 1;
+            if block_h == 1 {
+                break;
+            }
+        }
+    }
+    (block_w, gcd(block_h, run))
+}
+
+/// Resizes `rgba` to `width` x `height`, giving each output pixel the most
+/// common colour of the source pixels it covers.
+///
+/// Ties go to the darker colour. An opaque colour darker than the winner
+/// that crosses the whole block as a line, touching every row or every
+/// column while covering little else, wins instead, so 1 px outlines stay
+/// 1 px thick rather than dropping out. Pixels below the alpha threshold
+/// all count as one transparent colour. When upscaling, each output pixel
+/// covers a single source pixel.
+pub(super) fn majority(rgba: &RgbaImage, width: u32, height: u32) -> RgbaImage {
+    let (src_w, src_h) = rgba.dimensions();
+    let span = |i: u32, out: u32, src: u32| {
+        let start = u64::from(i) * u64::from(src) / u64::from(out);
+        let end = (u64::from(i + 1) * u64::from(src) / u64::from(out)).max(start + 1);
+        // Both ends are at most `src`, so they fit in u32.
+        let clamp = |v: u64| u32::try_from(v).unwrap_or(src).min(src);
+        clamp(start)..clamp(end)
+    };
+    let mut colours = HashMap::new();
+    RgbaImage::from_fn(width, height, |x, y| {
+        let (xs, ys) = (span(x, width, src_w), span(y, height, src_h));
+        block_colour(rgba, xs, ys, &mut colours)
+    })
+}
+
+/// One colour's tally within a block.
+struct Tally {
+    count: u32,
+    rows: u32,
+    cols: u32,
+    last: u32,
+}
+
+/// The majority colour of a block, with the line rule of [`majority`].
+fn block_colour(
+    rgba: &RgbaImage,
+    xs: std::ops::Range<u32>,
+    ys: std::ops::Range<u32>,
+    tallies: &mut HashMap<[u8; 4], Tally>,
+) -> Rgba<u8> {
+    let key = |x: u32, y: u32| {
+        let p = rgba.get_pixel(x, y).0;
+        if p[3] < ALPHA_THRESHOLD { [0; 4] } else { p }
+    };
+    tallies.clear();
+    for y in ys.clone() {
+        for x in xs.clone() {
+            let t = tallies.entry(key(x, y)).or_insert(Tally {
+                count: 0,
+                rows: 0,
+                cols: 0,
+                last: u32::MAX,
+            });
+            t.count += 1;
+            if t.last != y {
+                t.rows += 1;
+                t.last = y;
+            }
+        }
+    }
+    for t in tallies.values_mut() {
+        t.last = u32::MAX;
+    }
+    for x in xs.clone() {
+        for y in ys.clone() {
+            if let Some(t) = tallies.get_mut(&key(x, y))
+                && t.last != x
+            {
+                t.cols += 1;
+                t.last = x;
+            }
+        }
+    }
+
+    // The colour itself breaks exact ties, since the map has no order.
+    let Some((&winner, _)) = tallies
+        .iter()
+        .max_by_key(|&(&c, t)| (t.count, u32::MAX - luma(c), c))
+    else {
+        return Rgba([0; 4]);
+    };
+    let (block_w, block_h) = (xs.end - xs.start, ys.end - ys.start);
+    let is_line = |c: [u8; 4], t: &Tally| {
+        let opaque = c[3] >= ALPHA_THRESHOLD;
+        let vertical = block_h >= 2 && t.rows == block_h && t.count <= 2 * block_h;
+        let horizontal = block_w >= 2 && t.cols == block_w && t.count <= 2 * block_w;
+        opaque && (vertical || horizontal)
+    };
+    let winner_luma = if winner[3] < ALPHA_THRESHOLD {
+        u32::MAX
+    } else {
+        luma(winner)
+    };
+    let colour = tallies
+        .iter()
+        .filter(|&(&c, t)| c != winner && is_line(c, t) && luma(c) < winner_luma)
+        .map(|(&c, _)| c)
+        .min_by_key(|&c| (luma(c), c))
+        .unwrap_or(winner);
+    Rgba(colour)
+}
+
+fn luma(p: [u8; 4]) -> u32 {
+    crate::simd::luma_scalar(p[0], p[1], p[2])
+}
+
+const fn gcd(mut a: u32, mut b: u32) -> u32 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgba, imageops::FilterType};
+
+    /// A 5x3 sprite with no two neighbouring pixels alike.
+    fn sprite() -> RgbaImage {
+        RgbaImage::from_fn(5, 3, |x, y| {
+            let v = u8::try_from(x * 40 + y * 90).unwrap_or(u8::MAX);
+            Rgba([v, 255 - v, u8::try_from(x * y).unwrap_or(0), 255])
+        })
+    }
+
+    fn upscaled(kx: u32, ky: u32) -> DynamicImage {
+        let native = sprite();
+        DynamicImage::ImageRgba8(native).resize_exact(5 * kx, 3 * ky, FilterType::Nearest)
+    }
+
+    #[test]
+    fn finds_the_upscale_factor() {
+        assert_eq!(block_size(&sprite()), (1, 1));
+        assert_eq!(block_size(&upscaled(4, 4).to_rgba8()), (4, 4));
+        assert_eq!(block_size(&upscaled(3, 6).to_rgba8()), (3, 6));
+    }
+
+    #[test]
+    fn scales_the_native_grid_by_whole_numbers() {
+        let img = upscaled(8, 8);
+        // 40x24 upscaled from 5x3: 17 columns fit three native widths, and
+        // 30 native heights keep close to the 17:100 shape.
+        assert_eq!(pixel_art_size(&img, (17, 100)), (15, 90));
+        assert_eq!(pixel_art_size(&img, (5, 3)), (5, 3));
+        // Below the native grid, shrink by whole divisors.
+        assert_eq!(pixel_art_size(&img, (4, 2)), (2, 1));
+    }
+
+    #[test]
+    fn keeps_the_layout_aspect_ratio() {
+        let aspect = |(w, h): (u32, u32)| f64::from(w) / f64::from(h);
+        let img = upscaled(4, 4);
+        // 29x9 asks for twice the native width per native height; 5x and 3x
+        // fit but would squash it, so both factors step down to 4x and 2x.
+        let size = pixel_art_size(&img, (29, 9));
+        assert_eq!(size, (20, 6));
+        assert!((aspect(size) / aspect((29, 9)) - 1.0).abs() < 0.05);
+
+        // A 2:1 layout one pixel too narrow to double the width halves the
+        // height instead of dropping to 1:1.
+        let square = DynamicImage::ImageRgba8(RgbaImage::from_fn(4, 4, |x, y| {
+            Rgba([u8::try_from(x * 60 + y).unwrap_or(0), 0, 0, 255])
+        }))
+        .resize_exact(16, 16, FilterType::Nearest);
+        assert_eq!(pixel_art_size(&square, (7, 4)), (4, 2));
+        assert_eq!(pixel_art_size(&square, (15, 8)), (8, 4));
+    }
+
+    fn majority_of(pixels: &[[u8; 4]], w: u32, out: (u32, u32)) -> RgbaImage {
+        let h = u32::try_from(pixels.len()).unwrap_or(0) / w;
+        let img = RgbaImage::from_fn(w, h, |x, y| Rgba(pixels[(y * w + x) as usize]));
+        majority(&img, out.0, out.1)
+    }
+
+    const W: [u8; 4] = [255, 255, 255, 255];
+    const K: [u8; 4] = [0, 0, 0, 255];
+    const R: [u8; 4] = [200, 0, 0, 255];
+    const T: [u8; 4] = [0, 0, 0, 0];
+
+    #[test]
+    fn majority_picks_the_most_common_colour() {
+        let out = majority_of(&[R, R, W, R, R, W, W, R, R], 3, (1, 1));
+        assert_eq!(out.get_pixel(0, 0).0, R);
+        // Ties go to the darker colour.
+        let out = majority_of(&[R, W, W, R], 2, (1, 1));
+        assert_eq!(out.get_pixel(0, 0).0, R);
+    }
+
+    #[test]
+    fn majority_keeps_thin_outlines() {
+        // A one-pixel black outline down a white 4x4 block.
+        #[rustfmt::skip]
+        let block = [
+            W, K, W, W,
+            W, K, W, W,
+            W, K, W, W,
+            W, K, W, W,
+        ];
+        assert_eq!(majority_of(&block, 4, (1, 1)).get_pixel(0, 0).0, K);
+        // A dark speck that doesn't cross the block is outvoted.
+        #[rustfmt::skip]
+        let speck = [
+            W, W, W, W,
+            W, K, W, W,
+            W, W, W, W,
+            W, W, W, W,
+        ];
+        assert_eq!(majority_of(&speck, 4, (1, 1)).get_pixel(0, 0).0, W);
+    }
+
+    #[test]
+    fn majority_keeps_sprite_edges_against_transparency() {
+        #[rustfmt::skip]
+        let edge = [
+            T, T, K, R,
+            T, T, K, R,
+            T, T, K, R,
+        ];
+        let out = majority_of(&edge, 4, (2, 1));
+        assert_eq!(out.get_pixel(0, 0).0, T);
+        assert_eq!(out.get_pixel(1, 0).0, K);
+        let faint = majority_of(&[[9, 9, 9, 3], T, T, T], 2, (1, 1));
+        assert_eq!(faint.get_pixel(0, 0).0, T);
+    }
+
+    #[test]
+    fn majority_upscales_by_repeating_pixels() {
+        let out = majority_of(&[R, W], 2, (4, 2));
+        assert_eq!(out.get_pixel(1, 1).0, R);
+        assert_eq!(out.get_pixel(2, 0).0, W);
+    }
+
+    #[test]
+    fn integer_scaling_keeps_every_native_pixel() {
+        let img = upscaled(7, 7);
+        let (w, h) = pixel_art_size(&img, (12, 9));
+        let resized = img.resize_exact(w, h, FilterType::Nearest).to_rgba8();
+        let native = sprite();
+        assert_eq!((w, h), (10, 9));
+        for (x, y, p) in resized.enumerate_pixels() {
+            assert_eq!(p, native.get_pixel(x / 2, y / 3), "({x}, {y})");
+        }
+    }
+}
@@ -0,0 +1,303 @@
+//! Colour reduction for [`RenderOptions::text_max_colors`].
+//!
+//! Median cut splits the image's colours in Oklab into at most N boxes, and a
+//! few k-means passes then pull each box's centre towards its members. Every
+//! pixel is replaced by the centre of its cluster, so the prepared image holds
+//! at most N distinct colours and runs of equal colour let the renderer skip
+//! repeated escape sequences.
+//!
+//! [`RenderOptions::text_max_colors`]: super::RenderOptions::text_max_colors
+
+use image::RgbaImage;
+
+use crate::color::{oklab_distance, oklab_to_rgb, rgb_to_oklab};
+
+/// K-means passes after median cut; the clusters barely move after these.
+const REFINE_PASSES: usize = 4;
+
+/// One distinct colour of the image and how many pixels have it.
+#[derive(Clone, Copy)]
+struct Entry {
+    rgb: [u8; 3],
+    lab: [f32; 3],
+    count: u32,
+}
+
+/// Reduces the visible pixels of `img` to at most `max_colors` distinct
+/// colours. Alpha is left untouched, as are images that already fit.
+pub(super) fn quantize(img: &mut RgbaImage, max_colors: usize) {
+    let mut entries = histogram(img);
+    let max_colors = max_colors.max(1);
+    if entries.len() <= max_colors {
+        return;
+    }
+
+    let mut centres = median_cut(&mut entries, max_colors);
+    let mut assignment = vec![0; entries.len()];
+    for _ in 0..REFINE_PASSES {
+        assign(&entries, &centres, &mut assignment);
+        centres = recentre(&entries, &assignment, &centres);
+    }
+    assign(&entries, &centres, &mut assignment);
+
+    let palette: Vec<[u8; 3]> = centres.into_iter().map(oklab_to_rgb).collect();
+    // Median cut reordered the entries, so sort again for the lookup.
+    let mut lookup: Vec<([u8; 3], [u8; 3])> = entries
+        .iter()
+        .zip(&assignment)
+        .map(|(e, &c)| (e.rgb, palette[c]))
+        .collect();
+    lookup.sort_unstable_by_key(|&(from, _)| from);
+    for px in img.pixels_mut().filter(|p| p[3] > 0) {
+        let rgb = [px[0], px[1], px[2]];
+        if let Ok(i) = lookup.binary_search_by_key(&rgb, |&(from, _)| from) {
+            let [r, g, b] = lookup[i].1;
+            px.0 = [r, g, b, px[3]];
+        }
+    }
+}
+
+/// Snaps colours the renderer derives by averaging pixels, such as sub-cell
+/// foregrounds and backgrounds, back onto the colours of a quantized image.
+pub(super) struct Snapper {
+    colors: Vec<([u8; 3], [f32; 3])>,
+}
+
+impl Snapper {
+    /// Collects the distinct visible colours of `img`.
+    pub(super) fn new(img: &RgbaImage) -> Self {
+        Self {
+            colors: histogram(img).into_iter().map(|e| (e.rgb, e.lab)).collect(),
+        }
+    }
+
+    /// Returns the image colour closest to `rgb` in Oklab.
+    pub(super) fn snap(&self, rgb: [u8; 3]) -> [u8; 3] {
+        if self.colors.binary_search_by_key(&rgb, |&(c, _)| c).is_ok() {
+            return rgb;
+        }
+        let target = rgb_to_oklab(rgb[0], rgb[1], rgb[2]);
+        self.colors
+            .iter()
+            .min_by(|(_, a), (_, b)| {
+                oklab_distance(target, *a).total_cmp(&oklab_distance(target, *b))
+            })
+            .map_or(rgb, |&(c, _)| c)
+    }
+}
+
+/// The distinct colours of the visible pixels, sorted by RGB.
+fn histogram(img: &RgbaImage) -> Vec<Entry> {
+    let mut rgb: Vec<[u8; 3]> = img
+        .pixels()
+        .filter(|p| p[3] > 0)
+        .map(|p| [p[0], p[1], p[2]])
+        .collect();
+    rgb.sort_unstable();
+
+    let mut entries: Vec<Entry> = Vec::new();
+    for c in rgb {
+        match entries.last_mut() {
+            Some(last) if last.rgb == c => last.count += 1,
+            _ => entries.push(Entry {
+                rgb: c,
+                lab: rgb_to_oklab(c[0], c[1], c[2]),
+                count: 1,
+            }),
+        }
+    }
+    entries
+}
+
+/// Splits `entries` into `max_colors` boxes, always cutting the box with the
+/// widest Oklab extent at the pixel-weighted median of that axis, and returns
+/// each box's weighted mean.
+fn median_cut(entries: &mut [Entry], max_colors: usize) -> Vec<[f32; 3]> {
+    let mut boxes = Vec::with_capacity(max_colors);
+    boxes.push(0..entries.len());
+    while boxes.len() < max_colors {
+        let widest = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.len() > 1)
+            .map(|(i, b)| (i, widest_axis(&entries[b.clone()])))
+            .max_by(|(_, (_, a)), (_, (_, b))| a.total_cmp(b));
+        let Some((i, (axis, _))) = widest else {
+            break;
+        };
+        let range = boxes.swap_remove(i);
+        let slice = &mut entries[range.clone()];
+        slice.sort_unstable_by(|a, b| a.lab[axis].total_cmp(&b.lab[axis]));
+        let half = slice.iter().map(|e| u64::from(e.count)).sum::<u64>() / 2;
+        let mut seen = 0;
+        let split = slice
+            .iter()
+            .position(|e| {
+                seen += u64::from(e.count);
+                seen > half
+            })
+            .unwrap_or(0)
+            .clamp(1, slice.len() - 1);
+        boxes.push(range.start..range.start + split);
+        boxes.push(range.start + split..range.end);
+    }
+    boxes.iter().map(|b| mean(&entries[b.clone()])).collect()
+}
+
+/// The Oklab axis along which `entries` spread furthest, and that spread.
+fn widest_axis(entries: &[Entry]) -> (usize, f32) {
+    (0..3)
+        .map(|axis| {
+            let (lo, hi) = entries.iter().fold((f32::MAX, f32::MIN), |(lo, hi), e| {
+                (lo.min(e.lab[axis]), hi.max(e.lab[axis]))
+            });
+            (axis, hi - lo)
+        })
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .unwrap_or((0, 0.0))
+}
+
+/// Pixel-weighted mean of `entries` in Oklab.
+#[expect(
+    clippy::cast_precision_loss,
+    reason = "Pixel counts stay far below f32's exact integer range"
+)]
+fn mean(entries: &[Entry]) -> [f32; 3] {
+    let mut sum = [0.0f32; 3];
+    let mut total = 0.0f32;
+    for e in entries {
+        let weight = e.count as f32;
+        for (s, c) in sum.iter_mut().zip(e.lab) {
+            *s = c.mul_add(weight, *s);
+        }
+        total += weight;
+    }
+    sum.map(|s| s / total.max(1.0))
+}
+
+/// Points each entry at its nearest centre.
+fn assign(entries: &[Entry], centres: &[[f32; 3]], assignment: &mut [usize]) {
+    for (e, slot) in entries.iter().zip(assignment) {
+        *slot = centres
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| {
+                oklab_distance(e.lab, **a).total_cmp(&oklab_distance(e.lab, **b))
+            })
+            .map_or(0, |(i, _)| i);
+    }
+}
+
+/// Moves every centre to the weighted mean of its entries; centres left
+/// without entries stay where they were.
+#[expect(
+    clippy::cast_precision_loss,
+    reason = "Pixel counts stay far below f32's exact integer range"
+)]
+fn recentre(entries: &[Entry], assignment: &[usize], centres: &[[f32; 3]]) -> Vec<[f32; 3]> {
+    let mut sums = vec![([0.0f32; 3], 0.0f32); centres.len()];
+    for (e, &c) in entries.iter().zip(assignment) {
+        let weight = e.count as f32;
+        let (sum, total) = &mut sums[c];
+        for (s, l) in sum.iter_mut().zip(e.lab) {
+            *s = l.mul_add(weight, *s);
+        }
+        *total += weight;
+    }
+    sums.into_iter()
+        .zip(centres)
+        .map(|((sum, total), &old)| {
+            if total > 0.0 {
+                sum.map(|s| s / total)
+            } else {
+                old
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    fn distinct(img: &RgbaImage) -> usize {
+        histogram(img).len()
+    }
+
+    fn byte(v: u32) -> u8 {
+        u8::try_from(v).unwrap_or(u8::MAX)
+    }
+
+    #[test]
+    fn gradient_reduces_to_the_requested_count() {
+        let mut img =
+            RgbaImage::from_fn(64, 16, |x, y| Rgba([byte(x * 4), byte(y * 16), 128, 255]));
+        assert_eq!(distinct(&img), 64 * 16);
+        quantize(&mut img, 8);
+        assert!(distinct(&img) <= 8, "{}", distinct(&img));
+        assert!(distinct(&img) >= 6, "{}", distinct(&img));
+    }
+
+    #[test]
+    fn two_tone_image_keeps_its_colours() {
+        let mut img = RgbaImage::from_fn(8, 8, |x, _| {
+            if x < 4 {
+                Rgba([200, 30, 40, 255])
+            } else {
+                Rgba([10, 90, 220, 255])
+            }
+        });
+        let before = img.clone();
+        quantize(&mut img, 2);
+        assert_eq!(img, before);
+        quantize(&mut img, 1);
+        assert_eq!(distinct(&img), 1);
+    }
+
+    #[test]
+    fn clusters_separate_distant_colours() {
+        // Two tight clusters far apart in Oklab end up as one colour each.
+        let mut img = RgbaImage::from_fn(10, 2, |x, y| {
+            let jitter = byte(x % 5);
+            if y == 0 {
+                Rgba([250 - jitter, 10 + jitter, 10, 255])
+            } else {
+                Rgba([10, 10 + jitter, 250 - jitter, 255])
+            }
+        });
+        quantize(&mut img, 2);
+        let top = img.get_pixel(0, 0).0;
+        let bottom = img.get_pixel(0, 1).0;
+        assert!(img.rows().next().is_some_and(|mut r| r.all(|p| p.0 == top)));
+        assert!(
+            img.rows()
+                .nth(1)
+                .is_some_and(|mut r| r.all(|p| p.0 == bottom))
+        );
+        assert!(top[0] > 200 && bottom[2] > 200, "{top:?} {bottom:?}");
+    }
+
+    #[test]
+    fn transparent_pixels_are_left_alone() {
+        let mut img = RgbaImage::from_fn(4, 1, |x, _| Rgba([byte(x * 60), 0, 0, 255]));
+        img.put_pixel(0, 0, Rgba([1, 2, 3, 0]));
+        quantize(&mut img, 1);
+        assert_eq!(img.get_pixel(0, 0).0, [1, 2, 3, 0]);
+    }
+
+    #[test]
+    fn snapper_returns_the_nearest_image_colour() {
+        let img = RgbaImage::from_fn(2, 1, |x, _| {
+            if x == 0 {
+                Rgba([0, 0, 0, 255])
+            } else {
+                Rgba([255, 255, 255, 255])
+            }
+        });
+        let snapper = Snapper::new(&img);
+        assert_eq!(snapper.snap([40, 40, 40]), [0, 0, 0]);
+        assert_eq!(snapper.snap([200, 210, 220]), [255, 255, 255]);
+        assert_eq!(snapper.snap([255, 255, 255]), [255, 255, 255]);
+    }
+}
@@ -3,77 +3,159 @@ use std::io::{self, Write};
 
 use image::{DynamicImage, GenericImageView, Rgba, RgbaImage};
 
-use super::color::{ColorState, write_colored_glyph, write_full_block, write_half_block};
+use super::blocks::{BlockCell, BlockShape, fit_cell, mono_mask};
+use super::edges::detect;
+use super::encode::{AnsiEncoder, Encoder};
+use super::grid::{Cell, CellGrid};
+use super::hybrid::{CELL_OFFSETS, HybridFitter, to_oklab};
+use super::iterm::write_iterm2;
+use super::kitty::write_kitty;
 use super::options::RenderOptions;
-use super::pixel::{ColorParams, LumaParams, RenderCtx, luma_range_pass1};
-use super::serial::render_serial;
-use super::types::{CharsetMode, Density};
+use super::pixel::{LumaParams, RenderCtx, luma_range_pass1};
+use super::quantize::Snapper;
+use super::serial::grid_serial;
+use super::tone::levels;
+use super::types::{CharsetMode, Density, DitherMode, ToneMap};
+#[cfg(feature = "rasterize")]
+use super::{
+    pixel::ALPHA_THRESHOLD,
+    shape::{SHAPE_PIXELS, fit},
+    utils::{SHAPE_CELL_H, SHAPE_CELL_W},
+};
 use crate::ColorMode;
 
 #[cfg(feature = "parallel")]
-use super::parallel::render_parallel;
+use super::parallel::grid_parallel;
+
+const FADE_RAMP: &[&str] = &[" ", "░", "▒", "▓", "█"];
+
+const ASCII_LIGHT_RAMP: &[&str] = &[
+    " ", ".", "`", "\"", "\\", ":", "I", "!", ">", "~", "_", "?", "[", "{", "|", ")", "(", "/",
+    "Y", "L", "p", "d", "a", "*", "W", "8", "%", "@", "$",
+];
+
+const ASCII_MEDIUM_RAMP: &[&str] = &[
+    " ", ".", "'", "`", "^", "\"", ",", ":", ";", "I", "l", "!", "i", ">", "<", "~", "+", "_", "-",
+    "?", "]", "[", "}", "{", "1", ")", "(", "|", "\\", "/", "t", "f", "j", "r", "x", "n", "u", "v",
+    "c", "z", "X", "Y", "U", "J", "C", "L", "Q", "0", "O", "Z", "m", "w", "q", "p", "d", "b", "k",
+    "h", "a", "o", "*", "#", "M", "W", "&", "8", "%", "B", "@", "$",
+];
+
+const ASCII_HEAVY_RAMP: &[&str] = &[" ", ".", ":", "o", "O", "0", "#", "M", "W", "@", "$"];
+
+const KANJI_RAMP: &[&str] = &[
+    "\u{3000}", "一", "二", "十", "口", "日", "田", "目", "国", "風", "龍", "龘",
+];
+
+const CHINESE_RAMP: &[&str] = &[
+    "\u{3000}", "一", "二", "十", "人", "丁", "口", "日", "目", "田", "国", "木", "金", "華", "黑",
+    "龍", "龘",
+];
+
+const fn ascii_ramp(density: Density) -> &'static [&'static str] {
+    match density {
+        Density::Light => ASCII_LIGHT_RAMP,
+        Density::Medium => ASCII_MEDIUM_RAMP,
+        Density::Heavy => ASCII_HEAVY_RAMP,
+    }
+}
+
+/// Returns the brightness ramp, darkest first, that `charset` picks glyphs
+/// from by luminance, or `None` for modes that don't draw from a ramp.
+pub(super) const fn glyph_ramp(
+    charset: CharsetMode,
+    density: Density,
+) -> Option<&'static [&'static str]> {
+    match charset {
+        CharsetMode::Fade => Some(FADE_RAMP),
+        CharsetMode::Ascii => Some(ascii_ramp(density)),
+        CharsetMode::Kanji => Some(KANJI_RAMP),
+        CharsetMode::Chinese => Some(CHINESE_RAMP),
+        CharsetMode::Custom(custom) => Some(custom.glyphs()),
+        _ => None,
+    }
+}
 
-/// Internal engine that coordinates the conversion of image pixels into
-/// terminal-friendly character output.
-struct Renderer<'img, 'w, W: Write> {
-    writer: &'w mut W,
+/// Internal engine that converts image pixels into a [`CellGrid`] of
+/// terminal cells.
+struct Renderer<'img> {
     img: &'img DynamicImage,
     options: RenderOptions,
 }
 
-impl<'img, 'w, W: Write> Renderer<'img, 'w, W> {
-    const fn new(writer: &'w mut W, img: &'img DynamicImage, options: RenderOptions) -> Self {
-        Self {
-            writer,
-            img,
-            options,
-        }
+impl<'img> Renderer<'img> {
+    const fn new(img: &'img DynamicImage, options: RenderOptions) -> Self {
+        Self { img, options }
+    }
+
+    /// The image as 8-bit RGBA, borrowed when it already is.
+    fn rgba(&self) -> Cow<'img, RgbaImage> {
+        self.img
+            .as_rgba8()
+            .map_or_else(|| Cow::Owned(self.img.to_rgba8()), Cow::Borrowed)
+    }
+
+    /// With [`RenderOptions::text_max_colors`] set, returns a [`Snapper`] that keeps
+    /// colours averaged over several pixels within the image's reduced
+    /// palette.
+    fn snapper(&self, rgba: &RgbaImage) -> Option<Snapper> {
+        self.options
+            .text_max_colors()
+            .filter(|_| self.options.color_mode() != ColorMode::None)
+            .map(|_| Snapper::new(rgba))
     }
 
-    /// Renders using the "Half-Block" method (▄), which allows for two vertical
-    /// "pixels" per terminal cell by setting different foreground and background colors.
-    fn ansi_blocks(&mut self) -> io::Result<()> {
+    /// Renders using the "Half-Block" method (▀/▄), which allows for two
+    /// vertical "pixels" per terminal cell by setting different foreground
+    /// and background colors.
+    fn ansi_blocks(&self) -> CellGrid {
         let (width, height) = self.img.dimensions();
         // We step by 2 because one terminal row covers two rows of image pixels.
-        for y in (0..height).step_by(2) {
-            for x in 0..width {
-                let top = self.img.get_pixel(x, y);
-                let bot = if y + 1 < height {
-                    self.img.get_pixel(x, y + 1)
-                } else {
-                    Rgba([0, 0, 0, 0])
-                };
-                write_half_block(self.writer, top, bot)?;
-            }
-            writeln!(self.writer, "\x1b[0m")?; // Reset color at EOL
-        }
-        Ok(())
+        (0..height)
+            .step_by(2)
+            .map(|y| {
+                (0..width)
+                    .map(|x| {
+                        let top = self.img.get_pixel(x, y);
+                        let bot = if y + 1 < height {
+                            self.img.get_pixel(x, y + 1)
+                        } else {
+                            Rgba([0, 0, 0, 0])
+                        };
+                        half_block(top, bot)
+                    })
+                    .collect()
+            })
+            .collect()
     }
 
-    /// Renders using full block characters (█). If 'full' is false, it
+    /// Renders using full block characters (██). If 'full' is false, it
     /// defaults back to the more efficient half-block method.
-    fn unicode_blocks(&mut self, full: bool) -> io::Result<()> {
-        if full {
-            let (width, height) = self.img.dimensions();
-            for y in 0..height {
-                for x in 0..width {
-                    write_full_block(self.writer, self.img.get_pixel(x, y))?;
-                }
-                writeln!(self.writer, "\x1b[0m")?;
-            }
-        } else {
-            self.ansi_blocks()?;
+    fn unicode_blocks(&self, full: bool) -> CellGrid {
+        if !full {
+            return self.ansi_blocks();
         }
-        Ok(())
+        let (width, height) = self.img.dimensions();
+        (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| {
+                        let [r, g, b, a] = self.img.get_pixel(x, y).0;
+                        if a > 0 {
+                            Cell::new("██", 2).with_fg(Some([r, g, b]))
+                        } else {
+                            Cell::blank(2)
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
     }
 
     /// Renders using Unicode Braille patterns (U+2800 - U+28FF).
     /// This provides a 2x4 "sub-pixel" resolution within a single character cell.
-    fn braille(&mut self) -> io::Result<()> {
-        let rgba: Cow<'_, RgbaImage> = self
-            .img
-            .as_rgba8()
-            .map_or_else(|| Cow::Owned(self.img.to_rgba8()), Cow::Borrowed);
+    fn braille(&self) -> CellGrid {
+        let rgba = self.rgba();
         let (width, height) = rgba.dimensions();
 
         // Braille dot-to-bit mapping (standard 2x4 grid)
@@ -91,11 +173,11 @@ impl<'img, 'w, W: Write> Renderer<'img, 'w, W> {
             (0, 3, 0x40),
             (1, 3, 0x80),
         ];
+        let snapper = self.snapper(&rgba);
 
-        let mut last_color = ColorState::default();
-        let mode = self.options.color_mode(); // Cache the mode
-
+        let mut grid = CellGrid::new();
         for y in (0..height).step_by(4) {
+            let mut row = Vec::with_capacity(width.div_ceil(2) as usize);
             for x in (0..width).step_by(2) {
                 let mut byte = 0u8;
                 let (mut r_sum, mut g_sum, mut b_sum, mut lit_count) = (0u32, 0u32, 0u32, 0u32);
@@ -122,161 +204,315 @@ impl<'img, 'w, W: Write> Renderer<'img, 'w, W> {
                     }
                 }
 
-                // --- RENDERING LOGIC ---
-                //
-                // Render the accumulated 2x4 block
+                // No dots lit: an empty braille cell in the default colours.
                 if byte == 0 || lit_count == 0 {
-                    // No dots are lit: Reset color if needed and print empty braille
-                    if mode != ColorMode::None && last_color != ColorState::default() {
-                        write!(self.writer, "\x1b[0m")?;
-                        last_color = ColorState::default();
-                    }
-                    write!(self.writer, "\u{2800}")?; // Empty braille cell
-                } else {
-                    let r = u8::try_from(r_sum / lit_count).unwrap_or(0);
-                    let g = u8::try_from(g_sum / lit_count).unwrap_or(0);
-                    let b = u8::try_from(b_sum / lit_count).unwrap_or(0);
-
-                    let ch = char::from_u32(0x2800 + u32::from(byte)).unwrap_or(' ');
-
-                    if mode == ColorMode::None {
-                        write!(self.writer, "{ch}")?;
-                    } else {
-                        let mut buf = [0u8; 4];
-                        let glyph = ch.encode_utf8(&mut buf);
-                        write_colored_glyph(self.writer, glyph, r, g, b, mode, &mut last_color)?;
-                    }
+                    row.push(Cell::new("\u{2800}", 1));
+                    continue;
                 }
+                let r = u8::try_from(r_sum / lit_count).unwrap_or(0);
+                let g = u8::try_from(g_sum / lit_count).unwrap_or(0);
+                let b = u8::try_from(b_sum / lit_count).unwrap_or(0);
+                let rgb = snapper.as_ref().map_or([r, g, b], |s| s.snap([r, g, b]));
+                let ch = char::from_u32(0x2800 + u32::from(byte)).unwrap_or(' ');
+                row.push(Cell::from_char(ch, 1).with_fg(Some(rgb)));
             }
+            grid.push_row(row);
+        }
+        grid
+    }
 
-            if mode != ColorMode::None && last_color != ColorState::default() {
-                write!(self.writer, "\x1b[0m")?;
-                last_color = ColorState::default();
+    /// Renders using sub-cell block characters: quadrants (▘▝▖▗▚▞…), sextants
+    /// or octants. Each cell covers a 2-pixel-wide block of `shape.rows()`
+    /// rows with a fitted foreground/background pair, so unlike braille every
+    /// sub-pixel keeps its colour and the cell is fully covered.
+    fn blocks(&self, shape: BlockShape) -> CellGrid {
+        let rgba = self.rgba();
+        let (width, height) = rgba.dimensions();
+        let mono = self.options.color_mode() == ColorMode::None;
+        let snapper = self.snapper(&rgba);
+        let snap = |c: [u8; 3]| snapper.as_ref().map_or(c, |s| s.snap(c));
+        let sample = |x: u32, y: u32| {
+            (x < width && y < height)
+                .then(|| rgba.get_pixel(x, y).0)
+                .filter(|p| p[3] > 0)
+                .map(|[r, g, b, _]| [r, g, b])
+        };
+
+        let rows = shape.rows();
+        let mut px = Vec::with_capacity(2 * rows as usize);
+        let mut grid = CellGrid::new();
+        for y in (0..height).step_by(rows as usize) {
+            let mut row = Vec::with_capacity(width.div_ceil(2) as usize);
+            for x in (0..width).step_by(2) {
+                px.clear();
+                px.extend((0..rows).flat_map(|dy| [sample(x, y + dy), sample(x + 1, y + dy)]));
+                let cell = if mono {
+                    BlockCell {
+                        mask: mono_mask(&px),
+                        fg: None,
+                        bg: None,
+                    }
+                } else {
+                    fit_cell(&px)
+                };
+                row.push(
+                    Cell::from_char(shape.glyph(cell.mask), 1)
+                        .with_fg(cell.fg.map(snap))
+                        .with_bg(cell.bg.map(snap)),
+                );
             }
+            grid.push_row(row);
+        }
+        grid
+    }
+
+    /// Renders each 2x4 pixel cell with whichever glyph from the configured
+    /// [`SymbolSet`](super::types::SymbolSet) reproduces it with the lowest
+    /// Oklab error, mixing block, sextant, braille and shade glyphs freely.
+    fn hybrid(&self) -> CellGrid {
+        let rgba = self.rgba();
+        let (width, height) = rgba.dimensions();
+        let mono = self.options.color_mode() == ColorMode::None;
+        let fitter = HybridFitter::new(self.options.symbols());
+        let snapper = self.snapper(&rgba);
+        let snap = |c: [u8; 3]| snapper.as_ref().map_or(c, |s| s.snap(c));
+        let sample = |x: u32, y: u32| {
+            (x < width && y < height)
+                .then(|| rgba.get_pixel(x, y).0)
+                .filter(|p| p[3] > 0)
+                .map(|[r, g, b, _]| to_oklab([r, g, b]))
+        };
 
-            // Everyone gets a newline
-            writeln!(self.writer)?;
+        (0..height)
+            .step_by(4)
+            .map(|y| {
+                (0..width)
+                    .step_by(2)
+                    .map(|x| {
+                        let px = CELL_OFFSETS.map(|(dx, dy)| sample(x + dx, y + dy));
+                        if mono {
+                            return Cell::from_char(fitter.fit_mono(&px), 1);
+                        }
+                        let cell = fitter.fit(&px);
+                        Cell::from_char(cell.glyph, 1)
+                            .with_fg(cell.fg.map(snap))
+                            .with_bg(cell.bg.map(snap))
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Renders ASCII chosen by shape: each 4x8 pixel cell is normalised to the
+    /// image's luma range and matched against the font's glyph coverage.
+    /// Glyphs take the coverage-weighted colour of the pixels under their ink.
+    #[cfg(feature = "rasterize")]
+    fn shape(&self) -> CellGrid {
+        let rgba = self.rgba();
+        let (width, height) = rgba.dimensions();
+        let snapper = self.snapper(&rgba);
+        let (lo, hi) = luma_range_pass1(&rgba, width, height, 1, false, false).unwrap_or((0, 0));
+        #[expect(clippy::cast_precision_loss, reason = "luma is at most 255")]
+        let (lo, range) = (lo as f32, (hi - lo).max(1) as f32);
+
+        let mut grid = CellGrid::new();
+        for y in (0..height).step_by(SHAPE_CELL_H as usize) {
+            let mut row = Vec::with_capacity(width.div_ceil(SHAPE_CELL_W) as usize);
+            for x in (0..width).step_by(SHAPE_CELL_W as usize) {
+                let mut px = [None; SHAPE_PIXELS];
+                let mut cell = [0.0; SHAPE_PIXELS];
+                for (i, (p, l)) in px.iter_mut().zip(&mut cell).enumerate() {
+                    let (dx, dy) = shape_offset(i);
+                    let (sx, sy) = (x + dx, y + dy);
+                    if sx < width && sy < height {
+                        let [r, g, b, a] = rgba.get_pixel(sx, sy).0;
+                        if a >= ALPHA_THRESHOLD {
+                            #[expect(clippy::cast_precision_loss, reason = "luma is at most 255")]
+                            let luma = crate::simd::luma_scalar(r, g, b) as f32;
+                            *l = (luma - lo) / range;
+                            *p = Some([r, g, b]);
+                        }
+                    }
+                }
+                let fitted = fit(&cell);
+                let ink = ink_color(&px, &fitted.coverage)
+                    .filter(|_| fitted.glyph != ' ')
+                    .map(|ink| snapper.as_ref().map_or(ink, |s| s.snap(ink)));
+                row.push(Cell::from_char(fitted.glyph, 1).with_fg(ink));
+            }
+            grid.push_row(row);
         }
+        grid
+    }
 
-        Ok(())
+    /// Renders contours only: each edge pixel becomes a line glyph oriented
+    /// along the edge and coloured from its brighter side.
+    fn edges(&self) -> CellGrid {
+        let rgba = self.rgba();
+        let width = rgba.width() as usize;
+        detect(&rgba, self.options.cell_aspect())
+            .chunks(width.max(1))
+            .map(|row| {
+                row.iter()
+                    .map(|edge| {
+                        edge.as_ref().map_or_else(
+                            || Cell::blank(1),
+                            |edge| {
+                                let [r, g, b, _] = rgba.get_pixel(edge.source.0, edge.source.1).0;
+                                Cell::from_char(edge.dir.glyph(), 1).with_fg(Some([r, g, b]))
+                            },
+                        )
+                    })
+                    .collect()
+            })
+            .collect()
     }
 
     /// Renders using a gradient of block characters (░▒▓█) based on luminance.
-    fn fade(&mut self) -> io::Result<()> {
-        self.charset_colored(&[" ", "░", "▒", "▓", "█"], false)
+    fn fade(&self) -> CellGrid {
+        self.charset_colored(FADE_RAMP, false)
     }
 
     /// Renders using standard ASCII characters mapped by visual density.
-    fn ascii(&mut self, density: Density) -> io::Result<()> {
-        let charset: &[&str] = match density {
-            Density::Light => &[
-                " ", ".", "`", "\"", "\\", ":", "I", "!", ">", "~", "_", "?", "[", "{", "|", ")",
-                "(", "/", "Y", "L", "p", "d", "a", "*", "W", "8", "%", "@", "$",
-            ],
-            Density::Medium => &[
-                " ", ".", "'", "`", "^", "\"", ",", ":", ";", "I", "l", "!", "i", ">", "<", "~",
-                "+", "_", "-", "?", "]", "[", "}", "{", "1", ")", "(", "|", "\\", "/", "t", "f",
-                "j", "r", "x", "n", "u", "v", "c", "z", "X", "Y", "U", "J", "C", "L", "Q", "0",
-                "O", "Z", "m", "w", "q", "p", "d", "b", "k", "h", "a", "o", "*", "#", "M", "W",
-                "&", "8", "%", "B", "@", "$",
-            ],
-            Density::Heavy => &[" ", ".", ":", "o", "O", "0", "#", "M", "W", "@", "$"],
-        };
-        self.charset_colored(charset, false)
+    fn ascii(&self, density: Density) -> CellGrid {
+        self.charset_colored(ascii_ramp(density), false)
     }
 
     /// High-resolution rendering using Kanji characters of varying complexity.
-    fn kanji(&mut self) -> io::Result<()> {
-        self.charset_colored(
-            &[
-                "\u{3000}", "一", "二", "十", "口", "日", "田", "目", "国", "風", "龍", "龘",
-            ],
-            true,
-        )
+    fn kanji(&self) -> CellGrid {
+        self.charset_colored(KANJI_RAMP, true)
     }
 
     /// High-resolution rendering using Chinese characters of varying complexity.
-    fn chinese(&mut self) -> io::Result<()> {
-        self.charset_colored(
-            &[
-                "\u{3000}", "一", "二", "十", "人", "丁", "口", "日", "目", "田", "国", "木", "金",
-                "華", "黑", "龍", "龘",
-            ],
-            true,
-        )
+    fn chinese(&self) -> CellGrid {
+        self.charset_colored(CHINESE_RAMP, true)
     }
 
     /// Generic rendering path for custom character sets.
-    /// Handles luminance normalization to ensure the full range of the charset is used.
-    fn charset_colored(&mut self, charset: &[&str], wide: bool) -> io::Result<()> {
+    /// Handles luminance normalization to ensure the full range of the charset is used,
+    /// through the configured [`ToneMap`](super::ToneMap).
+    fn charset_colored(&self, charset: &[&'static str], wide: bool) -> CellGrid {
         let rgba = self.img.to_rgba8();
         let (width, height) = rgba.dimensions();
         let x_step: usize = if wide { 2 } else { 1 };
-        let blank: &str = if wide { "  " } else { " " };
         let num_chars_minus_1 = u32::try_from(charset.len()).unwrap_or(1) - 1;
 
         // Use parallel rendering if the image is large enough to justify the overhead
         let use_parallel = cfg!(feature = "parallel") && (width * height > 120_000);
 
+        let ctx = RenderCtx::new(&rgba, charset, wide);
         let Some((luma_min, luma_max)) =
             luma_range_pass1(&rgba, width, height, x_step, wide, use_parallel)
         else {
-            // Image is entirely transparent — fill with blanks and return
-            for _ in 0..height {
-                for _ in (0..width).step_by(x_step) {
-                    write!(self.writer, "{blank}")?;
-                }
-                writeln!(self.writer)?;
-            }
-            return Ok(());
+            // Image is entirely transparent — fill with blanks
+            let blank = Cell::blank(ctx.cell_width());
+            let columns = (0..width).step_by(x_step).count();
+            return (0..height).map(|_| vec![blank.clone(); columns]).collect();
+        };
+        // Dithering already quantized the image between tone-mapped levels.
+        let tone_map = match self.options.style().dither() {
+            DitherMode::None => self.options.tone_map(),
+            _ => ToneMap::Linear,
         };
+        let levels = levels(&rgba, tone_map);
         let lp = LumaParams {
             min: luma_min,
             range: (luma_max - luma_min).max(1),
             num_chars_minus_1,
+            levels: levels.as_deref(),
         };
-        let cp = ColorParams {
-            enabled: self.options.color_mode() != ColorMode::None,
-            mode: self.options.color_mode(),
-            blank,
-        };
-
-        let ctx = RenderCtx::new(&rgba, charset, wide);
 
+        #[cfg(feature = "parallel")]
         if use_parallel {
-            #[cfg(feature = "parallel")]
-            render_parallel(self.writer, &ctx, lp, cp)?;
-        } else {
-            render_serial(self.writer, &ctx, lp, cp)?;
+            return grid_parallel(&ctx, lp);
         }
+        grid_serial(&ctx, lp)
+    }
+}
 
-        Ok(())
+/// The half-block cell for a `top` and `bot` pixel: the upper half takes
+/// the foreground, the lower half the background.
+fn half_block(top: Rgba<u8>, bot: Rgba<u8>) -> Cell {
+    let rgb = |p: Rgba<u8>| Some([p[0], p[1], p[2]]);
+    match (top[3] > 0, bot[3] > 0) {
+        (true, true) => Cell::new("▀", 1).with_fg(rgb(top)).with_bg(rgb(bot)),
+        (true, false) => Cell::new("▀", 1).with_fg(rgb(top)),
+        (false, true) => Cell::new("▄", 1).with_fg(rgb(bot)),
+        (false, false) => Cell::blank(1),
     }
 }
 
+/// Renders a prepared image into a [`CellGrid`] using the mode specified in
+/// `options`, for post-processing or a custom [`Encoder`].
+///
+/// Colours are kept at full 24-bit precision; the encoder quantizes them.
+/// Returns `None` for the pixel protocols ([`CharsetMode::Sixel`],
+/// [`CharsetMode::Kitty`] and [`CharsetMode::Iterm2`]), which have no cells,
+/// and for [`CharsetMode::Shape`] without the `rasterize` feature.
+#[must_use]
+pub fn render_grid(img: &DynamicImage, options: RenderOptions) -> Option<CellGrid> {
+    let renderer = Renderer::new(img, options);
+    Some(match options.charset() {
+        CharsetMode::Ansi => renderer.ansi_blocks(),
+        CharsetMode::Unicode => renderer.unicode_blocks(options.style().full),
+        CharsetMode::Braille => renderer.braille(),
+        CharsetMode::Quadrant => renderer.blocks(BlockShape::Quadrant),
+        CharsetMode::Sextant => renderer.blocks(BlockShape::Sextant),
+        CharsetMode::Octant => renderer.blocks(BlockShape::Octant),
+        CharsetMode::Hybrid => renderer.hybrid(),
+        CharsetMode::Fade => renderer.fade(),
+        CharsetMode::Ascii => renderer.ascii(options.style().density),
+        CharsetMode::Edges => renderer.edges(),
+        #[cfg(feature = "rasterize")]
+        CharsetMode::Shape => renderer.shape(),
+        CharsetMode::Kanji => renderer.kanji(),
+        CharsetMode::Chinese => renderer.chinese(),
+        CharsetMode::Custom(custom) => {
+            let blank: &[&str] = if custom.is_wide() { &["  "] } else { &[" "] };
+            let glyphs = Some(custom.glyphs()).filter(|g| !g.is_empty());
+            renderer.charset_colored(glyphs.unwrap_or(blank), custom.is_wide())
+        }
+        #[cfg(not(feature = "rasterize"))]
+        CharsetMode::Shape => return None,
+        CharsetMode::Sixel | CharsetMode::Kitty | CharsetMode::Iterm2 => return None,
+    })
+}
+
+/// The encoder [`write_ansi_art`] uses for `options`.
+///
+/// Block glyphs are meaningless without colour, so the half- and full-block
+/// modes fall back to 24-bit escapes under [`ColorMode::None`].
+#[must_use]
+pub const fn ansi_encoder(options: &RenderOptions) -> AnsiEncoder {
+    let mode = match (options.charset(), options.color_mode()) {
+        (CharsetMode::Ansi | CharsetMode::Unicode, ColorMode::None) => ColorMode::TrueColor,
+        (_, mode) => mode,
+    };
+    AnsiEncoder::new(mode)
+}
+
 /// Renders a prepared image to `writer` using the mode specified in `options`.
 ///
-/// This is the primary entry point for the rendering engine. It handles the
-/// internal state management and dispatches the image data to the
-/// appropriate rendering strategy based on the chosen [`CharsetMode`].
+/// This is the primary entry point for the rendering engine. Text modes are
+/// rendered into a [`CellGrid`] with [`render_grid`] and written by
+/// [`ansi_encoder`]; the pixel protocols are written directly.
 ///
 /// # Errors
 ///
-/// Returns a [`std::io::Result`] error if the writer fails.
+/// Returns a [`std::io::Result`] error if the writer fails, or an
+/// [`io::ErrorKind::Unsupported`] error for [`CharsetMode::Shape`] without
+/// the `rasterize` feature.
 pub fn write_ansi_art<W: Write>(
     img: &DynamicImage,
     writer: &mut W,
     options: RenderOptions,
 ) -> io::Result<()> {
-    let mut renderer = Renderer::new(writer, img, options);
     match options.charset() {
-        CharsetMode::Ansi => renderer.ansi_blocks(),
-        CharsetMode::Unicode => renderer.unicode_blocks(options.style().full),
-        CharsetMode::Braille => renderer.braille(),
-        CharsetMode::Fade => renderer.fade(),
-        CharsetMode::Ascii => renderer.ascii(options.style().density),
-        CharsetMode::Kanji => renderer.kanji(),
-        CharsetMode::Chinese => renderer.chinese(),
+        #[cfg(not(feature = "rasterize"))]
+        CharsetMode::Shape => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Shape-matched ASCII requires the 'rasterize' feature",
+        )),
         #[cfg(feature = "sixel")]
         CharsetMode::Sixel => write_sixel(img, &options, writer),
         #[cfg(not(feature = "sixel"))]
@@ -285,6 +521,10 @@ pub fn write_ansi_art<W: Write>(
             eprintln!("Rebuild with: cargo build --features sixel");
             Ok(())
         }
+        CharsetMode::Kitty => write_kitty(img, &options, writer),
+        CharsetMode::Iterm2 => write_iterm2(img, &options, writer),
+        _ => render_grid(img, options)
+            .map_or(Ok(()), |grid| ansi_encoder(&options).encode(&grid, writer)),
     }
 }
 
@@ -421,3 +661,35 @@ pub fn write_sixel<W: Write>(
 //     out.write_all(sixel.as_bytes())?;
 //     out.flush()
 // }
+
+/// `(dx, dy)` of grid position `i` within a shape cell, row-major.
+#[cfg(feature = "rasterize")]
+const fn shape_offset(i: usize) -> (u32, u32) {
+    #[expect(clippy::cast_possible_truncation, reason = "i < SHAPE_PIXELS")]
+    let i = i as u32;
+    (i % SHAPE_CELL_W, i / SHAPE_CELL_W)
+}
+
+/// Coverage-weighted mean colour of the opaque pixels under a glyph's ink.
+#[cfg(feature = "rasterize")]
+fn ink_color(
+    px: &[Option<[u8; 3]>; SHAPE_PIXELS],
+    coverage: &[f32; SHAPE_PIXELS],
+) -> Option<[u8; 3]> {
+    let mut sum = [0.0_f32; 3];
+    let mut weight = 0.0_f32;
+    for (p, &w) in px.iter().zip(coverage) {
+        if let Some(c) = p {
+            for (acc, &v) in sum.iter_mut().zip(c) {
+                *acc += w * f32::from(v);
+            }
+            weight += w;
+        }
+    }
+    #[expect(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        reason = "weighted mean of u8 channels stays within 0..=255"
+    )]
+    (weight > 0.0).then(|| sum.map(|s| (s / weight).round().clamp(0.0, 255.0) as u8))
+}
@@ -0,0 +1,128 @@
+//! Linear-light resampling for [`ResizeSpace::Linear`] and
+//! [`ResizeSpace::LinearPremultiplied`].
+//!
+//! [`ResizeSpace::Linear`]: crate::ResizeSpace::Linear
+//! [`ResizeSpace::LinearPremultiplied`]: crate::ResizeSpace::LinearPremultiplied
+
+use image::{DynamicImage, Rgba32FImage, imageops, imageops::FilterType};
+
+use crate::color::{linear_to_srgb, srgb_to_linear, srgb_to_linear_fast};
+
+/// Resizes `img` to exactly `width` x `height` in linear light and returns
+/// the result as sRGB in `0.0..=1.0`.
+///
+/// With `premultiply`, colour is weighted by alpha while resampling, so
+/// fully transparent pixels contribute nothing to their neighbours.
+pub(super) fn resize_linear(
+    img: &DynamicImage,
+    width: u32,
+    height: u32,
+    filter: FilterType,
+    premultiply: bool,
+) -> Rgba32FImage {
+    let mut linear = to_linear(img);
+    if premultiply {
+        for px in linear.pixels_mut() {
+            let alpha = px[3];
+            px.0 = [px[0] * alpha, px[1] * alpha, px[2] * alpha, alpha];
+        }
+    }
+
+    let mut resized = imageops::resize(&linear, width, height, filter);
+    for px in resized.pixels_mut() {
+        let alpha = px[3].clamp(0.0, 1.0);
+        let scale = match (premultiply, alpha > 0.0) {
+            (false, _) => 1.0,
+            (true, true) => alpha.recip(),
+            (true, false) => 0.0,
+        };
+        let [r, g, b] = [px[0], px[1], px[2]].map(|c| linear_to_srgb(c * scale));
+        px.0 = [r, g, b, alpha];
+    }
+    resized
+}
+
+/// Decodes `img` to linear-light RGBA, through the 8-bit lookup table when
+/// the source has no more precision than that.
+fn to_linear(img: &DynamicImage) -> Rgba32FImage {
+    let color = img.color();
+    if color.bytes_per_pixel() > color.channel_count() {
+        let mut linear = img.to_rgba32f();
+        for px in linear.pixels_mut() {
+            let [r, g, b, a] = px.0;
+            px.0 = [srgb_to_linear(r), srgb_to_linear(g), srgb_to_linear(b), a];
+        }
+        return linear;
+    }
+    let rgba = img.to_rgba8();
+    Rgba32FImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+        let [r, g, b, a] = rgba.get_pixel(x, y).0;
+        image::Rgba([
+            srgb_to_linear_fast(r),
+            srgb_to_linear_fast(g),
+            srgb_to_linear_fast(b),
+            f32::from(a) / 255.0,
+        ])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgba, RgbaImage};
+
+    fn checkerboard(a: Rgba<u8>, b: Rgba<u8>) -> DynamicImage {
+        DynamicImage::ImageRgba8(RgbaImage::from_fn(8, 8, |x, y| {
+            if (x + y) % 2 == 0 { a } else { b }
+        }))
+    }
+
+    #[test]
+    fn black_and_white_average_to_linear_mid_grey() {
+        let img = checkerboard(Rgba([0, 0, 0, 255]), Rgba([255, 255, 255, 255]));
+        let linear = resize_linear(&img, 1, 1, FilterType::Triangle, false);
+        let srgb = img.resize_exact(1, 1, FilterType::Triangle).to_rgba8();
+        // Half the light of white is sRGB ~0.735, not the 0.5 that averaging
+        // gamma-encoded values gives.
+        assert!((linear.get_pixel(0, 0)[0] - 0.735).abs() < 0.01);
+        assert!(srgb.get_pixel(0, 0)[0].abs_diff(128) <= 1);
+    }
+
+    #[test]
+    fn premultiplied_alpha_keeps_edges_from_darkening() {
+        // Red next to transparent black: straight alpha pulls the colour
+        // towards black, premultiplied keeps it red.
+        let img = checkerboard(Rgba([255, 0, 0, 255]), Rgba([0, 0, 0, 0]));
+        let straight = resize_linear(&img, 1, 1, FilterType::Triangle, false);
+        let premultiplied = resize_linear(&img, 1, 1, FilterType::Triangle, true);
+        let [r, _, _, a] = premultiplied.get_pixel(0, 0).0;
+        assert!((r - 1.0).abs() < 0.01, "{r}");
+        assert!((a - 0.5).abs() < 0.01, "{a}");
+        assert!(straight.get_pixel(0, 0)[0] < 0.8);
+    }
+
+    #[test]
+    fn fully_transparent_output_stays_black() {
+        let img = checkerboard(Rgba([90, 10, 200, 0]), Rgba([0, 0, 0, 0]));
+        let resized = resize_linear(&img, 2, 2, FilterType::Lanczos3, true);
+        assert!(
+            resized
+                .pixels()
+                .flat_map(|p| p.0)
+                .all(|c| c.abs() < f32::EPSILON)
+        );
+    }
+
+    #[test]
+    fn sixteen_bit_sources_keep_their_precision() {
+        let img = DynamicImage::ImageRgba16(image::ImageBuffer::from_pixel(
+            2,
+            2,
+            Rgba([32768u16, 0, 65535, 65535]),
+        ));
+        let resized = resize_linear(&img, 1, 1, FilterType::Triangle, false);
+        let [r, g, b, a] = resized.get_pixel(0, 0).0;
+        assert!((r - 0.5).abs() < 0.001, "{r}");
+        assert!(g.abs() < 0.001 && (b - 1.0).abs() < 0.001 && (a - 1.0).abs() < 0.001);
+    }
+}
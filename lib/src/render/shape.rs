@@ -0,0 +1,219 @@
+//! Shape-matched ASCII for [`CharsetMode::Shape`].
+//!
+//! Every printable ASCII glyph of the embedded Iosevka font is rasterized once
+//! and reduced to a 4x8 grid of ink coverage. Each terminal cell covers the
+//! same 4x8 grid of source pixels, and the glyph whose coverage is closest to
+//! the cell's luminance pattern (least squares) is drawn. Edges and diagonals
+//! therefore come out as `/`, `\`, `|` or `_` instead of a density average.
+//!
+//! [`CharsetMode::Shape`]: super::CharsetMode::Shape
+
+use std::sync::OnceLock;
+
+use ab_glyph::{Font, FontRef, PxScale, ScaleFont, point};
+
+use super::utils::{SHAPE_CELL_H, SHAPE_CELL_W};
+use crate::rasterize::DEFAULT_FONT;
+
+/// Grid positions per cell, row-major, [`SHAPE_CELL_W`] per row.
+pub(super) const SHAPE_PIXELS: usize = (SHAPE_CELL_W * SHAPE_CELL_H) as usize;
+
+/// Font pixels rasterized per grid position along each axis.
+const SUPERSAMPLE: u32 = 4;
+
+/// Errors closer than this are ties, resolved in favour of the earlier glyph.
+const TIE_EPSILON: f32 = 1e-6;
+
+/// A candidate glyph and how much of each grid position its ink covers.
+#[derive(Debug)]
+pub(super) struct ShapeGlyph {
+    /// The character drawn for this candidate.
+    pub glyph: char,
+    /// Ink coverage per grid position, scaled so the densest glyph averages 1.
+    pub coverage: [f32; SHAPE_PIXELS],
+    /// Mean of `coverage`: the glyph's overall density.
+    mean: f32,
+    /// `coverage` minus `mean`: the glyph's shape.
+    detail: [f32; SHAPE_PIXELS],
+    /// Sum of squares of `detail`.
+    energy: f32,
+}
+
+impl ShapeGlyph {
+    fn new(glyph: char, coverage: [f32; SHAPE_PIXELS]) -> Self {
+        #[expect(clippy::cast_precision_loss, reason = "SHAPE_PIXELS is 32")]
+        let mean = coverage.iter().sum::<f32>() / SHAPE_PIXELS as f32;
+        let detail = coverage.map(|w| w - mean);
+        let energy = detail.iter().map(|d| d * d).sum();
+        Self {
+            glyph,
+            coverage,
+            mean,
+            detail,
+            energy,
+        }
+    }
+}
+
+static GLYPHS: OnceLock<Vec<ShapeGlyph>> = OnceLock::new();
+
+/// Returns the candidate table, building it on first use. The space is always
+/// the first entry.
+pub(super) fn glyphs() -> &'static [ShapeGlyph] {
+    GLYPHS.get_or_init(build_glyphs)
+}
+
+/// Picks the glyph that best reproduces `cell`, where each entry is the
+/// normalised luminance (`0.0` dark, `1.0` bright) of one grid position.
+///
+/// The error splits into density and shape: the squared difference of the
+/// means, plus the residual of the cell's detail against the glyph's detail
+/// scaled by `s`. `s` is the least-squares contrast clamped to `0..=1`, so a
+/// flat cell ignores shape and falls back to a density match, while a
+/// high-contrast edge is matched on shape at full strength.
+pub(super) fn fit(cell: &[f32; SHAPE_PIXELS]) -> &'static ShapeGlyph {
+    #[expect(clippy::cast_precision_loss, reason = "SHAPE_PIXELS is 32")]
+    let n = SHAPE_PIXELS as f32;
+    let mean = cell.iter().sum::<f32>() / n;
+
+    let mut best: Option<(&ShapeGlyph, f32)> = None;
+    for candidate in glyphs() {
+        let cov: f32 = cell
+            .iter()
+            .zip(&candidate.detail)
+            .map(|(l, d)| (l - mean) * d)
+            .sum();
+        let s = if candidate.energy > 0.0 {
+            (cov / candidate.energy).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let density = mean - candidate.mean;
+        // Σ(l - l̄ - s·d)² without the constant Σ(l - l̄)² term.
+        let err = (n * density).mul_add(density, s * s.mul_add(candidate.energy, -2.0 * cov));
+        if best.is_none_or(|(_, e)| err < e - TIE_EPSILON) {
+            best = Some((candidate, err));
+        }
+    }
+    best.map_or_else(|| &glyphs()[0], |(g, _)| g)
+}
+
+/// Rasterizes `' '..='~'` into coverage grids.
+///
+/// Glyphs are scaled so one advance spans the cell width and one line spans
+/// the cell height, as in a terminal, and share a baseline so `_` stays low
+/// and `'` stays high. Coverage is stretched so the most heavily inked grid position
+/// of any glyph reaches `1.0`; thin strokes would otherwise cap the brightest
+/// output well below white.
+#[expect(
+    clippy::cast_precision_loss,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    reason = "Small, non-negative pixel coordinates inside a 16x32 raster"
+)]
+fn build_glyphs() -> Vec<ShapeGlyph> {
+    let mut out = vec![ShapeGlyph::new(' ', [0.0; SHAPE_PIXELS])];
+    let Ok(font) = FontRef::try_from_slice(DEFAULT_FONT) else {
+        return out;
+    };
+
+    let (raster_w, raster_h) = (SHAPE_CELL_W * SUPERSAMPLE, SHAPE_CELL_H * SUPERSAMPLE);
+    let unit = font.as_scaled(PxScale::from(1.0));
+    let advance = unit.h_advance(font.glyph_id('M')).max(f32::EPSILON);
+    let line = (unit.ascent() - unit.descent()).max(f32::EPSILON);
+    let scale = PxScale {
+        x: raster_w as f32 / advance,
+        y: raster_h as f32 / line,
+    };
+    let baseline = font.as_scaled(scale).ascent();
+    let sample_area = (SUPERSAMPLE * SUPERSAMPLE) as f32;
+
+    for ch in '!'..='~' {
+        let glyph = font
+            .glyph_id(ch)
+            .with_scale_and_position(scale, point(0.0, baseline));
+        let Some(outlined) = font.outline_glyph(glyph) else {
+            continue;
+        };
+        let min = outlined.px_bounds().min;
+        let mut coverage = [0.0; SHAPE_PIXELS];
+        outlined.draw(|gx, gy, c| {
+            let x = min.x + gx as f32;
+            let y = min.y + gy as f32;
+            if x < 0.0 || y < 0.0 {
+                return;
+            }
+            let (gx, gy) = (x as u32 / SUPERSAMPLE, y as u32 / SUPERSAMPLE);
+            if gx < SHAPE_CELL_W && gy < SHAPE_CELL_H {
+                coverage[(gy * SHAPE_CELL_W + gx) as usize] += c / sample_area;
+            }
+        });
+        out.push(ShapeGlyph::new(ch, coverage));
+    }
+
+    let densest = out.iter().map(|g| g.mean).fold(0.0_f32, f32::max);
+    if densest > 0.0 {
+        out = out
+            .into_iter()
+            .map(|g| ShapeGlyph::new(g.glyph, g.coverage.map(|w| w / densest)))
+            .collect();
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A cell that is bright where `lit(col, row)` holds and dark elsewhere.
+    fn pattern(lit: impl Fn(u32, u32) -> bool) -> [f32; SHAPE_PIXELS] {
+        let mut cell = [0.0; SHAPE_PIXELS];
+        for row in 0..SHAPE_CELL_H {
+            for col in 0..SHAPE_CELL_W {
+                if lit(col, row) {
+                    cell[(row * SHAPE_CELL_W + col) as usize] = 1.0;
+                }
+            }
+        }
+        cell
+    }
+
+    #[test]
+    fn table_covers_printable_ascii() {
+        let table = glyphs();
+        assert_eq!(table[0].glyph, ' ');
+        assert_eq!(table.len(), 95);
+        assert!(
+            table
+                .iter()
+                .skip(1)
+                .all(|g| g.coverage.iter().any(|&w| w > 0.0))
+        );
+    }
+
+    #[test]
+    fn dark_cell_is_a_space() {
+        assert_eq!(fit(&[0.0; SHAPE_PIXELS]).glyph, ' ');
+    }
+
+    #[test]
+    fn vertical_line_is_a_bar() {
+        let g = fit(&pattern(|col, row| (col == 1 || col == 2) && row > 0)).glyph;
+        assert!(matches!(g, '|' | 'l' | '!' | 'I' | '1'), "{g:?}");
+    }
+
+    #[test]
+    fn low_row_is_an_underscore() {
+        // The underscore sits on the baseline, one row above the descender.
+        assert_eq!(fit(&pattern(|_, row| row == SHAPE_CELL_H - 2)).glyph, '_');
+    }
+
+    #[test]
+    fn diagonals_keep_their_direction() {
+        // Column grows as the row goes up: bottom-left to top-right.
+        let rising = fit(&pattern(|col, row| col == (SHAPE_CELL_H - 1 - row) / 2)).glyph;
+        let falling = fit(&pattern(|col, row| col == row / 2)).glyph;
+        assert_eq!(rising, '/');
+        assert_eq!(falling, '\\');
+    }
+}
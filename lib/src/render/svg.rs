@@ -0,0 +1,666 @@
+//! Serialisation of rendered art as SVG.
+//!
+//! [`SvgEncoder`] lays cells out on the same 8×14 pixel grid as
+//! [`rasterize_ansi`](crate::rasterize_ansi), but as vectors, so the art
+//! scales cleanly in docs and slides. Block glyphs (half and full blocks,
+//! quadrants, sextants and octants) become exact `<rect>`s; every other glyph
+//! becomes `<text>` placed with the metrics of the bundled Iosevka font.
+
+use std::fmt::Write as _;
+use std::io::{self, Cursor, Write};
+
+use ab_glyph::{Font, FontRef};
+use image::{DynamicImage, ImageFormat, RgbaImage};
+
+use super::base64;
+use super::blocks::block_mask;
+use super::color::ColorState;
+use super::encode::Encoder;
+use super::grid::{Attributes, Cell, CellGrid};
+use super::html::{Hex, escape};
+use super::options::RenderOptions;
+use super::renderer::render_grid;
+use super::types::{CharsetMode, ColorMode};
+use super::utils::{CELL_PX_H, CELL_PX_W, composite_bg};
+use crate::rasterize::{self, DEFAULT_FONT};
+use crate::themes::RasterTheme;
+
+/// User units per pixel, so sextant and octant rows land on whole units.
+const SCALE: u32 = 6;
+
+/// Cell width in user units.
+const CELL_W: u32 = rasterize::CELL_W * SCALE;
+
+/// Cell height in user units.
+const CELL_H: u32 = rasterize::CELL_H * SCALE;
+
+/// Family name of the bundled font, as embedded by
+/// [`SvgEncoder::with_embedded_font`].
+const FONT_FAMILY: &str = "Iosevka Charon Mono";
+
+/// Encodes a grid as an SVG image.
+///
+/// Cells without a background of their own show the encoder's background,
+/// and glyphs without a foreground its foreground; the defaults are the
+/// Tokyo Night background and white, as in
+/// [`rasterize_ansi`](crate::rasterize_ansi). Bold, italic, underline and
+/// strikethrough style text glyphs, dim halves their opacity, and reverse
+/// swaps the colours.
+///
+/// ```
+/// use px2ansi::{Cell, CellGrid, ColorMode, Encoder, SvgEncoder};
+///
+/// let grid = CellGrid::from(vec![vec![
+///     Cell::new("▀", 1).with_fg(Some([255, 0, 0])).with_bg(Some([0, 0, 255])),
+/// ]]);
+/// let mut out = Vec::new();
+/// SvgEncoder::new(ColorMode::TrueColor).encode(&grid, &mut out)?;
+/// let svg = String::from_utf8(out).unwrap();
+/// assert!(svg.contains(r##"<rect x="0" y="0" width="48" height="42" fill="#ff0000"/>"##));
+/// assert!(svg.contains(r##"<rect x="0" y="42" width="48" height="42" fill="#0000ff"/>"##));
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SvgEncoder {
+    mode: ColorMode,
+    merge: bool,
+    embed_font: bool,
+    background: Option<[u8; 3]>,
+    foreground: [u8; 3],
+}
+
+impl SvgEncoder {
+    /// An encoder quantizing colours for `mode`. [`ColorMode::None`] draws
+    /// every glyph in the foreground colour.
+    #[must_use]
+    pub const fn new(mode: ColorMode) -> Self {
+        let [r, g, b, _] = RasterTheme::TokyoNight.color().0;
+        Self {
+            mode,
+            merge: false,
+            embed_font: false,
+            background: Some([r, g, b]),
+            foreground: [255, 255, 255],
+        }
+    }
+
+    /// Returns the colour mode.
+    #[must_use]
+    pub const fn color_mode(&self) -> ColorMode {
+        self.mode
+    }
+
+    /// Merges adjacent rectangles of the same colour into one `<path>` per
+    /// colour, for a much smaller file.
+    #[must_use]
+    pub const fn with_merge(mut self, merge: bool) -> Self {
+        self.merge = merge;
+        self
+    }
+
+    /// Embeds the bundled Iosevka font, about 2 MB of base64, so text
+    /// glyphs look the same in any viewer.
+    #[must_use]
+    pub const fn with_embedded_font(mut self, embed: bool) -> Self {
+        self.embed_font = embed;
+        self
+    }
+
+    /// Sets the colour behind cells without a background of their own, or
+    /// `None` for a transparent image.
+    #[must_use]
+    pub const fn with_background(mut self, color: Option<[u8; 3]>) -> Self {
+        self.background = color;
+        self
+    }
+
+    /// Sets the colour of glyphs without a foreground of their own.
+    #[must_use]
+    pub const fn with_foreground(mut self, color: [u8; 3]) -> Self {
+        self.foreground = color;
+        self
+    }
+
+    /// Uses `theme`'s background.
+    #[must_use]
+    pub const fn with_theme(self, theme: RasterTheme) -> Self {
+        let [r, g, b, _] = theme.color().0;
+        self.with_background(Some([r, g, b]))
+    }
+
+    /// Writes `rgba` as an SVG holding the PNG, sized to the cell box the
+    /// terminal image protocols would give it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if PNG encoding or writing to `writer` fails.
+    pub fn encode_image(&self, rgba: &RgbaImage, writer: &mut dyn Write) -> io::Result<()> {
+        let mut png = Vec::new();
+        rgba.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .map_err(|e| io::Error::other(e.to_string()))?;
+        let cols = rgba.width().div_ceil(CELL_PX_W).max(1);
+        let rows = rgba.height().div_ceil(CELL_PX_H).max(1);
+        let body = format!(
+            "<image width=\"{}\" height=\"{}\" href=\"data:image/png;base64,{}\"/>\n",
+            cols * CELL_W,
+            rows * CELL_H,
+            base64::encode(&png)
+        );
+        writer.write_all(self.document(cols, rows, None, &body).as_bytes())
+    }
+
+    /// The colour a terminal shows for `color` in this encoder's mode.
+    fn rgb(&self, color: Option<[u8; 3]>) -> Option<[u8; 3]> {
+        color.and_then(|c| ColorState::from_rgb(c, self.mode).to_rgb(self.mode))
+    }
+
+    /// The ink and paper colours `cell` is drawn with; `None` paper leaves
+    /// the image background showing.
+    fn colors(&self, cell: &Cell) -> ([u8; 3], Option<[u8; 3]>) {
+        let fg = self.rgb(cell.fg);
+        let bg = self.rgb(cell.bg);
+        if cell.attributes.contains(Attributes::REVERSE) {
+            (
+                bg.or(self.background).unwrap_or([0, 0, 0]),
+                Some(fg.unwrap_or(self.foreground)),
+            )
+        } else {
+            (fg.unwrap_or(self.foreground), bg)
+        }
+    }
+
+    /// Adds the rectangles of one row to `rects` and its text to `text`.
+    fn encode_row(
+        &self,
+        row: &[Cell],
+        y: u32,
+        run: &mut TextRun,
+        rects: &mut Vec<Rect>,
+        text: &mut String,
+    ) {
+        let mut col = 0;
+        for cell in row {
+            let width = u32::from(cell.width.max(1));
+            let x = col * CELL_W;
+            let (fg, bg) = self.colors(cell);
+            let chars: Vec<char> = cell.glyph.chars().collect();
+            let masks: Option<Vec<_>> = chars.iter().map(|&c| block_mask(c)).collect();
+            match masks {
+                Some(masks) if !masks.is_empty() => {
+                    let slot = width * CELL_W / u32::try_from(masks.len()).unwrap_or(1);
+                    for (i, (shape, mask)) in (0..).zip(masks) {
+                        let cell_rect = Rect::new(x + i * slot, y, slot, CELL_H, [0; 3]);
+                        block_rects(cell_rect, shape.rows(), mask, fg, bg, rects);
+                    }
+                }
+                _ => {
+                    if let Some(bg) = bg {
+                        rects.push(Rect::new(x, y, width * CELL_W, CELL_H, bg));
+                    }
+                    // Text carries its own positions, so blank cells and
+                    // blocks need not break a run.
+                    if !cell.is_blank() {
+                        run.push(cell, fg, x, y, text);
+                    }
+                }
+            }
+            col += width;
+        }
+        run.flush(text);
+    }
+
+    /// Wraps `body` in the `<svg>` element for a `cols` × `rows` grid, with
+    /// the text style for `metrics` when there is text.
+    fn document(&self, cols: u32, rows: u32, metrics: Option<Metrics>, body: &str) -> String {
+        let (width, height) = (cols * CELL_W, rows * CELL_H);
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" \
+             viewBox=\"0 0 {width} {height}\">\n",
+            cols * rasterize::CELL_W,
+            rows * rasterize::CELL_H,
+        );
+        if let Some(metrics) = metrics {
+            svg.push_str("<style>\n");
+            if self.embed_font {
+                let _ = writeln!(
+                    svg,
+                    "@font-face{{font-family:'{FONT_FAMILY}';\
+                     src:url(data:font/ttf;base64,{})}}",
+                    base64::encode(DEFAULT_FONT)
+                );
+            }
+            let _ = writeln!(
+                svg,
+                "text{{font-family:'{FONT_FAMILY}',Iosevka,ui-monospace,monospace;\
+                 font-size:{:.2}px;text-anchor:middle;white-space:pre}}",
+                metrics.font_size
+            );
+            svg.push_str("</style>\n");
+        }
+        if let Some(bg) = self.background {
+            let _ = writeln!(
+                svg,
+                "<rect width=\"100%\" height=\"100%\" fill=\"{}\"/>",
+                Hex(bg)
+            );
+        }
+        svg.push_str(body);
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+impl Default for SvgEncoder {
+    fn default() -> Self {
+        Self::new(ColorMode::TrueColor)
+    }
+}
+
+impl Encoder for SvgEncoder {
+    fn encode(&self, grid: &CellGrid, writer: &mut dyn Write) -> io::Result<()> {
+        let metrics = Metrics::iosevka()?;
+        let mut run = TextRun::new(metrics.baseline);
+        let mut rects = Vec::new();
+        let mut text = String::new();
+        for (y, row) in (0..).zip(grid.rows()) {
+            self.encode_row(row, y * CELL_H, &mut run, &mut rects, &mut text);
+        }
+
+        let mut body = String::new();
+        if !rects.is_empty() {
+            body.push_str("<g shape-rendering=\"crispEdges\">\n");
+            if self.merge {
+                for (color, d) in merge(rects) {
+                    let _ = writeln!(body, "<path fill=\"{}\" d=\"{d}\"/>", Hex(color));
+                }
+            } else {
+                for r in rects {
+                    let _ = writeln!(
+                        body,
+                        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>",
+                        r.x,
+                        r.y,
+                        r.width,
+                        r.height,
+                        Hex(r.color)
+                    );
+                }
+            }
+            body.push_str("</g>\n");
+        }
+        body.push_str(&text);
+
+        let cols = u32::try_from(grid.width()).unwrap_or(u32::MAX);
+        let rows = u32::try_from(grid.height()).unwrap_or(u32::MAX);
+        let metrics = Some(metrics).filter(|_| !text.is_empty());
+        writer.write_all(self.document(cols, rows, metrics, &body).as_bytes())
+    }
+}
+
+/// Font size and baseline, in user units, that fit the bundled font's
+/// ascent and descent exactly into one cell, as the rasterizer does.
+#[derive(Clone, Copy, Debug)]
+struct Metrics {
+    font_size: f32,
+    baseline: f64,
+}
+
+impl Metrics {
+    fn iosevka() -> io::Result<Self> {
+        let font = FontRef::try_from_slice(DEFAULT_FONT).map_err(io::Error::other)?;
+        let ascent = font.ascent_unscaled();
+        let height = ascent - font.descent_unscaled();
+        let units_per_em = font.units_per_em().unwrap_or(height);
+        #[expect(
+            clippy::cast_precision_loss,
+            reason = "The cell height is a small constant"
+        )]
+        let cell_h = CELL_H as f32;
+        Ok(Self {
+            font_size: cell_h * units_per_em / height,
+            baseline: f64::from(cell_h * ascent / height),
+        })
+    }
+}
+
+/// A filled rectangle in user units.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Rect {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    color: [u8; 3],
+}
+
+impl Rect {
+    const fn new(x: u32, y: u32, width: u32, height: u32, color: [u8; 3]) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+            color,
+        }
+    }
+}
+
+/// Adds the rectangles of a block glyph with `rows` rows of two sub-pixels
+/// to `rects`: set bits of `mask` in `fg`, clear bits in `bg` if there is
+/// one. Rows whose halves match become one rectangle, so nothing overlaps.
+fn block_rects(
+    cell: Rect,
+    rows: u32,
+    mask: u8,
+    fg: [u8; 3],
+    bg: Option<[u8; 3]>,
+    rects: &mut Vec<Rect>,
+) {
+    if mask == 0 || u32::from(mask) == (1 << (rows * 2)) - 1 {
+        let color = if mask == 0 { bg } else { Some(fg) };
+        rects.extend(color.map(|c| Rect { color: c, ..cell }));
+        return;
+    }
+    let sub_h = cell.height / rows;
+    let half = cell.width / 2;
+    for row in 0..rows {
+        let y = cell.y + row * sub_h;
+        let color = |bit: u32| {
+            if mask & (1 << (row * 2 + bit)) == 0 {
+                bg
+            } else {
+                Some(fg)
+            }
+        };
+        match (color(0), color(1)) {
+            (left, right) if left == right => {
+                rects.extend(left.map(|c| Rect::new(cell.x, y, cell.width, sub_h, c)));
+            }
+            (left, right) => {
+                rects.extend(left.map(|c| Rect::new(cell.x, y, half, sub_h, c)));
+                rects.extend(
+                    right.map(|c| Rect::new(cell.x + half, y, cell.width - half, sub_h, c)),
+                );
+            }
+        }
+    }
+}
+
+/// Groups `rects` by colour, joins neighbours into larger rectangles and
+/// returns one path per colour, in order of first appearance. The
+/// rectangles never overlap, so the order of the paths doesn't matter.
+fn merge(rects: Vec<Rect>) -> Vec<([u8; 3], String)> {
+    let mut groups: Vec<([u8; 3], Vec<Rect>)> = Vec::new();
+    for rect in rects {
+        match groups.iter_mut().find(|(c, _)| *c == rect.color) {
+            Some((_, group)) => group.push(rect),
+            None => groups.push((rect.color, vec![rect])),
+        }
+    }
+    groups
+        .into_iter()
+        .map(|(color, mut group)| {
+            // Join along rows, then stack rows with the same span.
+            group.sort_by_key(|r| (r.y, r.height, r.x));
+            let group = join(group, |a, b| {
+                a.y == b.y && a.height == b.height && a.x + a.width == b.x
+            });
+            let mut group = group;
+            group.sort_by_key(|r| (r.x, r.width, r.y));
+            let group = join(group, |a, b| {
+                a.x == b.x && a.width == b.width && a.y + a.height == b.y
+            });
+            let mut d = String::new();
+            for r in group {
+                let _ = write!(d, "M{} {}h{}v{}h-{}z", r.x, r.y, r.width, r.height, r.width);
+            }
+            (color, d)
+        })
+        .collect()
+}
+
+/// Folds each rectangle into the one before it when `touches` says they
+/// share an edge; `sorted` must list such neighbours consecutively.
+fn join(sorted: Vec<Rect>, touches: impl Fn(&Rect, &Rect) -> bool) -> Vec<Rect> {
+    let mut out: Vec<Rect> = Vec::with_capacity(sorted.len());
+    for rect in sorted {
+        match out.last_mut() {
+            Some(last) if touches(last, &rect) => {
+                last.width = rect.x + rect.width - last.x;
+                last.height = rect.y + rect.height - last.y;
+            }
+            _ => out.push(rect),
+        }
+    }
+    out
+}
+
+/// Consecutive text glyphs of one row drawn with the same colour and
+/// attributes, written as one `<text>` with a position per character.
+struct TextRun {
+    baseline: f64,
+    style: Option<([u8; 3], Attributes)>,
+    xs: Vec<String>,
+    y: u32,
+    text: String,
+}
+
+impl TextRun {
+    /// An empty run for text whose baseline sits `baseline` below the top
+    /// of its row.
+    const fn new(baseline: f64) -> Self {
+        Self {
+            baseline,
+            style: None,
+            xs: Vec::new(),
+            y: 0,
+            text: String::new(),
+        }
+    }
+
+    /// Adds `cell`, drawn in `fg` at `x`, `y`, starting a new run when its
+    /// style differs.
+    fn push(&mut self, cell: &Cell, fg: [u8; 3], x: u32, y: u32, out: &mut String) {
+        let style = Some((fg, cell.attributes));
+        let width = u32::from(cell.width.max(1));
+        // One character per column is centred on its column; anything else,
+        // like a combining sequence, is centred on the whole cell as a unit.
+        let chars = u32::try_from(cell.glyph.chars().count()).unwrap_or(u32::MAX);
+        let per_column = chars == width;
+        if self.style != style || !per_column {
+            self.flush(out);
+            self.style = style;
+            self.y = y;
+        }
+        if per_column {
+            self.xs
+                .extend((0..chars).map(|i| (x + i * CELL_W + CELL_W / 2).to_string()));
+        } else {
+            self.xs.push((x + width * CELL_W / 2).to_string());
+        }
+        escape(&cell.glyph, &mut self.text);
+        if !per_column {
+            self.flush(out);
+        }
+    }
+
+    /// Writes the run, if any, to `out` and empties it.
+    fn flush(&mut self, out: &mut String) {
+        let Some((fg, attributes)) = self.style.take() else {
+            return;
+        };
+        let _ = write!(
+            out,
+            "<text x=\"{}\" y=\"{:.2}\" fill=\"{}\"",
+            self.xs.join(" "),
+            f64::from(self.y) + self.baseline,
+            Hex(fg)
+        );
+        if attributes.contains(Attributes::BOLD) {
+            out.push_str(" font-weight=\"bold\"");
+        }
+        if attributes.contains(Attributes::ITALIC) {
+            out.push_str(" font-style=\"italic\"");
+        }
+        if attributes.contains(Attributes::DIM) {
+            out.push_str(" opacity=\"0.5\"");
+        }
+        let decorations: Vec<_> = [
+            (Attributes::UNDERLINE, "underline"),
+            (Attributes::STRIKETHROUGH, "line-through"),
+        ]
+        .into_iter()
+        .filter(|&(attr, _)| attributes.contains(attr))
+        .map(|(_, line)| line)
+        .collect();
+        if !decorations.is_empty() {
+            let _ = write!(out, " text-decoration=\"{}\"", decorations.join(" "));
+        }
+        let _ = writeln!(out, ">{}</text>", self.text);
+        self.xs.clear();
+        self.text.clear();
+    }
+}
+
+/// Renders a prepared image to `writer` as SVG using the mode specified in
+/// `options`.
+///
+/// Text modes are rendered into a [`CellGrid`] with [`render_grid`] and
+/// written by `encoder`. The pixel protocols ([`CharsetMode::Sixel`],
+/// [`CharsetMode::Kitty`] and [`CharsetMode::Iterm2`]) have no cells, so the
+/// image is embedded as a PNG instead, composited against
+/// [`RenderOptions::bg_color`] when one is set.
+///
+/// # Errors
+///
+/// Returns an error if the bundled font fails to load or writing to `writer`
+/// fails.
+pub fn write_svg<W: Write>(
+    img: &DynamicImage,
+    writer: &mut W,
+    options: RenderOptions,
+    encoder: &SvgEncoder,
+) -> io::Result<()> {
+    if matches!(
+        options.charset(),
+        CharsetMode::Sixel | CharsetMode::Kitty | CharsetMode::Iterm2
+    ) {
+        return encoder.encode_image(&composite_bg(img, &options), writer);
+    }
+    // Shape needs `rasterize`, which this module is built with.
+    render_grid(img, options).map_or(Ok(()), |grid| encoder.encode(&grid, writer))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(encoder: SvgEncoder, rows: Vec<Vec<Cell>>) -> String {
+        let mut out = Vec::new();
+        encoder
+            .encode(&CellGrid::from(rows), &mut out)
+            .expect("write");
+        String::from_utf8(out).expect("utf-8")
+    }
+
+    fn rects(svg: &str) -> Vec<&str> {
+        svg.lines().filter(|l| l.starts_with("<rect x=")).collect()
+    }
+
+    const RED: [u8; 3] = [255, 0, 0];
+    const BLUE: [u8; 3] = [0, 0, 255];
+
+    #[test]
+    fn sub_cell_blocks_fill_exact_rectangles_without_overlap() {
+        // Octant 0x1CD00 is mask 0x04: the left sub-pixel of the second row.
+        let cell = Cell::new("\u{1CD00}", 1)
+            .with_fg(Some(RED))
+            .with_bg(Some(BLUE));
+        let svg = encode(SvgEncoder::default(), vec![vec![cell]]);
+        assert_eq!(
+            rects(&svg),
+            [
+                r##"<rect x="0" y="0" width="48" height="21" fill="#0000ff"/>"##,
+                r##"<rect x="0" y="21" width="24" height="21" fill="#ff0000"/>"##,
+                r##"<rect x="24" y="21" width="24" height="21" fill="#0000ff"/>"##,
+                r##"<rect x="0" y="42" width="48" height="21" fill="#0000ff"/>"##,
+                r##"<rect x="0" y="63" width="48" height="21" fill="#0000ff"/>"##,
+            ]
+        );
+        assert!(!svg.contains("<text"), "blocks need no font");
+    }
+
+    #[test]
+    fn full_blocks_cover_their_columns() {
+        let row = vec![Cell::new("██", 2).with_fg(Some(RED)), Cell::blank(1)];
+        let svg = encode(SvgEncoder::default(), vec![row]);
+        assert_eq!(
+            rects(&svg),
+            [
+                r##"<rect x="0" y="0" width="48" height="84" fill="#ff0000"/>"##,
+                r##"<rect x="48" y="0" width="48" height="84" fill="#ff0000"/>"##,
+            ]
+        );
+        assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="24" height="14" viewBox="0 0 144 84">"#));
+    }
+
+    #[test]
+    fn merge_joins_equal_colours_into_one_path() {
+        let half = Cell::new("▀", 1).with_fg(Some(RED)).with_bg(Some(BLUE));
+        let svg = encode(
+            SvgEncoder::default().with_merge(true),
+            vec![vec![half.clone(), half.clone()], vec![half.clone(), half]],
+        );
+        assert!(rects(&svg).is_empty());
+        assert!(svg.contains(r##"<path fill="#ff0000" d="M0 0h96v42h-96zM0 84h96v42h-96z"/>"##));
+        assert!(svg.contains(r##"<path fill="#0000ff" d="M0 42h96v42h-96zM0 126h96v42h-96z"/>"##));
+    }
+
+    #[test]
+    fn text_glyphs_are_centred_on_their_columns() {
+        let row = vec![
+            Cell::new("<", 1).with_fg(Some(RED)),
+            Cell::blank(1),
+            Cell::new("a", 1).with_fg(Some(RED)),
+            Cell::new("龍", 2).with_attributes(Attributes::BOLD),
+        ];
+        let svg = encode(SvgEncoder::default().with_background(None), vec![row]);
+        let metrics = Metrics::iosevka().expect("font");
+        let y = format!("{:.2}", metrics.baseline);
+        assert!(svg.contains(&format!(
+            r##"<text x="24 120" y="{y}" fill="#ff0000">&lt;a</text>"##
+        )));
+        assert!(svg.contains(&format!(
+            r##"<text x="192" y="{y}" fill="#ffffff" font-weight="bold">龍</text>"##
+        )));
+        assert!(!svg.contains("100%"), "no background rect");
+        // The font's ascent and descent fill the cell exactly.
+        assert!(metrics.baseline > 0.0 && metrics.baseline < f64::from(CELL_H));
+    }
+
+    #[test]
+    fn colours_follow_the_mode_and_reverse_swaps_them() {
+        let cell = Cell::new("x", 1)
+            .with_fg(Some([20, 30, 230]))
+            .with_attributes(Attributes::REVERSE);
+        let svg = encode(
+            SvgEncoder::new(ColorMode::Ansi16(crate::Ansi16Palette::XTERM)),
+            vec![vec![cell]],
+        );
+        assert!(svg.contains(r##"<rect x="0" y="0" width="48" height="84" fill="#0000ff"/>"##));
+        assert!(svg.contains(r##"fill="#1a1b26">x</text>"##));
+    }
+
+    #[test]
+    fn pixel_protocols_fill_whole_cells() {
+        // 20x40 pixels round up to a 3x3 cell box, with no cell geometry.
+        let img = DynamicImage::ImageRgba8(RgbaImage::new(20, 40));
+        let options = RenderOptions::default().with_charset(CharsetMode::Sixel);
+        let mut out = Vec::new();
+        write_svg(&img, &mut out, options, &SvgEncoder::default()).expect("write");
+        let svg = String::from_utf8(out).expect("utf-8");
+        assert!(svg.contains(r#"width="24" height="42" viewBox="0 0 144 252""#));
+        assert!(svg.contains(r#"<image width="144" height="252" href="data:image/png;base64,"#));
+        assert!(!svg.contains("<g ") && !svg.contains("<text"));
+    }
+}
@@ -0,0 +1,236 @@
+//! Tone mapping of luma onto glyph ramps for [`ToneMap`].
+//!
+//! Every strategy except [`ToneMap::Linear`] produces a level in `0..=255`
+//! for each pixel, which the charset renderers use in place of the linear
+//! stretch between the darkest and brightest pixel.
+
+use image::RgbaImage;
+
+use super::pixel::ALPHA_THRESHOLD;
+use super::types::ToneMap;
+
+/// Tiles along each axis for [`ToneMap::Clahe`].
+const CLAHE_TILES: u32 = 8;
+
+/// Histogram bins may hold at most this multiple of the mean bin count
+/// before CLAHE clips them, which caps how steep a tile's mapping can get.
+const CLAHE_CLIP: u32 = 4;
+
+/// Tone-mapped levels of every pixel of `rgba` in row-major order, or `None`
+/// for [`ToneMap::Linear`]. Transparent pixels get level 0.
+pub(super) fn levels(rgba: &RgbaImage, tone_map: ToneMap) -> Option<Vec<u8>> {
+    match tone_map {
+        ToneMap::Linear => None,
+        ToneMap::Percentile(percent) => {
+            let luma = luma_plane(rgba);
+            Some(map_luma(&luma, &percentile_lut(&histogram(&luma), percent)))
+        }
+        ToneMap::Equalize => {
+            let luma = luma_plane(rgba);
+            Some(map_luma(&luma, &equalize_lut(&histogram(&luma), u32::MAX)))
+        }
+        ToneMap::Clahe => Some(clahe(&luma_plane(rgba), rgba.width(), rgba.height())),
+    }
+}
+
+/// The luma of every visible pixel, as an index into a 256-bin histogram.
+fn luma_plane(rgba: &RgbaImage) -> Vec<Option<usize>> {
+    rgba.pixels()
+        .map(|p| {
+            (p[3] >= ALPHA_THRESHOLD)
+                .then(|| crate::simd::luma_scalar(p[0], p[1], p[2]).min(255) as usize)
+        })
+        .collect()
+}
+
+fn histogram(luma: &[Option<usize>]) -> [u32; 256] {
+    let mut hist = [0; 256];
+    for &l in luma.iter().flatten() {
+        hist[l] += 1;
+    }
+    hist
+}
+
+fn map_luma(luma: &[Option<usize>], lut: &[u8; 256]) -> Vec<u8> {
+    luma.iter().map(|l| l.map_or(0, |l| lut[l])).collect()
+}
+
+/// Stretches luma linearly between the `percent` and `100 - percent`
+/// percentiles of `hist`.
+fn percentile_lut(hist: &[u32; 256], percent: u8) -> [u8; 256] {
+    let total: u32 = hist.iter().sum();
+    let cut = u64::from(total) * u64::from(percent.min(49)) / 100;
+    let (mut lo, mut hi) = (0, 255);
+    let mut seen = 0;
+    for (l, &n) in hist.iter().enumerate() {
+        seen += u64::from(n);
+        if seen > cut {
+            lo = l;
+            break;
+        }
+    }
+    seen = 0;
+    for (l, &n) in hist.iter().enumerate().rev() {
+        seen += u64::from(n);
+        if seen > cut {
+            hi = l;
+            break;
+        }
+    }
+    let range = hi.saturating_sub(lo).max(1);
+    std::array::from_fn(|l| {
+        let level = l.clamp(lo, hi.max(lo)) - lo;
+        u8::try_from(level * 255 / range).unwrap_or(u8::MAX)
+    })
+}
+
+/// Maps luma through the cumulative distribution of `hist`, first clipping
+/// every bin to `clip` pixels and spreading the excess over all bins.
+fn equalize_lut(hist: &[u32; 256], clip: u32) -> [u8; 256] {
+    let mut hist = *hist;
+    let excess: u32 = hist
+        .iter_mut()
+        .map(|n| {
+            let over = n.saturating_sub(clip);
+            *n -= over;
+            over
+        })
+        .sum();
+    let (share, rest) = (excess / 256, excess % 256);
+    for (i, n) in (0u32..).zip(hist.iter_mut()) {
+        *n += share + u32::from(i < rest);
+    }
+
+    let total: u64 = hist.iter().map(|&n| u64::from(n)).sum();
+    let first = hist.iter().find(|&&n| n > 0).map_or(0, |&n| u64::from(n));
+    let mut cdf = 0;
+    std::array::from_fn(|l| {
+        cdf += u64::from(hist[l]);
+        let level = cdf.saturating_sub(first) * 255 / total.saturating_sub(first).max(1);
+        u8::try_from(level).unwrap_or(u8::MAX)
+    })
+}
+
+/// Equalises each tile of a `CLAHE_TILES` grid with a clipped histogram and
+/// blends the four nearest tiles' mappings bilinearly at every pixel.
+#[expect(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    clippy::cast_precision_loss,
+    reason = "Tile coordinates are small and the blend stays within 0.0..=255.0"
+)]
+fn clahe(luma: &[Option<usize>], width: u32, height: u32) -> Vec<u8> {
+    let tiles_x = CLAHE_TILES.min(width).max(1);
+    let tiles_y = CLAHE_TILES.min(height).max(1);
+    let tile_w = width.div_ceil(tiles_x);
+    let tile_h = height.div_ceil(tiles_y);
+    let tile_of = |x: u32, y: u32| ((y / tile_h).min(tiles_y - 1), (x / tile_w).min(tiles_x - 1));
+
+    let mut hists = vec![[0u32; 256]; (tiles_x * tiles_y) as usize];
+    for (i, l) in luma.iter().enumerate() {
+        if let Some(l) = l {
+            let i = i as u32;
+            let (ty, tx) = tile_of(i % width, i / width);
+            hists[(ty * tiles_x + tx) as usize][*l] += 1;
+        }
+    }
+    let luts: Vec<[u8; 256]> = hists
+        .iter()
+        .map(|h| {
+            let pixels: u32 = h.iter().sum();
+            equalize_lut(h, (pixels * CLAHE_CLIP / 256).max(1))
+        })
+        .collect();
+
+    // Tile centres sit at (t + 0.5) * tile size; outside them the nearest
+    // tile's mapping is used unblended.
+    let axis = |p: u32, size: u32, tiles: u32| {
+        let t = ((p as f32 + 0.5) / size as f32 - 0.5).clamp(0.0, (tiles - 1) as f32);
+        let t0 = t.floor();
+        (t0 as u32, (t0 as u32 + 1).min(tiles - 1), t - t0)
+    };
+    luma.iter()
+        .enumerate()
+        .map(|(i, l)| {
+            let Some(l) = *l else { return 0 };
+            let i = i as u32;
+            let (x0, x1, fx) = axis(i % width, tile_w, tiles_x);
+            let (y0, y1, fy) = axis(i / width, tile_h, tiles_y);
+            let at = |ty: u32, tx: u32| f32::from(luts[(ty * tiles_x + tx) as usize][l]);
+            let top = (at(y0, x1) - at(y0, x0)).mul_add(fx, at(y0, x0));
+            let bottom = (at(y1, x1) - at(y1, x0)).mul_add(fx, at(y1, x0));
+            (bottom - top).mul_add(fy, top).round() as u8
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    fn grey(v: u8) -> Rgba<u8> {
+        Rgba([v, v, v, 255])
+    }
+
+    #[test]
+    fn linear_needs_no_levels() {
+        let img = RgbaImage::from_pixel(4, 4, grey(90));
+        assert_eq!(levels(&img, ToneMap::Linear), None);
+    }
+
+    #[test]
+    fn percentile_ignores_a_bright_outlier() {
+        // A dim gradient with one white pixel: linear stretch would crush
+        // the gradient into the bottom third of the ramp.
+        let mut img = RgbaImage::from_fn(100, 1, |x, _| grey(u8::try_from(x).unwrap_or(0)));
+        img.put_pixel(99, 0, grey(255));
+        let levels = levels(&img, ToneMap::Percentile(2)).expect("percentile levels");
+        assert_eq!(levels[0], 0);
+        assert!(levels[90] > 230, "{}", levels[90]);
+        assert_eq!(levels[99], 255);
+    }
+
+    #[test]
+    fn equalize_spreads_a_narrow_range_over_the_ramp() {
+        let img = RgbaImage::from_fn(16, 16, |x, y| grey(u8::try_from(100 + x + y).unwrap_or(0)));
+        let levels = levels(&img, ToneMap::Equalize).expect("equalized levels");
+        assert_eq!(levels.iter().min(), Some(&0));
+        assert_eq!(levels.iter().max(), Some(&255));
+    }
+
+    #[test]
+    fn clahe_brings_out_detail_in_both_halves() {
+        // Dark noise on the left, bright noise on the right: a linear
+        // stretch leaves each half about 16 levels wide, CLAHE spreads both.
+        let img = RgbaImage::from_fn(64, 64, |x, y| {
+            let noise = u8::try_from((x * 7 + y * 13) % 16).unwrap_or(0);
+            if x < 32 {
+                grey(10 + noise)
+            } else {
+                grey(220 + noise)
+            }
+        });
+        let levels = levels(&img, ToneMap::Clahe).expect("clahe levels");
+        let spread = |xs: std::ops::Range<usize>| {
+            let row = &levels[16 * 64..17 * 64];
+            let band = &row[xs];
+            band.iter().max().unwrap_or(&0) - band.iter().min().unwrap_or(&0)
+        };
+        assert!(spread(0..16) > 48, "{}", spread(0..16));
+        assert!(spread(48..64) > 48, "{}", spread(48..64));
+    }
+
+    #[test]
+    fn transparent_pixels_get_level_zero() {
+        let mut img = RgbaImage::from_fn(4, 1, |x, _| grey(u8::try_from(x * 60).unwrap_or(0)));
+        img.put_pixel(3, 0, Rgba([255, 255, 255, 0]));
+        for tone_map in [ToneMap::Percentile(0), ToneMap::Equalize] {
+            let levels = levels(&img, tone_map).expect("levels");
+            assert_eq!(levels[3], 0, "{tone_map:?}");
+            assert_eq!(levels[2], 255, "{tone_map:?}");
+        }
+        let levels = levels(&img, ToneMap::Clahe).expect("clahe levels");
+        assert_eq!(levels[3], 0);
+    }
+}
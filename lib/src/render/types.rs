@@ -1,6 +1,9 @@
+use std::collections::HashMap;
 use std::str::FromStr;
+use std::sync::{Mutex, OnceLock, PoisonError};
 use thiserror::Error;
 
+use super::palette::{Ansi16Palette, ColorPalette};
 use crate::color::terminal_supports_truecolor;
 
 /// Defines the character set used to represent pixels in the terminal.
@@ -13,10 +16,30 @@ pub enum CharsetMode {
     Unicode,
     /// Maximum density mode using 2x4 Braille dot patterns.
     Braille,
+    /// Quadrant blocks (▘▝▖▗▚▞…) covering 2x2 pixels per cell, each cell
+    /// fitted with its best foreground/background color pair.
+    Quadrant,
+    /// Sextant blocks (U+1FB00 "Symbols for Legacy Computing") covering 2x3
+    /// pixels per cell with a fitted foreground/background pair.
+    Sextant,
+    /// Unicode 16 octant blocks (U+1CD00) covering 2x4 pixels per cell with a
+    /// fitted foreground/background pair: braille resolution, solid coverage.
+    Octant,
+    /// Tries every glyph in the configured [`SymbolSet`] for each cell and
+    /// keeps the glyph and foreground/background pair with the lowest Oklab
+    /// error against the 2x4 source pixels. Best suited to photos.
+    Hybrid,
     /// A small 4-character ramp ( ░▒▓█) for a "faded" or shaded look.
     Fade,
     /// Traditional 92-character density ramp for classic ASCII art.
     Ascii,
+    /// ASCII chosen by shape: each 4x8-pixel cell is matched against glyph
+    /// bitmaps from the embedded font, so edges become `/`, `\`, `|`, `_`.
+    /// Requires the `rasterize` feature.
+    Shape,
+    /// Line art: a Sobel edge detector traces contours with `─ │ / \\`
+    /// oriented along each edge, leaving filled regions blank.
+    Edges,
 
     /// Uses Japanese Kanji characters to represent visual density.
     /// Often used for a "Matrix-style" or highly stylized terminal aesthetic.
@@ -26,9 +49,21 @@ pub enum CharsetMode {
     /// Provides a unique texture and complex character patterns for image rendering.
     Chinese,
 
+    /// A user-supplied density ramp, ordered from darkest to brightest.
+    /// Rendered through the same luminance path as `Ascii` and `Kanji`.
+    Custom(CustomCharset),
+
     /// A high-performance bitmap protocol that renders actual image pixels
     /// directly in the terminal (requires a compatible terminal emulator).
     Sixel,
+
+    /// Sends the image as an RGBA bitmap using the kitty graphics protocol.
+    /// Supported by kitty, `WezTerm`, Ghostty and Konsole.
+    Kitty,
+
+    /// Sends the image as a PNG using the iTerm2 inline image protocol
+    /// (`OSC 1337`). Supported by iTerm2, `WezTerm`, Konsole and mintty.
+    Iterm2,
 }
 
 impl FromStr for CharsetMode {
@@ -39,15 +74,121 @@ impl FromStr for CharsetMode {
             "ansi" | "block" => Ok(Self::Ansi),
             "unicode" | "uni" => Ok(Self::Unicode),
             "braille" | "brl" => Ok(Self::Braille),
+            "quadrant" | "quad" => Ok(Self::Quadrant),
+            "sextant" => Ok(Self::Sextant),
+            "octant" => Ok(Self::Octant),
+            "hybrid" | "auto" => Ok(Self::Hybrid),
             "fade" | "grayscale" => Ok(Self::Fade),
             "kanji" | "jp" => Ok(Self::Kanji),
             "chinese" | "zh" | "hanzi" => Ok(Self::Chinese),
             "ascii" => Ok(Self::Ascii),
+            "shape" | "ascii-shape" => Ok(Self::Shape),
+            "edges" | "edge" | "outline" => Ok(Self::Edges),
+            "kitty" => Ok(Self::Kitty),
+            "iterm2" | "iterm" => Ok(Self::Iterm2),
             _ => Err(RenderError::InvalidCharset(s.to_string())),
         }
     }
 }
 
+/// An ordered glyph ramp for [`CharsetMode::Custom`].
+///
+/// Glyphs run from darkest (index 0, usually a space) to brightest. Set `wide`
+/// when every glyph occupies two terminal columns, as CJK characters do.
+///
+/// The glyphs are `'static` so that [`CharsetMode`] stays `Copy`. Ramps known
+/// at compile time can use [`CustomCharset::new`]; ramps read at runtime (a CLI
+/// flag, a config file) go through [`CustomCharset::from_ramp`].
+///
+/// ```
+/// use px2ansi::{CharsetMode, CustomCharset, RenderOptions};
+///
+/// const RAMP: &[&str] = &[" ", ".", ":", "-", "=", "+", "*", "#", "%", "@"];
+/// let opts = RenderOptions::builder()
+///     .charset(CharsetMode::Custom(CustomCharset::new(RAMP, false)))
+///     .build();
+/// assert_eq!(opts.charset().glyphs(), Some(RAMP));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CustomCharset {
+    glyphs: &'static [&'static str],
+    wide: bool,
+}
+
+impl CustomCharset {
+    /// Creates a ramp from static glyphs.
+    ///
+    /// An empty ramp renders every pixel as a blank.
+    #[must_use]
+    pub const fn new(glyphs: &'static [&'static str], wide: bool) -> Self {
+        Self { glyphs, wide }
+    }
+
+    /// Builds a ramp from a string, one glyph per character.
+    ///
+    /// Parsed ramps are interned for the life of the process to obtain a
+    /// `'static` lifetime, so each distinct ramp is stored once no matter
+    /// how often it is parsed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError::InvalidCharset`] if `ramp` is empty.
+    pub fn from_ramp(ramp: &str, wide: bool) -> Result<Self, RenderError> {
+        if ramp.is_empty() {
+            return Err(RenderError::InvalidCharset(
+                "custom charset needs at least one glyph".to_string(),
+            ));
+        }
+        Ok(Self::new(intern_ramp(ramp), wide))
+    }
+
+    /// Returns the glyphs, darkest first.
+    #[must_use]
+    pub const fn glyphs(&self) -> &'static [&'static str] {
+        self.glyphs
+    }
+
+    /// Returns `true` if each glyph is two terminal columns wide.
+    #[must_use]
+    pub const fn is_wide(&self) -> bool {
+        self.wide
+    }
+}
+
+/// Ramps parsed by [`CustomCharset::from_ramp`], keyed by their source.
+static RAMPS: OnceLock<Mutex<HashMap<&'static str, &'static [&'static str]>>> = OnceLock::new();
+
+/// Returns the interned glyphs of `ramp`, storing it on first use. The
+/// glyphs are slices of a single copy of `ramp`.
+fn intern_ramp(ramp: &str) -> &'static [&'static str] {
+    let mut ramps = RAMPS
+        .get_or_init(Mutex::default)
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    if let Some(&glyphs) = ramps.get(ramp) {
+        return glyphs;
+    }
+    let source: &'static str = Box::leak(ramp.into());
+    let glyphs: Box<[&'static str]> = source
+        .char_indices()
+        .map(|(i, c)| &source[i..i + c.len_utf8()])
+        .collect();
+    let glyphs: &'static [&'static str] = Box::leak(glyphs);
+    ramps.insert(source, glyphs);
+    glyphs
+}
+
+impl CharsetMode {
+    /// Returns the glyph ramp of a [`CharsetMode::Custom`] mode.
+    #[must_use]
+    pub const fn glyphs(&self) -> Option<&'static [&'static str]> {
+        match self {
+            Self::Custom(custom) => Some(custom.glyphs),
+            _ => None,
+        }
+    }
+}
+
 /// Aesthetic density settings for the ASCII rendering style.
 ///
 /// This determines the character set "ramp" used to map image brightness
@@ -79,13 +220,203 @@ impl FromStr for Density {
     }
 }
 
+/// Dithering algorithm applied after resizing.
+///
+/// Every mode quantizes against the colours the output can actually show: the
+/// 8-bit channels of truecolor, the xterm-256 palette, or the brightness
+/// levels of a glyph ramp. Error diffusion spreads each pixel's rounding error
+/// to its unprocessed neighbours; ordered modes add a fixed threshold pattern
+/// instead, which keeps frames stable when rendering animations.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DitherMode {
+    /// No dithering: every pixel is quantized independently by the renderer.
+    #[default]
+    None,
+    /// Floyd–Steinberg error diffusion over four neighbours.
+    FloydSteinberg,
+    /// Atkinson error diffusion, which drops a quarter of the error for
+    /// higher contrast.
+    Atkinson,
+    /// Three-row Sierra error diffusion.
+    Sierra,
+    /// Stucki error diffusion over twelve neighbours.
+    Stucki,
+    /// Jarvis–Judice–Ninke error diffusion over twelve neighbours.
+    Jarvis,
+    /// Ordered dithering with a 2x2 Bayer matrix.
+    Bayer2,
+    /// Ordered dithering with a 4x4 Bayer matrix.
+    Bayer4,
+    /// Ordered dithering with an 8x8 Bayer matrix.
+    Bayer8,
+    /// Ordered dithering with a 32x32 blue-noise threshold map.
+    BlueNoise,
+}
+
+impl FromStr for DitherMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "none" | "off" => Ok(Self::None),
+            "floyd-steinberg" | "floyd" | "fs" => Ok(Self::FloydSteinberg),
+            "atkinson" => Ok(Self::Atkinson),
+            "sierra" => Ok(Self::Sierra),
+            "stucki" => Ok(Self::Stucki),
+            "jarvis" | "jjn" => Ok(Self::Jarvis),
+            "bayer2" => Ok(Self::Bayer2),
+            "bayer4" | "bayer" => Ok(Self::Bayer4),
+            "bayer8" => Ok(Self::Bayer8),
+            "blue-noise" | "bluenoise" | "noise" => Ok(Self::BlueNoise),
+            _ => Err(format!(
+                "invalid dither mode: '{s}'. (valid: none, floyd-steinberg, atkinson, sierra, stucki, jarvis, bayer2, bayer4, bayer8, blue-noise)"
+            )),
+        }
+    }
+}
+
+/// How luma is spread over a glyph ramp.
+///
+/// Only the luminance-mapped charsets use this: [`CharsetMode::Ascii`],
+/// [`CharsetMode::Fade`], [`CharsetMode::Kanji`], [`CharsetMode::Chinese`]
+/// and custom ramps. Colours are left alone unless a [`DitherMode`] is set,
+/// which dithers between the tone-mapped levels and scales each pixel's
+/// colour to the level it lands on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ToneMap {
+    /// Stretch luma linearly between the darkest and brightest pixel.
+    #[default]
+    Linear,
+    /// Stretch linearly between two percentiles, ignoring the given
+    /// percentage of pixels at each end, so a few outliers cannot flatten
+    /// the ramp.
+    Percentile(u8),
+    /// Global histogram equalisation: every glyph covers roughly the same
+    /// number of pixels.
+    Equalize,
+    /// Contrast-limited adaptive histogram equalisation (CLAHE): equalises
+    /// an 8x8 grid of tiles separately, with clipped histograms so flat areas
+    /// do not turn into noise, and blends neighbouring tiles.
+    Clahe,
+}
+
+impl FromStr for ToneMap {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.to_lowercase();
+        if let Some(percent) = s.strip_prefix("percentile:") {
+            return match percent.parse::<u8>() {
+                Ok(p) if p < 50 => Ok(Self::Percentile(p)),
+                _ => Err(format!(
+                    "invalid percentile: '{percent}'. (expected a whole percentage below 50)"
+                )),
+            };
+        }
+        match s.as_str() {
+            "linear" | "none" => Ok(Self::Linear),
+            "percentile" => Ok(Self::Percentile(1)),
+            "equalize" | "equalise" | "histogram" => Ok(Self::Equalize),
+            "clahe" | "adaptive" => Ok(Self::Clahe),
+            _ => Err(format!(
+                "invalid tone map: '{s}'. (valid: linear, percentile, percentile:N, equalize, clahe)"
+            )),
+        }
+    }
+}
+
+/// Glyph families that [`CharsetMode::Hybrid`] may choose from.
+///
+/// A space (solid background) is always available. Octants are off by default
+/// because few fonts ship the Unicode 16 glyphs yet.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[expect(
+    clippy::struct_excessive_bools,
+    reason = "Each flag independently enables one glyph family"
+)]
+pub struct SymbolSet {
+    /// Upper and lower half blocks (▀▄).
+    pub half: bool,
+    /// 2x2 quadrant blocks (▘▝▖▗▚▞▌▐…).
+    pub quadrant: bool,
+    /// 2x3 sextants (U+1FB00).
+    pub sextant: bool,
+    /// 2x4 octants (U+1CD00, Unicode 16).
+    pub octant: bool,
+    /// 2x4 braille dot patterns, drawn as partial coverage.
+    pub braille: bool,
+    /// Shade glyphs (░▒▓), drawn as a uniform mix of foreground and background.
+    pub shade: bool,
+}
+
+impl Default for SymbolSet {
+    fn default() -> Self {
+        Self {
+            half: true,
+            quadrant: true,
+            sextant: true,
+            octant: false,
+            braille: true,
+            shade: true,
+        }
+    }
+}
+
+impl SymbolSet {
+    /// Every supported glyph family, including octants.
+    pub const ALL: Self = Self {
+        half: true,
+        quadrant: true,
+        sextant: true,
+        octant: true,
+        braille: true,
+        shade: true,
+    };
+
+    /// No glyph families; only solid cells are emitted.
+    pub const NONE: Self = Self {
+        half: false,
+        quadrant: false,
+        sextant: false,
+        octant: false,
+        braille: false,
+        shade: false,
+    };
+}
+
+impl FromStr for SymbolSet {
+    type Err = String;
+
+    /// Parses a comma-separated list such as `half,quadrant,shade`, or `all`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut set = Self::NONE;
+        for name in s.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+            match name.to_lowercase().as_str() {
+                "all" => set = Self::ALL,
+                "half" => set.half = true,
+                "quadrant" => set.quadrant = true,
+                "sextant" => set.sextant = true,
+                "octant" => set.octant = true,
+                "braille" => set.braille = true,
+                "shade" => set.shade = true,
+                _ => {
+                    return Err(format!(
+                        "invalid symbol set: '{name}'. (valid: half, quadrant, sextant, octant, braille, shade, all)"
+                    ));
+                }
+            }
+        }
+        Ok(set)
+    }
+}
+
 /// Combines physical character choice with layout logic (like full-width vs half-width).
 #[derive(Clone, Copy, Debug)]
 pub struct RenderStyle {
     /// If true, uses double-width characters (██) to force a 1:1 pixel aspect ratio.
     pub(crate) full: bool,
     pub(crate) density: Density,
-    pub(crate) dither: bool,
+    pub(crate) dither: DitherMode,
     #[allow(dead_code)]
     pub(crate) wide: bool,
 }
@@ -95,7 +426,7 @@ impl Default for RenderStyle {
         Self {
             full: false,
             density: Density::Medium,
-            dither: false,
+            dither: DitherMode::None,
             wide: false,
         }
     }
@@ -113,9 +444,9 @@ impl RenderStyle {
         self.full
     }
 
-    /// Returns `true` if "dithering" is enabled.
+    /// Returns the [`DitherMode`] applied after resizing.
     #[must_use]
-    pub const fn dither(&self) -> bool {
+    pub const fn dither(&self) -> DitherMode {
         self.dither
     }
     /// Returns the current [`Density`] level.
@@ -142,6 +473,9 @@ pub enum RenderError {
     /// Errors occurring during image decoding, resizing, or pixel manipulation.
     #[error("Image processing error: {0}")]
     Image(String),
+    /// Returned when a palette file or color list cannot be parsed.
+    #[error("Invalid palette: {0}")]
+    InvalidPalette(String),
     /// Returned when an unsupported density string is provided via configuration.
     #[error("Invalid density: {0}. (valid: light, medium, heavy)")]
     InvalidDensity(String),
@@ -166,6 +500,23 @@ pub enum ColorMode {
     /// Uses xterm-compatible 256-color escape sequences.
     /// Colors are quantized using the Oklab color space for better perceptual accuracy.
     Ansi256,
+    /// Uses the 16 basic SGR colors (30–37 and 90–97, backgrounds 40–47 and
+    /// 100–107), the only ones guaranteed on the Linux console and in CI logs.
+    /// Colors are matched in Oklab against the given palette, which should
+    /// mirror the terminal's theme.
+    Ansi16(Ansi16Palette),
+    /// Uses the 24-step xterm grayscale ramp (colors 232–255).
+    Grayscale,
+    /// Locks every color to a fixed [`ColorPalette`], matched in Oklab, for
+    /// retro or themed art where a consistent palette matters more than
+    /// fidelity. The chosen colors are emitted as 24-bit escapes, or as their
+    /// nearest xterm-256 index when `ansi256` is set.
+    Palette {
+        /// The colors output is locked to.
+        palette: ColorPalette,
+        /// Emit 256-color instead of 24-bit escapes.
+        ansi256: bool,
+    },
     /// Disables all color escape sequences, producing plain text output.
     None,
 }
@@ -187,6 +538,8 @@ impl std::str::FromStr for ColorMode {
         match s.to_lowercase().as_str() {
             "truecolor" => Ok(Self::TrueColor),
             "ansi256" | "256" => Ok(Self::Ansi256),
+            "ansi16" | "16" => Ok(Self::Ansi16(Ansi16Palette::default())),
+            "grayscale" | "greyscale" | "gray" | "grey" => Ok(Self::Grayscale),
             "none" => Ok(Self::None),
             _ => Err(format!("'{s}' is not a valid color mode")),
         }
@@ -0,0 +1,298 @@
+//! Edge-aware pixel-art upscalers for [`ResizeFilter::Scale2x`],
+//! [`ResizeFilter::Scale3x`] and [`ResizeFilter::Xbr`].
+//!
+//! Each pass multiplies the size by a whole factor and decides every new
+//! pixel from the source pixel's neighbours, so diagonals come out as
+//! smooth steps instead of the square blocks nearest neighbour gives.
+//!
+//! [`ResizeFilter::Scale2x`]: crate::ResizeFilter::Scale2x
+//! [`ResizeFilter::Scale3x`]: crate::ResizeFilter::Scale3x
+//! [`ResizeFilter::Xbr`]: crate::ResizeFilter::Xbr
+
+use image::{Rgba, RgbaImage};
+
+use crate::cli_enums::ResizeFilter;
+
+/// One upscaling pass.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum Scaler {
+    /// Scale2x, also known as EPX or `AdvMAME2x`.
+    Scale2x,
+    /// Scale3x, also known as `AdvMAME3x`.
+    Scale3x,
+    /// 2xBR: blends corners where weighted colour distances find an edge.
+    Xbr,
+}
+
+impl Scaler {
+    const fn factor(self) -> u32 {
+        match self {
+            Self::Scale2x | Self::Xbr => 2,
+            Self::Scale3x => 3,
+        }
+    }
+}
+
+/// The passes that upscale a `src` image towards `target` for `filter`.
+///
+/// The upscaling filters run for as long as the result still fits inside
+/// `target`. With `auto`, [`ResizeFilter::Nearest`] and
+/// [`ResizeFilter::PixelArt`] use the Scale3x and Scale2x passes with the
+/// largest combined factor that still fits inside `target`, and nearest
+/// neighbour stretches the rest. Everything else gets no passes.
+pub(super) fn upscale_passes(
+    filter: ResizeFilter,
+    auto: bool,
+    (src_w, src_h): (u32, u32),
+    (width, height): (u32, u32),
+) -> Vec<Scaler> {
+    let scaler = match filter {
+        ResizeFilter::Scale2x => Scaler::Scale2x,
+        ResizeFilter::Scale3x => Scaler::Scale3x,
+        ResizeFilter::Xbr => Scaler::Xbr,
+        ResizeFilter::Nearest | ResizeFilter::PixelArt if auto => {
+            return auto_passes((src_w, src_h), (width, height));
+        }
+        _ => return Vec::new(),
+    };
+    let k = scaler.factor();
+    let (mut w, mut h) = (src_w.max(1), src_h.max(1));
+    let mut passes = Vec::new();
+    while w * k <= width && h * k <= height {
+        passes.push(scaler);
+        (w, h) = (w * k, h * k);
+    }
+    passes
+}
+
+/// Scale3x and Scale2x passes for the largest factor made of 3s and 2s
+/// that keeps `src` inside `target` on both axes.
+fn auto_passes((src_w, src_h): (u32, u32), (width, height): (u32, u32)) -> Vec<Scaler> {
+    if src_w == 0 || src_h == 0 {
+        return Vec::new();
+    }
+    let limit = (width / src_w).min(height / src_h);
+    let factors = |mut k: u32| {
+        let mut passes = Vec::new();
+        for (scaler, f) in [(Scaler::Scale3x, 3), (Scaler::Scale2x, 2)] {
+            while k.is_multiple_of(f) {
+                passes.push(scaler);
+                k /= f;
+            }
+        }
+        (k == 1).then_some(passes)
+    };
+    (2..=limit).rev().find_map(factors).unwrap_or_default()
+}
+
+/// Runs `passes` over `rgba` in order.
+pub(super) fn upscale(rgba: &RgbaImage, passes: &[Scaler]) -> RgbaImage {
+    passes
+        .iter()
+        .fold(rgba.clone(), |img, scaler| match scaler {
+            Scaler::Scale2x => scale2x(&img),
+            Scaler::Scale3x => scale3x(&img),
+            Scaler::Xbr => xbr(&img),
+        })
+}
+
+/// The pixel at (`x` + `dx`, `y` + `dy`), clamped to the image.
+fn at(img: &RgbaImage, x: u32, y: u32, dx: i64, dy: i64) -> Rgba<u8> {
+    let clamp = |v: u32, d: i64, max: u32| {
+        u32::try_from((i64::from(v) + d).clamp(0, i64::from(max) - 1)).unwrap_or(0)
+    };
+    *img.get_pixel(clamp(x, dx, img.width()), clamp(y, dy, img.height()))
+}
+
+fn scale2x(img: &RgbaImage) -> RgbaImage {
+    RgbaImage::from_fn(img.width() * 2, img.height() * 2, |ox, oy| {
+        let (x, y) = (ox / 2, oy / 2);
+        let centre = at(img, x, y, 0, 0);
+        let up = at(img, x, y, 0, -1);
+        let down = at(img, x, y, 0, 1);
+        let left = at(img, x, y, -1, 0);
+        let right = at(img, x, y, 1, 0);
+        // Mirror the top-left rule into the quadrant being filled.
+        let (near_v, far_v) = if oy % 2 == 0 { (up, down) } else { (down, up) };
+        let (near_h, far_h) = if ox % 2 == 0 {
+            (left, right)
+        } else {
+            (right, left)
+        };
+        if near_h == near_v && near_h != far_v && near_v != far_h {
+            near_v
+        } else {
+            centre
+        }
+    })
+}
+
+#[expect(
+    clippy::many_single_char_names,
+    reason = "The neighbourhood uses the letters of the published Scale3x rules"
+)]
+fn scale3x(img: &RgbaImage) -> RgbaImage {
+    RgbaImage::from_fn(img.width() * 3, img.height() * 3, |ox, oy| {
+        let (x, y) = (ox / 3, oy / 3);
+        let n = |dx, dy| at(img, x, y, dx, dy);
+        let [a, b, c] = [n(-1, -1), n(0, -1), n(1, -1)];
+        let [d, e, f] = [n(-1, 0), n(0, 0), n(1, 0)];
+        let [g, h, i] = [n(-1, 1), n(0, 1), n(1, 1)];
+        if !(b != h && d != f) {
+            return e;
+        }
+        match (ox % 3, oy % 3) {
+            (0, 0) if d == b => d,
+            (1, 0) if (d == b && e != c) || (b == f && e != a) => b,
+            (2, 0) if b == f => f,
+            (0, 1) if (d == b && e != g) || (d == h && e != a) => d,
+            (2, 1) if (b == f && e != i) || (h == f && e != c) => f,
+            (0, 2) if d == h => d,
+            (1, 2) if (d == h && e != i) || (h == f && e != g) => h,
+            (2, 2) if h == f => f,
+            _ => e,
+        }
+    })
+}
+
+/// Weighted YUV distance between two colours, as used by xBR.
+fn distance(a: Rgba<u8>, b: Rgba<u8>) -> f32 {
+    let diff = |i: usize| f32::from(a[i]) - f32::from(b[i]);
+    let (red, green, blue) = (diff(0), diff(1), diff(2));
+    let luma = 0.114f32.mul_add(blue, 0.299f32.mul_add(red, 0.587 * green));
+    let chroma_u = 0.5f32.mul_add(blue, (-0.169f32).mul_add(red, -0.331 * green));
+    let chroma_v = (-0.081f32).mul_add(blue, 0.5f32.mul_add(red, -0.419 * green));
+    48.0f32.mul_add(
+        luma.abs() + diff(3).abs(),
+        7.0f32.mul_add(chroma_u.abs(), 6.0 * chroma_v.abs()),
+    )
+}
+
+fn blend(a: Rgba<u8>, b: Rgba<u8>) -> Rgba<u8> {
+    Rgba(std::array::from_fn(|i| {
+        u8::try_from((u16::from(a[i]) + u16::from(b[i])).div_ceil(2)).unwrap_or(u8::MAX)
+    }))
+}
+
+#[expect(
+    clippy::many_single_char_names,
+    reason = "The neighbourhood uses the letters of the published xBR rules"
+)]
+fn xbr(img: &RgbaImage) -> RgbaImage {
+    RgbaImage::from_fn(img.width() * 2, img.height() * 2, |ox, oy| {
+        let (x, y) = (ox / 2, oy / 2);
+        // Mirror the bottom-right rule into the quadrant being filled; it is
+        // symmetric about the diagonal, so mirroring covers every corner.
+        let sx = if ox % 2 == 0 { -1 } else { 1 };
+        let sy = if oy % 2 == 0 { -1 } else { 1 };
+        let n = |dx: i64, dy: i64| at(img, x, y, dx * sx, dy * sy);
+        let e = n(0, 0);
+        let (b, c, d, f) = (n(0, -1), n(1, -1), n(-1, 0), n(1, 0));
+        let (g, h, i) = (n(-1, 1), n(0, 1), n(1, 1));
+        let (f4, h5, i4, i5) = (n(2, 0), n(0, 2), n(2, 1), n(1, 2));
+
+        let across = 4.0f32.mul_add(
+            distance(h, f),
+            distance(e, c) + distance(e, g) + distance(i, f4) + distance(i, h5),
+        );
+        let along = 4.0f32.mul_add(
+            distance(e, i),
+            distance(h, d) + distance(h, i5) + distance(f, i4) + distance(f, b),
+        );
+        if across < along {
+            let edge = if distance(e, f) <= distance(e, h) {
+                f
+            } else {
+                h
+            };
+            blend(e, edge)
+        } else {
+            e
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const W: Rgba<u8> = Rgba([255, 255, 255, 255]);
+    const K: Rgba<u8> = Rgba([0, 0, 0, 255]);
+
+    /// A black staircase diagonal on white.
+    fn diagonal(size: u32) -> RgbaImage {
+        RgbaImage::from_fn(size, size, |x, y| if x <= y { K } else { W })
+    }
+
+    #[test]
+    fn scale2x_smooths_a_diagonal() {
+        let out = scale2x(&diagonal(4));
+        assert_eq!(out.dimensions(), (8, 8));
+        // Nearest would leave a white 2x2 block above the diagonal; Scale2x
+        // fills its lower-left corner.
+        assert_eq!(*out.get_pixel(2, 1), K);
+        assert_eq!(*out.get_pixel(3, 0), W);
+        // Flat areas stay flat.
+        assert_eq!(*out.get_pixel(7, 0), W);
+        assert_eq!(*out.get_pixel(0, 7), K);
+    }
+
+    #[test]
+    fn scale3x_smooths_a_diagonal() {
+        let out = scale3x(&diagonal(4));
+        assert_eq!(out.dimensions(), (12, 12));
+        assert_eq!(*out.get_pixel(3, 2), K);
+        assert_eq!(*out.get_pixel(5, 0), W);
+    }
+
+    #[test]
+    fn xbr_blends_edges_and_keeps_flat_areas() {
+        let out = xbr(&diagonal(4));
+        assert_eq!(out.dimensions(), (8, 8));
+        assert_eq!(*out.get_pixel(2, 1), Rgba([128, 128, 128, 255]));
+        assert_eq!(*out.get_pixel(7, 0), W);
+        let flat = RgbaImage::from_pixel(3, 3, K);
+        assert!(xbr(&flat).pixels().all(|p| *p == K));
+    }
+
+    #[test]
+    fn passes_fit_the_target() {
+        let two = |n| vec![Scaler::Scale2x; n];
+        assert_eq!(
+            upscale_passes(ResizeFilter::Scale2x, false, (16, 16), (70, 64)),
+            two(2)
+        );
+        assert_eq!(
+            upscale_passes(ResizeFilter::Xbr, false, (16, 16), (31, 64)),
+            Vec::new()
+        );
+        assert_eq!(
+            upscale_passes(ResizeFilter::Nearest, true, (16, 16), (64, 64)),
+            two(2)
+        );
+        assert_eq!(
+            upscale_passes(ResizeFilter::Nearest, true, (16, 16), (96, 96)),
+            vec![Scaler::Scale3x, Scaler::Scale2x]
+        );
+        // Automatic passes take the largest factor of 2s and 3s that fits,
+        // and nearest neighbour stretches the rest.
+        for (target, passes) in [
+            ((80, 80), two(2)),
+            ((70, 64), two(2)),
+            ((64, 48), vec![Scaler::Scale3x]),
+            ((64, 32), two(1)),
+        ] {
+            assert_eq!(
+                upscale_passes(ResizeFilter::Nearest, true, (16, 16), target),
+                passes
+            );
+        }
+        assert_eq!(
+            upscale_passes(ResizeFilter::PixelArt, true, (16, 16), (64, 32)),
+            two(1)
+        );
+        assert!(upscale_passes(ResizeFilter::Nearest, true, (16, 16), (31, 64)).is_empty());
+        assert!(upscale_passes(ResizeFilter::Nearest, false, (16, 16), (64, 64)).is_empty());
+        assert!(upscale_passes(ResizeFilter::Lanczos3, true, (16, 16), (64, 64)).is_empty());
+    }
+}
@@ -1,143 +1,267 @@
+use super::crop::clamp_region;
 use super::options::RenderOptions;
+use crate::FitMode;
 use crate::render::CharsetMode;
+use image::{DynamicImage, Rgba, RgbaImage};
 use terminal_size::{Height, Width, terminal_size};
 
+/// Assumed width of one terminal cell in pixels for bitmap protocols.
+///
+/// Kitty and iTerm2 output is placed in `ceil(width / CELL_PX_W)` columns.
+pub const CELL_PX_W: u32 = 8;
+/// Assumed height of one terminal cell in pixels for bitmap protocols.
+///
+/// Kitty and iTerm2 output is placed in `ceil(height / CELL_PX_H)` rows.
+pub const CELL_PX_H: u32 = 16;
+
+/// Source pixels per cell horizontally for shape-matched ASCII.
+pub(super) const SHAPE_CELL_W: u32 = 4;
+/// Source pixels per cell vertically for shape-matched ASCII.
+pub(super) const SHAPE_CELL_H: u32 = 8;
+
+/// Pixel columns and rows per cell for the sub-cell modes.
+const fn subcell_grid(charset: CharsetMode) -> (u32, u32) {
+    match charset {
+        CharsetMode::Quadrant => (2, 2),
+        CharsetMode::Sextant => (2, 3),
+        CharsetMode::Shape => (SHAPE_CELL_W, SHAPE_CELL_H),
+        _ => (2, 4),
+    }
+}
+
+/// Terminal cells are assumed to be twice as tall as they are wide unless
+/// [`RenderOptions::cell_aspect`] says otherwise.
+pub(super) const DEFAULT_CELL_ASPECT: f32 = 2.0;
+
+/// Converts `img` to RGBA for the pixel protocols and embedded PNGs,
+/// composited against [`RenderOptions::bg_color`] when one is set.
+pub(super) fn composite_bg(img: &DynamicImage, options: &RenderOptions) -> RgbaImage {
+    let rgba = img.to_rgba8();
+    let Some([r, g, b]) = options.bg_color() else {
+        return rgba;
+    };
+    let mut composited = RgbaImage::from_pixel(rgba.width(), rgba.height(), Rgba([r, g, b, 255]));
+    image::imageops::overlay(&mut composited, &rgba, 0, 0);
+    composited
+}
+
+/// The largest prepared image side, in pixels.
+const MAX_SAFE: u32 = 16_384;
+
+/// Where [`RenderOptions::prepare_image`] crops the source image and what
+/// size it resizes the result to.
+pub(super) struct Layout {
+    /// The `(x, y, width, height)` source region to keep, if any.
+    pub(super) crop: Option<(u32, u32, u32, u32)>,
+    /// The prepared image size in pixels.
+    pub(super) size: (u32, u32),
+}
+
 impl RenderOptions {
     /// Calculates the optimal target dimensions for the terminal.
+    ///
+    /// The image is sized in terminal cells according to the [`FitMode`],
+    /// [`width`](Self::width), [`height`](Self::height),
+    /// [`max_width`](Self::max_width) and [`max_height`](Self::max_height),
+    /// keeping the source's shape on cells of
+    /// [`cell_aspect`](Self::cell_aspect), and the result is converted to the
+    /// pixels the charset draws per cell. A [`crop`](Self::crop) region is
+    /// sized instead of the whole image; [`auto_trim`](Self::auto_trim)
+    /// depends on the pixels, so only
+    /// [`prepare_image`](Self::prepare_image) accounts for it.
     #[must_use]
     pub fn calculate_dimensions(&self, orig_w: u32, orig_h: u32) -> (u32, u32) {
-        const MAX_SAFE: u32 = 16_384;
-        #[expect(
-            clippy::cast_possible_truncation,
-            clippy::cast_sign_loss,
-            reason = "Scaling positive terminal dimensions by aspect ratios produces positive values within u32 range; MAX_SAFE further bounds results"
-        )]
-        fn fit_preserving_aspect(
-            orig_w: u32,
-            orig_h: u32,
-            max_w: u32,
-            max_h: u32,
-            cell_w: f64,
-            cell_h: f64,
-        ) -> (u32, u32) {
-            let img_aspect = f64::from(orig_h) / f64::from(orig_w);
-            let cell_aspect = cell_h / cell_w;
-            let h_from_w = (f64::from(max_w) * img_aspect * cell_aspect).ceil() as u32;
-            if h_from_w <= max_h {
-                (max_w.max(1), h_from_w.max(1))
-            } else {
-                let w_from_h = (f64::from(max_h) / (img_aspect * cell_aspect)).floor() as u32;
-                (w_from_h.max(1), max_h.max(1))
+        let (w, h) = self
+            .crop()
+            .and_then(|region| clamp_region(region, orig_w, orig_h))
+            .map_or((orig_w, orig_h), |(_, _, w, h)| (w, h));
+        self.layout(w, h).size
+    }
+
+    /// Sizes an `orig_w` x `orig_h` source image; see
+    /// [`calculate_dimensions`](Self::calculate_dimensions).
+    pub(super) fn layout(&self, orig_w: u32, orig_h: u32) -> Layout {
+        let (orig_w, orig_h) = (orig_w.max(1), orig_h.max(1));
+        let (px_w, px_h) = self.cell_pixels();
+        let cell_aspect = self.effective_cell_aspect();
+        // Rows of cells per column that keep the source's aspect ratio.
+        let aspect = f64::from(orig_h) / f64::from(orig_w) / cell_aspect;
+
+        let mut crop = None;
+        let (cols, rows) = match (self.fit(), self.width(), self.height()) {
+            (FitMode::Native, ..) => (f64::from(orig_w) / px_w, f64::from(orig_h) / px_h),
+            (_, Some(w), None) => (f64::from(w), f64::from(w) * aspect),
+            (_, None, Some(h)) => (f64::from(h) / aspect, f64::from(h)),
+            (FitMode::Contain, Some(w), Some(h)) => contain(f64::from(w), f64::from(h), aspect),
+            (FitMode::Contain, None, None) => self.fit_terminal(orig_w, orig_h, aspect),
+            (FitMode::Cover | FitMode::Stretch, w, h) => {
+                let (term_cols, term_rows) = self.terminal_box();
+                let cols = f64::from(w.unwrap_or(term_cols));
+                let rows = f64::from(h.unwrap_or(term_rows));
+                if self.fit() == FitMode::Cover {
+                    crop = Some(cover_crop(orig_w, orig_h, rows / cols * cell_aspect));
+                }
+                (cols, rows)
+            }
+        };
+
+        let (cols, rows) = self.cap(cols, rows);
+        Layout {
+            crop,
+            size: (to_pixels(cols * px_w), to_pixels(rows * px_h)),
+        }
+    }
+
+    /// Prepared-image pixels per terminal cell, horizontally and vertically.
+    pub(super) fn cell_pixels(&self) -> (f64, f64) {
+        match self.charset() {
+            // Full blocks draw each pixel as two columns.
+            CharsetMode::Unicode if self.style().full => (0.5, 1.0),
+            CharsetMode::Ansi | CharsetMode::Unicode => (1.0, 2.0),
+            CharsetMode::Braille
+            | CharsetMode::Quadrant
+            | CharsetMode::Sextant
+            | CharsetMode::Octant
+            | CharsetMode::Hybrid
+            | CharsetMode::Shape => {
+                let (cols, rows) = subcell_grid(self.charset());
+                (f64::from(cols), f64::from(rows))
+            }
+            CharsetMode::Sixel | CharsetMode::Kitty | CharsetMode::Iterm2 => {
+                (f64::from(CELL_PX_W), f64::from(CELL_PX_H))
             }
+            // Wide ramps sample every other pixel and draw each glyph two
+            // columns wide, so they also use one pixel per column.
+            CharsetMode::Ascii
+            | CharsetMode::Fade
+            | CharsetMode::Edges
+            | CharsetMode::Kanji
+            | CharsetMode::Chinese
+            | CharsetMode::Custom(_) => (1.0, 1.0),
         }
+    }
+
+    /// Terminal columns a prepared image `pixel_width` pixels wide renders to.
+    ///
+    /// [`render`](Self::render) draws the image it is given as is, so this is
+    /// also the width of its output for an image sized by the caller.
+    #[must_use]
+    #[expect(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        reason = "Columns never exceed the prepared width times two"
+    )]
+    pub fn rendered_cols(&self, pixel_width: u32) -> u32 {
+        (f64::from(pixel_width) / self.cell_pixels().0).ceil() as u32
+    }
+
+    /// The cell aspect ratio sizing uses. Bitmap protocols place their
+    /// pixels in cells of `CELL_PX_W` x `CELL_PX_H`, whatever the font.
+    fn effective_cell_aspect(&self) -> f64 {
+        match self.charset() {
+            CharsetMode::Sixel | CharsetMode::Kitty | CharsetMode::Iterm2 => {
+                f64::from(CELL_PX_H) / f64::from(CELL_PX_W)
+            }
+            _ => f64::from(self.cell_aspect()),
+        }
+    }
+
+    /// The cell box the terminal offers when neither width nor height is set.
+    fn terminal_box(&self) -> (u32, u32) {
         let (term_w, term_h) = get_terminal_size();
-        #[expect(
-            clippy::cast_possible_truncation,
-            clippy::cast_sign_loss,
-            reason = "Terminal scaling math uses bounded conversions"
-        )]
-        let (render_w, render_h) = self.width().map_or_else(
-            || match self.charset() {
-                CharsetMode::Ansi => fit_preserving_aspect(
-                    orig_w,
-                    orig_h,
-                    term_w.saturating_sub(2),
-                    term_h * 2,
-                    1.0,
-                    1.0,
-                ),
-                CharsetMode::Unicode => {
-                    if self.style().full {
-                        fit_preserving_aspect(orig_w, orig_h, term_w / 2, term_h, 1.0, 1.0)
-                    } else {
-                        fit_preserving_aspect(
-                            orig_w,
-                            orig_h,
-                            term_w.saturating_sub(2),
-                            term_h * 2,
-                            1.0,
-                            1.0,
-                        )
-                    }
-                }
-                CharsetMode::Braille => {
-                    fit_preserving_aspect(orig_w, orig_h, term_w * 2, term_h * 4, 1.0, 1.0)
-                }
+        match self.charset() {
+            CharsetMode::Unicode if self.style().full => (term_w, term_h),
+            CharsetMode::Braille
+            | CharsetMode::Sixel
+            | CharsetMode::Kitty
+            | CharsetMode::Iterm2 => (term_w, term_h),
+            _ => (term_w.saturating_sub(2), term_h),
+        }
+    }
 
-                CharsetMode::Sixel => {
-                    let px_w = term_w * 8;
-                    let px_h = term_h * 16;
-                    let scale = (f64::from(px_w) / f64::from(orig_w))
-                        .min(f64::from(px_h) / f64::from(orig_h))
-                        .min(1.0);
-                    (
-                        (f64::from(orig_w) * scale) as u32,
-                        (f64::from(orig_h) * scale) as u32,
-                    )
-                }
-                // ASCII/Fade/Kanji/Chinese: derive height directly from terminal
-                // width using aspect ratio + cell correction (cells are ~2:1 tall:wide).
-                // Do NOT use fit_preserving_aspect here -- the two-way fit shrinks width
-                // to satisfy height constraints and breaks the output.
-                CharsetMode::Ascii
-                | CharsetMode::Fade
-                | CharsetMode::Kanji
-                | CharsetMode::Chinese => {
-                    let w = term_w.saturating_sub(2);
-                    let aspect = f64::from(orig_h) / f64::from(orig_w);
-                    let h = (f64::from(w) * aspect / 2.0).ceil() as u32;
-                    (w, h.min(term_h))
-                }
-            },
-            // |tw| match self.charset() {
-            //     CharsetMode::Sixel => {
-            //         let px_w = tw * 8;
-            //         let px_h = u32::MAX;
-            //         let scale = (f64::from(px_w) / f64::from(orig_w))
-            //             .min(f64::from(px_h) / f64::from(orig_h))
-            //             .min(1.0);
-            //         (
-            //             (f64::from(orig_w) * scale) as u32,
-            //             (f64::from(orig_h) * scale) as u32,
-            //         )
-            //     }
-            //     CharsetMode::Braille => {
-            //         fit_preserving_aspect(orig_w, orig_h, tw * 2, u32::MAX, 1.0, 1.0)
-            //     }
-            //     CharsetMode::Ascii
-            //     | CharsetMode::Fade
-            //     | CharsetMode::Kanji
-            //     | CharsetMode::Chinese => {
-            //         let aspect = f64::from(orig_h) / f64::from(orig_w);
-            //         let h = (f64::from(tw) * aspect / 2.0).ceil() as u32;
-            //         (tw.max(1), h.max(1))
-            //     }
-            //     _ => {
-            //         let h = fit_preserving_aspect(orig_w, orig_h, tw, u32::MAX, 1.0, 1.0).1;
-            //         (tw.max(1), h.max(1))
-            //     }
-            // },
-            |tw| {
-                let h = fit_preserving_aspect(orig_w, orig_h, tw, u32::MAX, 1.0, 2.0).1;
-                (tw.max(1), h.max(1))
-            },
-        );
-        (render_w.clamp(1, MAX_SAFE), render_h.clamp(1, MAX_SAFE))
-        // let result = (render_w.clamp(1, MAX_SAFE), render_h.clamp(1, MAX_SAFE));
-        //         eprintln!(
-        //             "DEBUG charset={:?} term={}x{} orig={}x{} render={}x{}",
-        //             self.charset(),
-        //             term_w,
-        //             term_h,
-        //             orig_w,
-        //             orig_h,
-        //             result.0,
-        //             result.1
-        //         );
-        //         result
+    /// The default [`FitMode::Contain`] size when neither width nor height is
+    /// set.
+    fn fit_terminal(&self, orig_w: u32, orig_h: u32, aspect: f64) -> (f64, f64) {
+        let (cols, rows) = self.terminal_box();
+        let (cols, rows) = (f64::from(cols), f64::from(rows));
+        match self.charset() {
+            // Bitmaps are scaled down to fit, never up.
+            CharsetMode::Sixel | CharsetMode::Kitty | CharsetMode::Iterm2 => {
+                let (px_w, px_h) = self.cell_pixels();
+                let (w, h) = contain(cols, rows, aspect);
+                (
+                    w.min(f64::from(orig_w) / px_w),
+                    h.min(f64::from(orig_h) / px_h),
+                )
+            }
+            // Luminance ramps fill the terminal width and only cap the
+            // height; shrinking the width to fit the height breaks the
+            // output.
+            CharsetMode::Ascii
+            | CharsetMode::Fade
+            | CharsetMode::Edges
+            | CharsetMode::Kanji
+            | CharsetMode::Chinese
+            | CharsetMode::Custom(_) => (cols, (cols * aspect).min(rows)),
+            _ => contain(cols, rows, aspect),
+        }
+    }
+
+    /// Shrinks `cols` x `rows` to honour `max_width` and `max_height`. Only
+    /// [`FitMode::Stretch`] clamps each side on its own; the other modes
+    /// scale both to keep the aspect ratio.
+    fn cap(&self, cols: f64, rows: f64) -> (f64, f64) {
+        let max_cols = self.max_width().map_or(f64::INFINITY, f64::from);
+        let max_rows = self.max_height().map_or(f64::INFINITY, f64::from);
+        if self.fit() == FitMode::Stretch {
+            return (cols.min(max_cols), rows.min(max_rows));
+        }
+        let scale = (max_cols / cols).min(max_rows / rows).min(1.0);
+        (cols * scale, rows * scale)
+    }
+}
+
+/// The largest `rows / cols == aspect` box inside `cols` x `rows`.
+fn contain(cols: f64, rows: f64, aspect: f64) -> (f64, f64) {
+    if cols * aspect <= rows {
+        (cols, cols * aspect)
+    } else {
+        (rows / aspect, rows)
+    }
+}
+
+/// The centred region of an `orig_w` x `orig_h` image with `height / width`
+/// equal to `target_aspect`.
+#[expect(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    reason = "The region is clamped to the source dimensions"
+)]
+fn cover_crop(orig_w: u32, orig_h: u32, target_aspect: f64) -> (u32, u32, u32, u32) {
+    let source_aspect = f64::from(orig_h) / f64::from(orig_w);
+    if source_aspect > target_aspect {
+        let h = (f64::from(orig_w) * target_aspect)
+            .round()
+            .clamp(1.0, f64::from(orig_h)) as u32;
+        (0, (orig_h - h) / 2, orig_w, h)
+    } else {
+        let w = (f64::from(orig_h) / target_aspect)
+            .round()
+            .clamp(1.0, f64::from(orig_w)) as u32;
+        ((orig_w - w) / 2, 0, w, orig_h)
     }
 }
 
+/// Rounds a pixel length to a usable image dimension.
+#[expect(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    reason = "The length is clamped to 1..=MAX_SAFE first"
+)]
+fn to_pixels(len: f64) -> u32 {
+    len.round().clamp(1.0, f64::from(MAX_SAFE)) as u32
+}
+
 /// Returns the terminal size in character columns/rows.
 /// Prefers the actual ioctl result, falls back to COLUMNS/LINES env vars,
 /// then a safe 80x24 default.
@@ -5,7 +5,9 @@
 
 use image::{DynamicImage, Rgba, RgbaImage};
 use px2ansi::{
-    CharsetMode, ColorMode, Density, RenderOptions, RenderStylePreset, ResizeFilter,
+    AnsiEncoder, CellGrid, CharsetMode, ColorMode, ColorPalette, CustomCharset, Density,
+    DitherMode, Encoder, FitMode, HtmlEncoder, RenderOptions, RenderStylePreset, ResizeFilter,
+    ResizeSpace, SymbolSet, ToneMap,
     indexer::{ImageEntry, build_index},
 };
 use std::path::Path;
@@ -141,6 +143,30 @@ fn render_centered_produces_output() -> TestResult {
     Ok(())
 }
 
+#[test]
+fn render_sends_a_prepared_image_to_pixel_protocols_unchanged() -> TestResult {
+    let img = make_gradient_image(100, 100);
+    let opts = RenderOptions::builder()
+        .charset(CharsetMode::Kitty)
+        .width(10)
+        .crop(50, 0, 50, 100)
+        .brightness(0.5)
+        .build();
+    let prepared = opts.prepare_image(&img);
+    let mut buf = Vec::new();
+
+    opts.render(&prepared, &mut buf)?;
+
+    let out = String::from_utf8(buf)?;
+    let size = format!("s={},v={},", prepared.width(), prepared.height());
+    assert!(out.contains(&size), "expected {size} in {out:.60}");
+
+    let mut direct = Vec::new();
+    px2ansi::write_ansi_art(&prepared, &mut direct, opts)?;
+    assert_eq!(out.into_bytes(), direct, "render re-prepared the image");
+    Ok(())
+}
+
 #[test]
 fn render_output_contains_ansi_escapes() -> TestResult {
     let img = make_test_image(4, 4, [200, 100, 50, 255]);
@@ -175,6 +201,222 @@ fn render_no_color_has_no_ansi_escapes() -> TestResult {
     Ok(())
 }
 
+#[test]
+fn render_ansi256_palette_dither_alternates_block_colours() -> TestResult {
+    // Grey 113 sits between two xterm grey-ramp entries.
+    let img = make_test_image(8, 2, [113, 113, 113, 255]);
+    let opts = RenderOptions::builder()
+        .width(8)
+        .filter(ResizeFilter::Nearest)
+        .color_mode(ColorMode::Ansi256)
+        .palette_dither(true)
+        .build();
+    let mut buf = Vec::new();
+
+    opts.render(&opts.prepare_image(&img), &mut buf)?;
+
+    let out = String::from_utf8(buf)?;
+    assert!(!out.contains("38;2;"), "256-colour mode emitted truecolor");
+    let fg: std::collections::BTreeSet<&str> = out
+        .split("\x1b[38;5;")
+        .skip(1)
+        .filter_map(|s| s.split('m').next())
+        .collect();
+    assert!(
+        fg.len() > 1,
+        "expected dithered palette entries, got {fg:?}"
+    );
+    Ok(())
+}
+
+#[test]
+fn render_palette_mode_only_emits_palette_colours() -> TestResult {
+    let dir = TempDir::new()?;
+    let path = dir.path().join("duo.gpl");
+    std::fs::write(
+        &path,
+        "GIMP Palette\nName: Duo\n#\n 20  30 120\tNavy\n240 200  40\tGold\n",
+    )?;
+    let palette = ColorPalette::load(&path)?;
+    let img = make_gradient_image(8, 4);
+    let opts = RenderOptions::builder()
+        .width(8)
+        .color_mode(ColorMode::Palette {
+            palette,
+            ansi256: false,
+        })
+        .build();
+    let mut buf = Vec::new();
+
+    opts.render(&opts.prepare_image(&img), &mut buf)?;
+
+    let out = String::from_utf8(buf)?;
+    let colours: std::collections::BTreeSet<&str> = out
+        .split(";2;")
+        .skip(1)
+        .filter_map(|s| s.split('m').next())
+        .collect();
+    assert_eq!(
+        colours,
+        ["20;30;120", "240;200;40"].into_iter().collect(),
+        "{out:?}"
+    );
+    Ok(())
+}
+
+#[test]
+fn render_max_colors_shrinks_truecolor_output() -> TestResult {
+    let img = make_gradient_image(64, 32);
+    let distinct_fg =
+        |max_colors: Option<u16>| -> Result<(usize, usize), Box<dyn std::error::Error>> {
+            let opts = RenderOptions::builder()
+                .width(64)
+                .preset(RenderStylePreset::Quadrant)
+                .color_mode(ColorMode::TrueColor)
+                .build()
+                .with_text_max_colors(max_colors);
+            let mut buf = Vec::new();
+            opts.render(&opts.prepare_image(&img), &mut buf)?;
+            let out = String::from_utf8(buf)?;
+            let colours: std::collections::BTreeSet<&str> = out
+                .split(";2;")
+                .skip(1)
+                .filter_map(|s| s.split('m').next())
+                .collect();
+            Ok((colours.len(), out.len()))
+        };
+
+    let (full_colours, full_len) = distinct_fg(None)?;
+    let (colours, len) = distinct_fg(Some(6))?;
+    assert!(colours <= 6, "{colours} colours");
+    assert!(full_colours > 6, "{full_colours} colours");
+    assert!(len < full_len, "{len} >= {full_len}");
+    Ok(())
+}
+
+#[test]
+fn builder_max_colors_only_sets_the_sixel_palette() {
+    let opts = RenderOptions::builder().max_colors(16).build();
+    assert_eq!(opts.sixel_max_colors(), 16);
+    assert_eq!(opts.text_max_colors(), None);
+
+    let opts = RenderOptions::builder().text_max_colors(8).build();
+    assert_eq!(opts.sixel_max_colors(), 64);
+    assert_eq!(opts.text_max_colors(), Some(8));
+}
+
+#[test]
+fn tone_map_uses_the_full_ramp_despite_an_outlier() -> TestResult {
+    // A dim gradient with a single white pixel.
+    let mut img = RgbaImage::from_fn(64, 8, |x, _| {
+        let v = u8::try_from(x).unwrap_or(0);
+        Rgba([v, v, v, 255])
+    });
+    img.put_pixel(63, 7, Rgba([255, 255, 255, 255]));
+    let img = DynamicImage::ImageRgba8(img);
+
+    let glyphs = |tone_map: ToneMap| -> Result<usize, Box<dyn std::error::Error>> {
+        let opts = RenderOptions::builder()
+            .preset(RenderStylePreset::Ascii)
+            .color_mode(ColorMode::None)
+            .tone_map(tone_map)
+            .width(64)
+            .build();
+        let mut buf = Vec::new();
+        opts.render(&opts.prepare_image(&img), &mut buf)?;
+        let out = String::from_utf8(buf)?;
+        Ok(out
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .chars()
+            .collect::<std::collections::BTreeSet<_>>()
+            .len())
+    };
+
+    let linear = glyphs(ToneMap::Linear)?;
+    for tone_map in [ToneMap::Percentile(1), ToneMap::Equalize] {
+        let mapped = glyphs(tone_map)?;
+        assert!(mapped > linear * 2, "{tone_map:?}: {mapped} vs {linear}");
+    }
+    Ok(())
+}
+
+#[test]
+fn dithering_keeps_the_levels_a_tone_map_spreads() -> TestResult {
+    // Mostly dark with a narrow bright band, which equalizing spreads out.
+    let img = DynamicImage::ImageRgba8(RgbaImage::from_fn(64, 32, |x, _| {
+        let v = u8::try_from(if x < 48 { 20 + x / 4 } else { 152 + x }).unwrap_or(u8::MAX);
+        Rgba([v, v, v, 255])
+    }));
+    let glyphs = |dither: DitherMode| -> Result<usize, Box<dyn std::error::Error>> {
+        let opts = RenderOptions::builder()
+            .preset(RenderStylePreset::Ascii)
+            .color_mode(ColorMode::None)
+            .tone_map(ToneMap::Equalize)
+            .dither(dither)
+            .width(64)
+            .build();
+        let mut buf = Vec::new();
+        opts.render(&opts.prepare_image(&img), &mut buf)?;
+        let out = String::from_utf8(buf)?;
+        Ok(out
+            .chars()
+            .filter(|&c| c != '\n')
+            .collect::<std::collections::BTreeSet<_>>()
+            .len())
+    };
+
+    let plain = glyphs(DitherMode::None)?;
+    let dithered = glyphs(DitherMode::FloydSteinberg)?;
+    assert!(dithered >= plain, "{dithered} vs {plain}");
+    Ok(())
+}
+
+#[test]
+fn linear_resize_keeps_downscaled_sprites_bright() {
+    // A white sprite with a one-pixel-wide stripe pattern on transparency.
+    let img = DynamicImage::ImageRgba8(RgbaImage::from_fn(32, 32, |x, _| {
+        if x % 2 == 0 {
+            Rgba([255, 255, 255, 255])
+        } else {
+            Rgba([0, 0, 0, 0])
+        }
+    }));
+    let brightest = |space: ResizeSpace| {
+        let opts = RenderOptions::builder()
+            .width(8)
+            .filter(ResizeFilter::Triangle)
+            .resize_space(space)
+            .build();
+        let prepared = opts.prepare_image(&img).into_rgba8();
+        prepared.pixels().map(|p| p[0]).max().unwrap_or(0)
+    };
+    assert!(brightest(ResizeSpace::Srgb) < 200);
+    assert!(brightest(ResizeSpace::Linear) > brightest(ResizeSpace::Srgb));
+    assert_eq!(brightest(ResizeSpace::LinearPremultiplied), 255);
+}
+
+#[test]
+fn prepare_image_applies_adjustments_after_resize() {
+    let img = DynamicImage::ImageRgba8(RgbaImage::from_pixel(40, 40, Rgba([200, 50, 10, 255])));
+    for dither in [DitherMode::None, DitherMode::FloydSteinberg] {
+        let opts = RenderOptions::builder()
+            .width(10)
+            .color_mode(ColorMode::TrueColor)
+            .dither(dither)
+            .invert(true)
+            .build();
+        let prepared = opts.prepare_image(&img).into_rgba8();
+        assert_eq!(prepared.width(), 10);
+        assert!(
+            prepared.pixels().all(|p| p.0 == [55, 205, 245, 255]),
+            "{dither:?}: {:?}",
+            prepared.get_pixel(0, 0)
+        );
+    }
+}
+
 #[test]
 fn render_transparent_image_produces_spaces() -> TestResult {
     let img = make_test_image(4, 4, [0, 0, 0, 0]);
@@ -203,6 +445,13 @@ fn all_charset_modes_produce_output() -> TestResult {
         RenderStylePreset::Kanji,
         RenderStylePreset::Chinese,
         RenderStylePreset::FullBlock,
+        RenderStylePreset::Kitty,
+        RenderStylePreset::Iterm2,
+        RenderStylePreset::Quadrant,
+        RenderStylePreset::Sextant,
+        RenderStylePreset::Octant,
+        RenderStylePreset::Hybrid,
+        RenderStylePreset::Edges,
     ];
 
     for preset in presets {
@@ -216,6 +465,183 @@ fn all_charset_modes_produce_output() -> TestResult {
     Ok(())
 }
 
+#[test]
+fn render_quadrant_fits_two_colour_cells() -> TestResult {
+    let img = DynamicImage::ImageRgba8(RgbaImage::from_fn(4, 2, |x, y| {
+        if (x + y) % 2 == 0 {
+            Rgba([255, 255, 255, 255])
+        } else {
+            Rgba([0, 0, 0, 255])
+        }
+    }));
+    let opts = RenderOptions::builder()
+        .preset(RenderStylePreset::Quadrant)
+        .color_mode(ColorMode::TrueColor)
+        .build();
+    let mut buf = Vec::new();
+
+    px2ansi::write_ansi_art(&img, &mut buf, opts)?;
+
+    let output = String::from_utf8(buf)?;
+    assert_eq!(output.matches('▚').count(), 2, "{output:?}");
+    assert!(output.contains("\x1b[38;2;255;255;255m\x1b[48;2;0;0;0m▚"));
+    Ok(())
+}
+
+#[test]
+fn cell_grid_carries_cells_and_encodes_like_write_ansi_art() -> TestResult {
+    /// Writes the glyphs alone, rows separated by `|`.
+    struct Glyphs;
+    impl Encoder for Glyphs {
+        fn encode(&self, grid: &CellGrid, writer: &mut dyn std::io::Write) -> std::io::Result<()> {
+            for row in grid.rows() {
+                for cell in row {
+                    writer.write_all(cell.glyph.as_bytes())?;
+                }
+                writer.write_all(b"|")?;
+            }
+            Ok(())
+        }
+    }
+
+    let img = DynamicImage::ImageRgba8(RgbaImage::from_fn(4, 4, |x, y| {
+        if (x + y) % 2 == 0 {
+            Rgba([255, 255, 255, 255])
+        } else {
+            Rgba([0, 0, 0, 255])
+        }
+    }));
+    let opts = RenderOptions::builder()
+        .preset(RenderStylePreset::Quadrant)
+        .color_mode(ColorMode::Ansi256)
+        .build();
+
+    let grid = px2ansi::render_grid(&img, opts).ok_or("quadrant renders cells")?;
+    assert_eq!((grid.width(), grid.height()), (2, 2));
+    let cell = grid.get(0, 0).ok_or("cell")?;
+    assert_eq!(cell.glyph, "▚");
+    assert_eq!((cell.fg, cell.bg), (Some([255, 255, 255]), Some([0, 0, 0])));
+
+    let mut direct = Vec::new();
+    px2ansi::write_ansi_art(&img, &mut direct, opts)?;
+    let mut encoded = Vec::new();
+    px2ansi::ansi_encoder(&opts).encode(&grid, &mut encoded)?;
+    assert_eq!(direct, encoded);
+    assert!(String::from_utf8(encoded)?.contains("\x1b[38;5;"));
+
+    // The same grid, encoded for another colour depth or a custom format.
+    let mut ansi16 = Vec::new();
+    AnsiEncoder::new(ColorMode::Ansi16(px2ansi::Ansi16Palette::XTERM))
+        .encode(&grid, &mut ansi16)?;
+    assert!(String::from_utf8(ansi16)?.starts_with("\x1b[97m\x1b[40m▚"));
+
+    let mut plain = Vec::new();
+    Glyphs.encode(&grid, &mut plain)?;
+    assert_eq!(String::from_utf8(plain)?, "▚▚|▚▚|");
+
+    let kitty = RenderOptions::builder().charset(CharsetMode::Kitty).build();
+    assert!(px2ansi::render_grid(&img, kitty).is_none());
+    Ok(())
+}
+
+#[test]
+fn write_html_keeps_the_glyphs_of_every_text_mode() -> TestResult {
+    let img = make_gradient_image(8, 8);
+    for preset in [
+        RenderStylePreset::Ansi,
+        RenderStylePreset::Braille,
+        RenderStylePreset::Octant,
+        RenderStylePreset::Ascii,
+        RenderStylePreset::Kanji,
+    ] {
+        let opts = RenderOptions::builder().preset(preset).build();
+        let grid = px2ansi::render_grid(&img, opts).ok_or("text mode renders cells")?;
+        let mut buf = Vec::new();
+        px2ansi::write_html(&img, &mut buf, opts, &HtmlEncoder::default())?;
+        let html = String::from_utf8(buf)?;
+        for cell in grid.rows().iter().flatten() {
+            assert!(
+                html.contains(cell.glyph.as_ref()),
+                "{preset:?}: {:?}",
+                cell.glyph
+            );
+        }
+        assert!(html.starts_with("<pre class=\"px2ansi\""), "{preset:?}");
+        assert_eq!(html.lines().count(), grid.height(), "{preset:?}");
+    }
+
+    let iterm = RenderOptions::builder()
+        .charset(CharsetMode::Iterm2)
+        .build();
+    let mut buf = Vec::new();
+    px2ansi::write_html(&img, &mut buf, iterm, &HtmlEncoder::default())?;
+    assert!(String::from_utf8(buf)?.contains("<img alt=\"\""));
+    Ok(())
+}
+
+#[test]
+fn render_octant_emits_one_row_per_four_pixels() -> TestResult {
+    let img = make_gradient_image(8, 8);
+    let opts = RenderOptions::builder()
+        .preset(RenderStylePreset::Octant)
+        .build();
+    let mut buf = Vec::new();
+
+    px2ansi::write_ansi_art(&img, &mut buf, opts)?;
+
+    let output = String::from_utf8(buf)?;
+    assert_eq!(output.lines().count(), 2);
+    assert!(
+        output.contains("\x1b[48;"),
+        "octant cells carry a background"
+    );
+    Ok(())
+}
+
+#[test]
+fn builder_symbols_override() {
+    let symbols: SymbolSet = "half,shade".parse().expect("valid symbol list");
+    let opts = RenderOptions::builder()
+        .preset(RenderStylePreset::Hybrid)
+        .symbols(symbols)
+        .build();
+    assert_eq!(opts.charset(), CharsetMode::Hybrid);
+    assert_eq!(opts.symbols(), symbols);
+    assert!(symbols.half && symbols.shade && !symbols.quadrant);
+    assert!(matches!(
+        "half,bogus".parse::<SymbolSet>(),
+        Err(e) if e.contains("bogus")
+    ));
+}
+
+#[test]
+fn render_hybrid_limited_to_half_blocks() -> TestResult {
+    let img = make_gradient_image(8, 8);
+    let opts = RenderOptions::builder()
+        .preset(RenderStylePreset::Hybrid)
+        .symbols(SymbolSet {
+            half: true,
+            ..SymbolSet::NONE
+        })
+        .color_mode(ColorMode::TrueColor)
+        .build();
+    let mut buf = Vec::new();
+
+    px2ansi::write_ansi_art(&img, &mut buf, opts)?;
+
+    let output = String::from_utf8(buf)?;
+    let glyphs: String = output
+        .split('m')
+        .filter_map(|s| s.chars().next())
+        .filter(|c| !c.is_ascii_digit() && *c != '\x1b' && *c != '\n')
+        .collect();
+    assert!(
+        glyphs.chars().all(|c| [' ', '▀', '▄'].contains(&c)),
+        "{glyphs:?}"
+    );
+    Ok(())
+}
+
 #[test]
 fn render_writer_trait_works_with_cursor() -> TestResult {
     let img = make_test_image(4, 4, [100, 200, 50, 255]);
@@ -236,6 +662,237 @@ fn prepare_image_respects_width_override() {
     assert_eq!(prepared.width(), 40);
 }
 
+#[test]
+fn width_and_height_are_measured_in_cells() {
+    // A square image is half as many rows as columns on 2:1 cells.
+    let img = make_test_image(100, 100, [255, 255, 255, 255]);
+    let cases = [
+        (CharsetMode::Ansi, (40, 40)),
+        (CharsetMode::Ascii, (40, 20)),
+        (CharsetMode::Braille, (80, 80)),
+        (CharsetMode::Sextant, (80, 60)),
+    ];
+    for (charset, size) in cases {
+        let opts = RenderOptions::builder().charset(charset).width(40).build();
+        assert_eq!(opts.calculate_dimensions(100, 100), size, "{charset:?}");
+    }
+    let opts = RenderOptions::builder().height(10).build();
+    let prepared = opts.prepare_image(&img);
+    assert_eq!((prepared.width(), prepared.height()), (20, 20));
+}
+
+#[test]
+fn fit_modes_size_into_the_cell_box() {
+    // 200x100 source into a 40x40 cell box of half blocks (40x80 pixels).
+    let size = |fit| {
+        RenderOptions::builder()
+            .width(40)
+            .height(40)
+            .fit(fit)
+            .build()
+            .calculate_dimensions(200, 100)
+    };
+    assert_eq!(size(FitMode::Contain), (40, 20));
+    assert_eq!(size(FitMode::Cover), (40, 80));
+    assert_eq!(size(FitMode::Stretch), (40, 80));
+    assert_eq!(size(FitMode::Native), (200, 100));
+}
+
+#[test]
+fn cover_crops_the_overflow_from_the_centre() {
+    // Red, green and blue thirds; a square box keeps only the green middle.
+    let img = DynamicImage::ImageRgba8(RgbaImage::from_fn(30, 10, |x, _| match x / 10 {
+        0 => Rgba([255, 0, 0, 255]),
+        1 => Rgba([0, 255, 0, 255]),
+        _ => Rgba([0, 0, 255, 255]),
+    }));
+    let opts = RenderOptions::builder()
+        .width(4)
+        .height(2)
+        .fit(FitMode::Cover)
+        .build();
+    let prepared = opts.prepare_image(&img).to_rgba8();
+    assert_eq!(prepared.dimensions(), (4, 4));
+    assert!(prepared.pixels().all(|p| p.0 == [0, 255, 0, 255]));
+}
+
+#[test]
+fn cell_aspect_changes_rows_per_column() {
+    let rows = |aspect| {
+        RenderOptions::builder()
+            .charset(CharsetMode::Ascii)
+            .width(40)
+            .cell_aspect(aspect)
+            .build()
+            .calculate_dimensions(100, 100)
+            .1
+    };
+    assert_eq!(rows(2.0), 20);
+    assert_eq!(rows(2.5), 16);
+    assert_eq!(rows(1.0), 40);
+    // Nonsense ratios keep the default.
+    assert_eq!(rows(0.0), 20);
+    assert_eq!(rows(f32::NAN), 20);
+
+    // Bitmap protocols size their own cells.
+    let sixel = RenderOptions::builder()
+        .charset(CharsetMode::Kitty)
+        .width(10)
+        .cell_aspect(3.0)
+        .build();
+    assert_eq!(sixel.calculate_dimensions(100, 100), (80, 80));
+}
+
+#[test]
+fn auto_trim_spends_the_width_on_the_sprite() {
+    // A 10x10 red sprite in the middle of 50x50 transparent padding.
+    let img = DynamicImage::ImageRgba8(RgbaImage::from_fn(50, 50, |x, y| {
+        if (20..30).contains(&x) && (20..30).contains(&y) {
+            Rgba([255, 0, 0, 255])
+        } else {
+            Rgba([0, 0, 0, 0])
+        }
+    }));
+    let opts = RenderOptions::builder()
+        .width(10)
+        .filter(ResizeFilter::Nearest)
+        .auto_trim(true)
+        .build();
+    assert_eq!(opts.crop_image(&img).width(), 10);
+    let prepared = opts.prepare_image(&img).to_rgba8();
+    assert_eq!(prepared.dimensions(), (10, 10));
+    assert!(prepared.pixels().all(|p| p.0 == [255, 0, 0, 255]));
+}
+
+#[test]
+fn crop_selects_a_source_region() {
+    let img = make_test_image(100, 50, [0, 0, 255, 255]);
+    let opts = RenderOptions::builder()
+        .width(20)
+        .crop(60, 10, 80, 20)
+        .build();
+    // Clamped to the 40x20 that lies inside the image.
+    assert_eq!(opts.crop_image(&img).width(), 40);
+    assert_eq!(opts.calculate_dimensions(100, 50), (20, 10));
+    let prepared = opts.prepare_image(&img);
+    assert_eq!((prepared.width(), prepared.height()), (20, 10));
+
+    let outside = opts.with_crop(Some((200, 0, 10, 10)));
+    assert_eq!(outside.calculate_dimensions(100, 50), (20, 10));
+}
+
+#[test]
+fn pixel_art_filter_recovers_the_native_grid() {
+    // A 4x4 checkerboard upscaled 9x: Nearest to 10 columns drops and
+    // doubles pixels, PixelArt scales the 4x4 grid by 2.
+    let native = RgbaImage::from_fn(4, 4, |x, y| {
+        if (x + y) % 2 == 0 {
+            Rgba([255, 255, 255, 255])
+        } else {
+            Rgba([0, 0, 0, 255])
+        }
+    });
+    let img = DynamicImage::ImageRgba8(native.clone()).resize_exact(
+        36,
+        36,
+        image::imageops::FilterType::Nearest,
+    );
+    let opts = RenderOptions::builder()
+        .width(10)
+        .filter(ResizeFilter::PixelArt)
+        .build();
+    assert_eq!(opts.resize_filter(), ResizeFilter::PixelArt);
+    assert_eq!(opts.filter(), image::imageops::FilterType::Nearest);
+    let prepared = opts.prepare_image(&img).to_rgba8();
+    assert_eq!(prepared.dimensions(), (8, 8));
+    for (x, y, p) in prepared.enumerate_pixels() {
+        assert_eq!(p, native.get_pixel(x / 2, y / 2));
+    }
+}
+
+#[test]
+fn braille_upscales_tiny_sources_with_scale2x() {
+    // A 16x16 staircase at 16 Braille columns is an exact 2x upscale, so
+    // Nearest gives way to Scale2x and the steps get filled in.
+    let black = Rgba([0, 0, 0, 255]);
+    let white = Rgba([255, 255, 255, 255]);
+    let img = DynamicImage::ImageRgba8(RgbaImage::from_fn(16, 16, |x, y| {
+        if x <= y { black } else { white }
+    }));
+    let braille = RenderOptions::builder()
+        .width(16)
+        .charset(CharsetMode::Braille)
+        .build()
+        .prepare_image(&img)
+        .to_rgba8();
+    assert_eq!(braille.dimensions(), (32, 32));
+    assert_eq!(*braille.get_pixel(2, 1), black);
+
+    // Quadrant cells ask for twice the width: Scale2x doubles both axes and
+    // nearest stretches the width.
+    let quadrant = RenderOptions::builder()
+        .width(32)
+        .charset(CharsetMode::Quadrant)
+        .build()
+        .prepare_image(&img)
+        .to_rgba8();
+    assert_eq!(quadrant.dimensions(), (64, 32));
+    assert_eq!(*quadrant.get_pixel(4, 1), black);
+
+    // Pixel-art runs the passes on the native grid of an upscaled sprite.
+    let blocky = img.resize_exact(64, 64, image::imageops::FilterType::Nearest);
+    let pixel_art = RenderOptions::builder()
+        .width(16)
+        .charset(CharsetMode::Braille)
+        .filter(ResizeFilter::PixelArt)
+        .build()
+        .prepare_image(&blocky)
+        .to_rgba8();
+    assert_eq!(pixel_art.dimensions(), (32, 32));
+    assert_eq!(*pixel_art.get_pixel(2, 1), black);
+
+    // Other styles keep plain nearest-neighbour blocks.
+    let unicode = RenderOptions::builder()
+        .width(32)
+        .charset(CharsetMode::Unicode)
+        .build()
+        .prepare_image(&img)
+        .to_rgba8();
+    assert_eq!(unicode.dimensions(), (32, 32));
+    assert_eq!(*unicode.get_pixel(2, 1), white);
+
+    // Asking for Scale2x by name applies it to any style.
+    let scaled = RenderOptions::builder()
+        .width(32)
+        .charset(CharsetMode::Unicode)
+        .filter(ResizeFilter::Scale2x)
+        .build()
+        .prepare_image(&img)
+        .to_rgba8();
+    assert_eq!(*scaled.get_pixel(2, 1), black);
+}
+
+#[test]
+fn max_dimensions_cap_every_fit_mode() {
+    let opts = RenderOptions::builder()
+        .fit(FitMode::Native)
+        .max_width(50)
+        .build();
+    assert_eq!(opts.calculate_dimensions(200, 100), (50, 25));
+
+    let opts = RenderOptions::builder().width(80).max_height(10).build();
+    assert_eq!(opts.calculate_dimensions(100, 100), (20, 20));
+
+    let opts = RenderOptions::builder()
+        .width(80)
+        .height(30)
+        .fit(FitMode::Stretch)
+        .max_width(60)
+        .max_height(20)
+        .build();
+    assert_eq!(opts.calculate_dimensions(100, 100), (60, 40));
+}
+
 // --- Indexer ---
 
 #[test]
@@ -359,12 +1016,27 @@ fn build_index_empty_directory_produces_empty_array() -> TestResult {
     Ok(())
 }
 
+#[cfg(not(feature = "rasterize"))]
+#[test]
+fn shape_without_rasterize_is_unsupported() {
+    let img = make_gradient_image(8, 8);
+    let opts = RenderOptions::builder()
+        .preset(RenderStylePreset::Shape)
+        .build();
+    let mut buf = Vec::new();
+
+    let err = px2ansi::write_ansi_art(&img, &mut buf, opts).expect_err("needs rasterize");
+
+    assert_eq!(err.kind(), std::io::ErrorKind::Unsupported);
+    assert!(buf.is_empty());
+}
+
 // --- Rasterization ---
 
 #[cfg(feature = "rasterize")]
 mod rasterize_tests {
     use super::*;
-    use px2ansi::{RasterTheme, rasterize_ansi_with_theme};
+    use px2ansi::{RasterTheme, SvgEncoder, rasterize_ansi_with_theme, write_svg};
 
     // Re-using the same type alias for consistency
     type TestResult = Result<(), Box<dyn std::error::Error>>;
@@ -412,4 +1084,123 @@ mod rasterize_tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn render_shape_draws_diagonal_lines_as_slashes() -> TestResult {
+        // One 4x8 cell with a rising line, one with a falling line.
+        let img = DynamicImage::ImageRgba8(RgbaImage::from_fn(8, 8, |x, y| {
+            let on_line = if x < 4 {
+                x == (7 - y) / 2
+            } else {
+                x - 4 == y / 2
+            };
+            if on_line {
+                Rgba([255, 255, 255, 255])
+            } else {
+                Rgba([0, 0, 0, 255])
+            }
+        }));
+        let opts = RenderOptions::builder()
+            .preset(RenderStylePreset::Shape)
+            .color_mode(ColorMode::None)
+            .width(8)
+            .build();
+        let mut buf = Vec::new();
+
+        px2ansi::write_ansi_art(&img, &mut buf, opts)?;
+
+        let output = String::from_utf8(buf)?;
+        assert_eq!(output, "/\\\n");
+        Ok(())
+    }
+
+    #[test]
+    fn write_svg_draws_blocks_as_rects_and_text_as_text() -> TestResult {
+        let img = make_gradient_image(8, 8);
+        let svg = |preset, encoder: &SvgEncoder| -> Result<String, Box<dyn std::error::Error>> {
+            let opts = RenderOptions::builder().preset(preset).build();
+            let mut buf = Vec::new();
+            write_svg(&img, &mut buf, opts, encoder)?;
+            Ok(String::from_utf8(buf)?)
+        };
+
+        let blocks = svg(RenderStylePreset::Ansi, &SvgEncoder::default())?;
+        assert!(blocks.starts_with("<svg"));
+        assert!(blocks.contains("<rect"));
+        assert!(!blocks.contains("<text"));
+
+        let ascii = svg(RenderStylePreset::Ascii, &SvgEncoder::default())?;
+        assert!(ascii.contains("<text"));
+
+        let merged = svg(
+            RenderStylePreset::Ansi,
+            &SvgEncoder::default().with_merge(true),
+        )?;
+        assert!(merged.contains("<path"));
+        assert!(merged.len() < blocks.len());
+        Ok(())
+    }
+}
+
+#[test]
+fn render_custom_charset_uses_only_ramp_glyphs() -> TestResult {
+    let img = make_gradient_image(16, 8);
+    let charset = CustomCharset::from_ramp(" .:#", false)?;
+    let opts = RenderOptions::builder()
+        .charset(CharsetMode::Custom(charset))
+        .color_mode(ColorMode::None)
+        .width(16)
+        .build();
+    let mut buf = Vec::new();
+
+    px2ansi::write_ansi_art(&img, &mut buf, opts)?;
+
+    let output = String::from_utf8(buf)?;
+    assert!(output.contains('#'), "{output:?}");
+    assert!(output.chars().all(|c| " .:#\n".contains(c)), "{output:?}");
+    Ok(())
+}
+
+#[test]
+fn custom_charset_rejects_empty_ramp() {
+    assert!(matches!(
+        CustomCharset::from_ramp("", false),
+        Err(px2ansi::RenderError::InvalidCharset(_))
+    ));
+    let wide = CustomCharset::from_ramp("口回", true).expect("non-empty ramp");
+    assert!(wide.is_wide());
+    assert_eq!(wide.glyphs(), ["口", "回"]);
+}
+
+#[test]
+fn custom_charset_reuses_a_ramp_parsed_twice() -> TestResult {
+    let first = CustomCharset::from_ramp(" ░▒▓█", false)?;
+    let again = CustomCharset::from_ramp(" ░▒▓█", true)?;
+    assert!(std::ptr::eq(first.glyphs(), again.glyphs()));
+    assert_eq!(first.glyphs(), [" ", "░", "▒", "▓", "█"]);
+    Ok(())
+}
+
+#[test]
+fn render_edges_outlines_a_square() -> TestResult {
+    let img = DynamicImage::ImageRgba8(RgbaImage::from_fn(16, 16, |x, y| {
+        if (4..12).contains(&x) && (4..12).contains(&y) {
+            Rgba([255, 255, 255, 255])
+        } else {
+            Rgba([0, 0, 0, 255])
+        }
+    }));
+    let opts = RenderOptions::builder()
+        .preset(RenderStylePreset::Edges)
+        .color_mode(ColorMode::None)
+        .width(16)
+        .build();
+    let mut buf = Vec::new();
+
+    px2ansi::write_ansi_art(&img, &mut buf, opts)?;
+
+    let output = String::from_utf8(buf)?;
+    assert!(output.contains('│') && output.contains('─'), "{output}");
+    assert!(output.chars().all(|c| " │─/\\\n".contains(c)), "{output}");
+    Ok(())
 }